@@ -60,9 +60,10 @@ export function useAuctionProgram() {
         );
     }, []);
 
-    const getBidPda = useCallback((auctionPda: PublicKey, bidder: PublicKey) => {
+    // Seeded with the auction's creation slot, so a re-created auction gets fresh bids.
+    const getBidPda = useCallback((auctionPda: PublicKey, creationSlot: BN, bidder: PublicKey) => {
         return PublicKey.findProgramAddressSync(
-            [Buffer.from(BID_SEED), auctionPda.toBuffer(), bidder.toBuffer()],
+            [Buffer.from(BID_SEED), auctionPda.toBuffer(), creationSlot.toArrayLike(Buffer, 'le', 8), bidder.toBuffer()],
             PROGRAM_ID
         );
     }, []);
@@ -145,7 +146,8 @@ export function useAuctionProgram() {
     const initializeBid = useCallback(async (auctionPda: PublicKey) => {
         if (!program || !wallet) throw new Error('Wallet not connected');
         const bidder = wallet.publicKey;
-        const [bidPda] = getBidPda(auctionPda, bidder);
+        const { creationSlot } = await program.account.auction.fetch(auctionPda);
+        const [bidPda] = getBidPda(auctionPda, creationSlot, bidder);
         const bidPermissionPda = permissionPdaFromAccount(bidPda);
 
         // 1. Initialize Bid on L1
@@ -183,7 +185,7 @@ export function useAuctionProgram() {
 
         // 4. Delegate Bid PDA to ER
         const delegateBidIx = await program.methods
-            .delegateBid(auctionPda, bidder)
+            .delegateBid(auctionPda, creationSlot, bidder)
             .accounts({
                 payer: bidder,
                 bid: bidPda,
@@ -204,7 +206,8 @@ export function useAuctionProgram() {
     const submitSealedBid = useCallback(async (auctionPda: PublicKey, amount: BN) => {
         if (!program || !wallet) throw new Error('Wallet not connected');
         const bidder = wallet.publicKey;
-        const [bidPda] = getBidPda(auctionPda, bidder);
+        const { creationSlot } = await program.account.auction.fetch(auctionPda);
+        const [bidPda] = getBidPda(auctionPda, creationSlot, bidder);
 
         const nonce = new Uint8Array(32);
         crypto.getRandomValues(nonce);
@@ -240,7 +243,8 @@ export function useAuctionProgram() {
     const revealBid = useCallback(async (auctionPda: PublicKey) => {
         if (!program || !wallet) throw new Error('Wallet not connected');
         const bidder = wallet.publicKey;
-        const [bidPda] = getBidPda(auctionPda, bidder);
+        const { creationSlot } = await program.account.auction.fetch(auctionPda);
+        const [bidPda] = getBidPda(auctionPda, creationSlot, bidder);
 
         const bidKey = `bid_${auctionPda.toBase58()}_${bidder.toBase58()}`;
         const stored = localStorage.getItem(bidKey);
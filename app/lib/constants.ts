@@ -13,4 +13,5 @@ export const VALIDATOR_PUBKEY = new PublicKey("FnE6VJT5QNZdedZPnCoLsARgBwoE6DeJN
 
 // Seeds
 export const AUCTION_SEED = "auction";
+// Bid PDA: [BID_SEED, auction, auction.creationSlot (u64 LE), bidder]
 export const BID_SEED = "bid";
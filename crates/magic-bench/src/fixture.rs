@@ -0,0 +1,71 @@
+//! The Groth16 range proof in `programs/magic/fixtures/range_proof.json`, which
+//! `crates/magic-client/examples/range_proof_fixture.rs` generates for fixed auction and
+//! bidder keys.
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Context, Result};
+use magic_client::{RangeProof, RangeProofVerifyingKey};
+use serde_json::Value;
+
+const FIXTURE: &str = include_str!("../../../programs/magic/fixtures/range_proof.json");
+
+pub struct RangeProofFixture {
+    pub vk: RangeProofVerifyingKey,
+    pub proof: RangeProof,
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub creation_slot: u64,
+    pub reserve_price: u64,
+    pub deposit_amount: u64,
+    pub amount: u64,
+    pub nonce: [u8; 32],
+    pub bid_hash: [u8; 32],
+}
+
+impl RangeProofFixture {
+    pub fn load() -> Result<Self> {
+        let fixture: Value = serde_json::from_str(FIXTURE)?;
+        let (vk, proof) = (&fixture["vk"], &fixture["proof"]);
+        let ic = vk["ic"]
+            .as_array()
+            .context("vk.ic is not an array")?
+            .iter()
+            .map(bytes)
+            .collect::<Result<Vec<[u8; 64]>>>()?;
+        Ok(Self {
+            vk: RangeProofVerifyingKey {
+                alpha_g1: bytes(&vk["alpha_g1"])?,
+                beta_g2: bytes(&vk["beta_g2"])?,
+                gamma_g2: bytes(&vk["gamma_g2"])?,
+                delta_g2: bytes(&vk["delta_g2"])?,
+                ic: ic
+                    .try_into()
+                    .map_err(|_| anyhow!("vk.ic has the wrong length"))?,
+            },
+            proof: RangeProof {
+                a: bytes(&proof["a"])?,
+                b: bytes(&proof["b"])?,
+                c: bytes(&proof["c"])?,
+            },
+            auction: Pubkey::new_from_array(bytes(&fixture["auction"])?),
+            bidder: Pubkey::new_from_array(bytes(&fixture["bidder"])?),
+            creation_slot: number(&fixture["creation_slot"])?,
+            reserve_price: number(&fixture["reserve_price"])?,
+            deposit_amount: number(&fixture["deposit_amount"])?,
+            amount: number(&fixture["amount"])?,
+            nonce: bytes(&fixture["nonce"])?,
+            bid_hash: bytes(&fixture["bid_hash"])?,
+        })
+    }
+}
+
+fn bytes<const N: usize>(value: &Value) -> Result<[u8; N]> {
+    let hex = value.as_str().context("expected a hex string")?;
+    hex::decode(hex)?
+        .try_into()
+        .map_err(|_| anyhow!("expected {N} bytes, got {hex}"))
+}
+
+fn number(value: &Value) -> Result<u64> {
+    value.as_u64().context("expected a number")
+}
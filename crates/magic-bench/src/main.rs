@@ -0,0 +1,90 @@
+//! `magic-bench`: measures the compute units of every instruction a local validator can run,
+//! and of the delegation, permission, crank, range proof and migration paths in LiteSVM,
+//! writes a report and fails when a measurement regresses past the baseline's threshold or
+//! is missing from either side of the baseline.
+
+mod fixture;
+mod report;
+mod scenarios;
+mod svm;
+
+use std::{path::PathBuf, process::ExitCode};
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use magic_client::rpc::RpcClient;
+
+use crate::{
+    report::{compare, Baseline},
+    scenarios::Bench,
+    svm::SvmBench,
+};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Validator with the program deployed, e.g. `solana-test-validator` after `anchor deploy`.
+    #[arg(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Funded wallet that creates the auctions; defaults to the Solana CLI keypair.
+    #[arg(long)]
+    keypair: Option<PathBuf>,
+    #[arg(long, default_value = "crates/magic-bench/baseline.toml")]
+    baseline: PathBuf,
+    #[arg(long, default_value = "target/cu-report.json")]
+    report: PathBuf,
+    /// Accept this run's measurements as the new baseline.
+    #[arg(long)]
+    update_baseline: bool,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    let keypair = cli
+        .keypair
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/solana/id.json"))
+        })
+        .ok_or_else(|| anyhow!("no --keypair given and HOME is not set"))?;
+    let payer = solana_keypair::read_keypair_file(&keypair)
+        .map_err(|err| anyhow!("cannot read keypair {}: {err}", keypair.display()))?;
+    let baseline = Baseline::load(&cli.baseline)?;
+
+    let rpc = RpcClient::new(cli.url);
+    let mut report = Bench::new(&rpc, &payer).run()?;
+    report.measurements.extend(SvmBench::new()?.run()?);
+    report.save(&cli.report)?;
+
+    let comparison = compare(&baseline, &report);
+    println!("\n{}", comparison.render());
+    for (name, reason) in &report.not_measured {
+        println!("not measured: {name} ({reason})");
+    }
+    if cli.update_baseline {
+        baseline.updated(&report).save(&cli.baseline)?;
+        println!("baseline updated: {}", cli.baseline.display());
+        return Ok(ExitCode::SUCCESS);
+    }
+    if comparison.passed() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    let regressions = comparison.regressions().count();
+    if regressions > 0 {
+        eprintln!(
+            "{regressions} measurement(s) regressed by more than {}%",
+            baseline.threshold_percent
+        );
+    }
+    let unbaselined = comparison.unbaselined().count();
+    if unbaselined > 0 {
+        eprintln!("{unbaselined} measurement(s) have no baseline entry");
+    }
+    if !comparison.missing.is_empty() {
+        eprintln!(
+            "baseline entries not measured: {}",
+            comparison.missing.join(", ")
+        );
+    }
+    eprintln!("accept intended changes with --update-baseline");
+    Ok(ExitCode::FAILURE)
+}
@@ -0,0 +1,284 @@
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Compute units of one transaction, keyed `instruction` or `instruction/case`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub name: String,
+    pub units: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub measurements: Vec<Measurement>,
+    /// Instructions the run could not exercise, with the reason.
+    pub not_measured: BTreeMap<String, String>,
+}
+
+impl Report {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").with_context(|| format!("cannot write {}", path.display()))
+    }
+}
+
+/// Accepted compute units per measurement. A measurement regresses when it exceeds its
+/// baseline by more than `threshold_percent`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    #[serde(default = "default_threshold")]
+    pub threshold_percent: f64,
+    #[serde(default)]
+    pub units: BTreeMap<String, u64>,
+}
+
+fn default_threshold() -> f64 {
+    5.0
+}
+
+impl Default for Baseline {
+    fn default() -> Self {
+        Self {
+            threshold_percent: default_threshold(),
+            units: BTreeMap::new(),
+        }
+    }
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("invalid baseline {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    /// This baseline with every entry replaced by `report`'s measurements.
+    pub fn updated(&self, report: &Report) -> Self {
+        Self {
+            threshold_percent: self.threshold_percent,
+            units: report
+                .measurements
+                .iter()
+                .map(|measurement| (measurement.name.clone(), measurement.units))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Unchanged,
+    Improved,
+    Regressed,
+    /// Not in the baseline yet.
+    New,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Row {
+    pub name: String,
+    pub baseline: Option<u64>,
+    pub units: u64,
+    pub status: Status,
+}
+
+#[derive(Debug)]
+pub struct Comparison {
+    pub rows: Vec<Row>,
+    /// Baseline entries the report no longer measures.
+    pub missing: Vec<String>,
+}
+
+impl Comparison {
+    pub fn regressions(&self) -> impl Iterator<Item = &Row> {
+        self.rows
+            .iter()
+            .filter(|row| row.status == Status::Regressed)
+    }
+
+    /// Measurements with no baseline entry; accept them with `--update-baseline`.
+    pub fn unbaselined(&self) -> impl Iterator<Item = &Row> {
+        self.rows.iter().filter(|row| row.status == Status::New)
+    }
+
+    /// Whether the run matches the baseline: nothing regressed, new or missing.
+    pub fn passed(&self) -> bool {
+        self.regressions().next().is_none()
+            && self.unbaselined().next().is_none()
+            && self.missing.is_empty()
+    }
+
+    /// Markdown table of the comparison.
+    pub fn render(&self) -> String {
+        let mut out =
+            String::from("| measurement | baseline | units | change |\n|---|---:|---:|---|\n");
+        for row in &self.rows {
+            let baseline = row
+                .baseline
+                .map_or("-".to_string(), |units| units.to_string());
+            let change = match (row.status, row.baseline) {
+                (Status::New, _) | (_, None) => "new".to_string(),
+                (status, Some(baseline)) => {
+                    let delta = row.units as f64 / baseline.max(1) as f64 * 100.0 - 100.0;
+                    let flag = if status == Status::Regressed {
+                        " REGRESSED"
+                    } else {
+                        ""
+                    };
+                    format!("{delta:+.1}%{flag}")
+                }
+            };
+            writeln!(
+                out,
+                "| {} | {baseline} | {} | {change} |",
+                row.name, row.units
+            )
+            .unwrap();
+        }
+        for name in &self.missing {
+            writeln!(out, "| {name} | - | - | not measured |").unwrap();
+        }
+        out
+    }
+}
+
+pub fn compare(baseline: &Baseline, report: &Report) -> Comparison {
+    let limit = |units: u64| units as f64 * (1.0 + baseline.threshold_percent / 100.0);
+    let rows = report
+        .measurements
+        .iter()
+        .map(|measurement| {
+            let accepted = baseline.units.get(&measurement.name).copied();
+            let status = match accepted {
+                None => Status::New,
+                Some(accepted) if measurement.units as f64 > limit(accepted) => Status::Regressed,
+                Some(accepted) if measurement.units < accepted => Status::Improved,
+                Some(_) => Status::Unchanged,
+            };
+            Row {
+                name: measurement.name.clone(),
+                baseline: accepted,
+                units: measurement.units,
+                status,
+            }
+        })
+        .collect();
+    let missing = baseline
+        .units
+        .keys()
+        .filter(|name| !report.measurements.iter().any(|m| &m.name == *name))
+        .cloned()
+        .collect();
+    Comparison { rows, missing }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(measurements: &[(&str, u64)]) -> Report {
+        Report {
+            measurements: measurements
+                .iter()
+                .map(|(name, units)| Measurement {
+                    name: name.to_string(),
+                    units: *units,
+                })
+                .collect(),
+            not_measured: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn regressions_are_measured_against_the_threshold() {
+        let baseline: Baseline = toml::from_str(
+            "threshold_percent = 10.0\n[units]\n\"reveal_bid/sha256\" = 10000\nfinalize_auction = 4000\nsettle_auction = 6000\n",
+        )
+        .unwrap();
+        let comparison = compare(
+            &baseline,
+            &report(&[
+                ("reveal_bid/sha256", 11_000),
+                ("finalize_auction", 4_401),
+                ("create_auction", 12_000),
+            ]),
+        );
+
+        let statuses: Vec<_> = comparison.rows.iter().map(|row| row.status).collect();
+        assert_eq!(
+            statuses,
+            [Status::Unchanged, Status::Regressed, Status::New]
+        );
+        assert_eq!(comparison.missing, ["settle_auction"]);
+        let regressed: Vec<_> = comparison.regressions().map(|row| &row.name).collect();
+        assert_eq!(regressed, ["finalize_auction"]);
+        let new: Vec<_> = comparison.unbaselined().map(|row| &row.name).collect();
+        assert_eq!(new, ["create_auction"]);
+        assert!(!comparison.passed());
+        assert!(comparison
+            .render()
+            .contains("| finalize_auction | 4000 | 4401 | +10.0% REGRESSED |"));
+    }
+
+    #[test]
+    fn updating_keeps_the_threshold_and_replaces_the_units() {
+        let baseline = Baseline {
+            threshold_percent: 2.5,
+            units: BTreeMap::from([("withdraw_deposit".to_string(), 5_000)]),
+        };
+        let updated = baseline.updated(&report(&[("finalize_auction", 3_900)]));
+        assert_eq!(updated.threshold_percent, 2.5);
+        assert_eq!(
+            updated.units,
+            BTreeMap::from([("finalize_auction".to_string(), 3_900)])
+        );
+
+        let round_trip: Baseline = toml::from_str(&toml::to_string(&updated).unwrap()).unwrap();
+        assert_eq!(round_trip, updated);
+        let empty = compare(
+            &Baseline::default(),
+            &report(&[("finalize_auction", 3_900)]),
+        );
+        assert_eq!(empty.rows[0].status, Status::New);
+        assert_eq!(empty.regressions().count(), 0);
+        assert!(!empty.passed());
+    }
+
+    #[test]
+    fn new_or_missing_measurements_fail_the_run() {
+        let baseline = Baseline {
+            threshold_percent: 5.0,
+            units: BTreeMap::from([
+                ("finalize_auction".to_string(), 4_000),
+                ("settle_auction".to_string(), 6_000),
+            ]),
+        };
+        assert!(compare(
+            &baseline,
+            &report(&[("finalize_auction", 4_100), ("settle_auction", 5_900)])
+        )
+        .passed());
+
+        let missing = compare(&baseline, &report(&[("finalize_auction", 4_000)]));
+        assert_eq!(missing.missing, ["settle_auction"]);
+        assert!(!missing.passed());
+
+        let new = compare(
+            &baseline,
+            &report(&[
+                ("finalize_auction", 4_000),
+                ("settle_auction", 6_000),
+                ("withdraw_deposit", 3_000),
+            ]),
+        );
+        assert!(!new.passed());
+    }
+}
@@ -0,0 +1,475 @@
+//! Drives every L1-reachable instruction through an auction lifecycle on a local validator,
+//! recording the compute units `simulateTransaction` reports before sending each one.
+
+use std::{collections::BTreeMap, thread, time::Duration};
+
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{instruction::Instruction, system_instruction},
+};
+use anyhow::{bail, Context, Result};
+use magic_client::{
+    account::{decode_auction, Auction},
+    commitment::{
+        bid_proof, derive_bid_nonce, nonce_derivation_message, seal_bid, signed_bid_message,
+        sum_blindings,
+    },
+    instruction,
+    pda::auction_pda,
+    rpc::RpcClient,
+    AuctionOptions, BidMode, BidProof, BidReveal, CommitmentHash, CommitmentScheme,
+};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::{
+    fixture::RangeProofFixture,
+    report::{Measurement, Report},
+};
+
+/// Bids revealed together by `reveal_bids_batch`.
+pub const BATCH_SIZE: usize = 6;
+/// Seconds of bidding and of revealing; long enough to land every transaction in a phase.
+const BIDDING_SECS: i64 = 40;
+const REVEALING_SECS: i64 = 40;
+const BIDDER_FUNDS: u64 = 50_000_000;
+const DEPOSIT: u64 = 1_000_000;
+const CLOCK_POLL: Duration = Duration::from_secs(1);
+
+/// Instructions that run on an Ephemeral Rollup against delegated accounts, which
+/// neither a plain local validator nor the LiteSVM stubs provide.
+const NOT_MEASURED: &[(&str, &str)] = &[
+    ("submit_sealed_bid_delegated", "runs on the ER"),
+    ("resolve_private_auction", "runs on the ER"),
+    ("submit_encrypted_bid", "needs an encryption keypair and ER"),
+    ("decrypt_and_reveal", "needs an encryption keypair and ER"),
+    ("finalize_and_settle", "runs on the ER"),
+    ("undelegate_bids", "runs on the ER"),
+];
+
+pub struct Bench<'a> {
+    rpc: &'a RpcClient,
+    payer: &'a Keypair,
+    measurements: Vec<Measurement>,
+}
+
+/// A bidder's key and the bid it committed.
+struct Bidder {
+    keypair: Keypair,
+    amount: u64,
+    nonce: [u8; 32],
+}
+
+impl<'a> Bench<'a> {
+    pub fn new(rpc: &'a RpcClient, payer: &'a Keypair) -> Self {
+        Self {
+            rpc,
+            payer,
+            measurements: Vec::new(),
+        }
+    }
+
+    fn transaction(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Transaction> {
+        let mut all = vec![self.payer];
+        all.extend(signers);
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all,
+            blockhash,
+        ))
+    }
+
+    /// Lands `instructions` without measuring them.
+    fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+        let transaction = self.transaction(instructions, signers)?;
+        self.rpc.send_and_confirm(&transaction)?;
+        Ok(())
+    }
+
+    /// Simulates `instructions` for their compute units, then lands them.
+    fn measure(
+        &mut self,
+        name: &str,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<()> {
+        let transaction = self.transaction(instructions, signers)?;
+        let simulation = self.rpc.simulate(&transaction)?;
+        if let Some(err) = simulation.err {
+            bail!(
+                "{name} failed in simulation: {err}\n{}",
+                simulation.logs.join("\n")
+            );
+        }
+        self.rpc
+            .send_and_confirm(&transaction)
+            .with_context(|| format!("{name} failed"))?;
+        println!("{name}: {} CU", simulation.units_consumed);
+        self.measurements.push(Measurement {
+            name: name.to_string(),
+            units: simulation.units_consumed,
+        });
+        Ok(())
+    }
+
+    fn wait_until(&self, unix_timestamp: i64) -> Result<()> {
+        while self.rpc.get_unix_timestamp()? < unix_timestamp {
+            thread::sleep(CLOCK_POLL);
+        }
+        Ok(())
+    }
+
+    fn funded_bidders(&self, count: usize) -> Result<Vec<Keypair>> {
+        let bidders: Vec<Keypair> = (0..count).map(|_| Keypair::new()).collect();
+        let transfers: Vec<Instruction> = bidders
+            .iter()
+            .map(|bidder| {
+                system_instruction::transfer(&self.payer.pubkey(), &bidder.pubkey(), BIDDER_FUNDS)
+            })
+            .collect();
+        for chunk in transfers.chunks(8) {
+            self.send(chunk, &[])?;
+        }
+        Ok(bidders)
+    }
+
+    fn create(
+        &mut self,
+        name: &str,
+        auction_id: u64,
+        start_ts: i64,
+        options: AuctionOptions,
+    ) -> Result<Pubkey> {
+        let authority = self.payer.pubkey();
+        let end_ts = start_ts + BIDDING_SECS;
+        let create = instruction::create_auction(
+            &authority,
+            auction_id,
+            start_ts,
+            end_ts,
+            end_ts + REVEALING_SECS,
+            0,
+            options,
+        );
+        self.measure(name, &[create], &[])?;
+        Ok(auction_pda(&authority, auction_id).0)
+    }
+
+    fn auction(&self, auction: &Pubkey) -> Result<Auction> {
+        let account = self
+            .rpc
+            .get_account(auction)?
+            .with_context(|| format!("auction {auction} not found"))?;
+        Ok(decode_auction(&account.data)?)
+    }
+
+    /// Seals `amount` for `keypair` with a nonce the auction's commitment accepts, along
+    /// with the proof the auction requires for it.
+    fn seal(
+        &self,
+        auction: &Pubkey,
+        keypair: Keypair,
+        amount: u64,
+    ) -> Result<(Bidder, [u8; 32], Option<BidProof>)> {
+        let state = self.auction(auction)?;
+        let nonce = if state.derived_nonce {
+            let signature: [u8; 64] = keypair
+                .sign_message(&nonce_derivation_message(auction))
+                .into();
+            derive_bid_nonce(&signature)
+        } else {
+            // Canonical as a Pedersen blinding scalar, too.
+            let mut nonce = keypair.pubkey().to_bytes();
+            nonce[31] &= 0x0f;
+            nonce
+        };
+        let bid_hash = seal_bid(&state, auction, &keypair.pubkey(), amount, &nonce)?;
+        let proof = bid_proof(&state, auction, &keypair.pubkey(), amount, &nonce)?;
+        Ok((
+            Bidder {
+                keypair,
+                amount,
+                nonce,
+            },
+            bid_hash,
+            proof,
+        ))
+    }
+
+    fn commit(
+        &mut self,
+        name: Option<&str>,
+        auction: &Pubkey,
+        keypair: Keypair,
+        amount: u64,
+    ) -> Result<Bidder> {
+        let (bidder, bid_hash, proof) = self.seal(auction, keypair, amount)?;
+        let submit = instruction::submit_sealed_bid(
+            auction,
+            self.auction(auction)?.creation_slot,
+            &bidder.keypair.pubkey(),
+            bid_hash,
+            proof,
+        );
+        match name {
+            Some(name) => self.measure(name, &[submit], &[&bidder.keypair])?,
+            None => self.send(&[submit], &[&bidder.keypair])?,
+        }
+        Ok(bidder)
+    }
+
+    fn reveal(
+        &mut self,
+        name: &str,
+        auction: &Pubkey,
+        bidder: &Bidder,
+        prefix: Vec<Instruction>,
+    ) -> Result<()> {
+        let key = bidder.keypair.pubkey();
+        let mut instructions = prefix;
+        instructions.push(instruction::reveal_bid(
+            auction,
+            self.auction(auction)?.creation_slot,
+            &key,
+            bidder.amount,
+            bidder.nonce,
+            false,
+        ));
+        self.measure(name, &instructions, &[&bidder.keypair])
+    }
+
+    /// Runs the scenarios, one auction per commitment variant, all sharing one schedule.
+    pub fn run(mut self) -> Result<Report> {
+        let authority = self.payer.pubkey();
+        let now = self.rpc.get_unix_timestamp()?;
+        let start_ts = now + 2;
+        let end_ts = start_ts + BIDDING_SECS;
+        let reveal_end_ts = end_ts + REVEALING_SECS;
+        // Fresh ids, so reruns against the same validator don't collide.
+        let base_id = (now as u64) * 10;
+        let options = |commitment_hash, derived_nonce| AuctionOptions {
+            bid_mode: BidMode::Sealed,
+            commitment_scheme: CommitmentScheme::V2,
+            commitment_hash,
+            range_proof: false,
+            encryption_key: None,
+            derived_nonce,
+        };
+
+        let main = self.create(
+            "create_auction",
+            base_id,
+            start_ts,
+            options(CommitmentHash::Sha256, false),
+        )?;
+        let poseidon = self.create(
+            "create_auction/poseidon",
+            base_id + 1,
+            start_ts,
+            options(CommitmentHash::Poseidon, false),
+        )?;
+        let pedersen = self.create(
+            "create_auction/pedersen",
+            base_id + 2,
+            start_ts,
+            options(CommitmentHash::Pedersen, false),
+        )?;
+        let derived = self.create(
+            "create_auction/derived_nonce",
+            base_id + 3,
+            start_ts,
+            options(CommitmentHash::Sha256, true),
+        )?;
+        // Never bid on, so it opens late enough for the key to land before it starts.
+        let ranged = self.create(
+            "create_auction/range_proof",
+            base_id + 4,
+            reveal_end_ts,
+            AuctionOptions {
+                range_proof: true,
+                ..options(CommitmentHash::Poseidon, false)
+            },
+        )?;
+        let fixture = RangeProofFixture::load()?;
+        self.measure(
+            "set_range_proof_key",
+            &[instruction::set_range_proof_key(
+                &ranged, &authority, fixture.vk,
+            )],
+            &[],
+        )?;
+
+        let main_slot = self.auction(&main)?.creation_slot;
+
+        let mut keys = self.funded_bidders(BATCH_SIZE + 8)?.into_iter();
+        let mut next = || keys.next().expect("enough bidders");
+        self.wait_until(start_ts)?;
+
+        // Bidding on the SHA-256 auction, covering every commit path.
+        let idle = next();
+        let init =
+            instruction::initialize_bid_account(&main, main_slot, &idle.pubkey(), &authority);
+        self.measure("initialize_bid_account", &[init], &[])?;
+
+        let plain = self.commit(Some("submit_sealed_bid"), &main, next(), 300)?;
+        let (relayed, bid_hash, proof) = self.seal(&main, next(), 200)?;
+        let signature: [u8; 64] = relayed
+            .keypair
+            .sign_message(&signed_bid_message(&main, main_slot, &bid_hash))
+            .into();
+        let relayed_key = relayed.keypair.pubkey();
+        self.measure(
+            "submit_sealed_bid_signed",
+            &[
+                instruction::ed25519_verify(
+                    &relayed_key,
+                    &signature,
+                    &signed_bid_message(&main, main_slot, &bid_hash),
+                ),
+                instruction::submit_sealed_bid_signed(
+                    &main,
+                    main_slot,
+                    &relayed_key,
+                    &authority,
+                    bid_hash,
+                    proof,
+                ),
+            ],
+            &[],
+        )?;
+        let agented = self.commit(None, &main, next(), 100)?;
+        let batched: Vec<Bidder> = (0..BATCH_SIZE)
+            .map(|index| self.commit(None, &main, next(), 10 + index as u64))
+            .collect::<Result<_>>()?;
+
+        for (index, bidder) in [&plain, &relayed, &agented].into_iter().enumerate() {
+            let deposit = instruction::deposit_escrow(&main, &bidder.keypair.pubkey(), DEPOSIT);
+            match index {
+                0 => self.measure("deposit_escrow", &[deposit], &[&bidder.keypair])?,
+                _ => self.send(&[deposit], &[&bidder.keypair])?,
+            }
+        }
+        let agent = instruction::set_reveal_agent(
+            &main,
+            main_slot,
+            &agented.keypair.pubkey(),
+            Some(authority),
+        );
+        self.measure("set_reveal_agent", &[agent], &[&agented.keypair])?;
+
+        // One committed bid per commitment variant.
+        let poseidon_bid =
+            self.commit(Some("submit_sealed_bid/poseidon"), &poseidon, next(), 100)?;
+        let pedersen_bid =
+            self.commit(Some("submit_sealed_bid/pedersen"), &pedersen, next(), 100)?;
+        let pedersen_other = self.commit(None, &pedersen, next(), 250)?;
+        let derived_bid = self.commit(
+            Some("submit_sealed_bid/derived_nonce"),
+            &derived,
+            next(),
+            100,
+        )?;
+
+        self.wait_until(end_ts)?;
+        // Whoever pools every bidder's blinding can open the demand sum.
+        let open = instruction::open_aggregate_demand(
+            &pedersen,
+            pedersen_bid.amount + pedersen_other.amount,
+            sum_blindings(&[pedersen_bid.nonce, pedersen_other.nonce])?,
+        );
+        self.measure("open_aggregate_demand", &[open], &[])?;
+        self.reveal("reveal_bid/sha256", &main, &plain, Vec::new())?;
+        // Reveals are often bundled with priority fees; the budget instructions count too.
+        self.reveal(
+            "reveal_bid/priority_fee",
+            &main,
+            &relayed,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            ],
+        )?;
+        let reveal_for = instruction::reveal_bid_for(
+            &main,
+            main_slot,
+            &agented.keypair.pubkey(),
+            &authority,
+            agented.amount,
+            agented.nonce,
+            false,
+        );
+        self.measure("reveal_bid_for", &[reveal_for], &[])?;
+        let batch = instruction::reveal_bids_batch(
+            &main,
+            main_slot,
+            &authority,
+            &batched
+                .iter()
+                .map(|bidder| bidder.keypair.pubkey())
+                .collect::<Vec<_>>(),
+            batched
+                .iter()
+                .map(|bidder| BidReveal {
+                    amount: bidder.amount,
+                    nonce: bidder.nonce,
+                })
+                .collect(),
+        );
+        self.measure(&format!("reveal_bids_batch/{BATCH_SIZE}"), &[batch], &[])?;
+
+        self.reveal("reveal_bid/poseidon", &poseidon, &poseidon_bid, Vec::new())?;
+        self.reveal("reveal_bid/pedersen", &pedersen, &pedersen_bid, Vec::new())?;
+        let derived_key = derived_bid.keypair.pubkey();
+        let message = nonce_derivation_message(&derived);
+        let signature: [u8; 64] = derived_bid.keypair.sign_message(&message).into();
+        self.reveal(
+            "reveal_bid/derived_nonce",
+            &derived,
+            &derived_bid,
+            vec![instruction::ed25519_verify(
+                &derived_key,
+                &signature,
+                &message,
+            )],
+        )?;
+
+        self.wait_until(reveal_end_ts)?;
+        self.measure(
+            "finalize_auction",
+            &[instruction::finalize_auction(&main, &authority)],
+            &[],
+        )?;
+        let winner = Some(plain.keypair.pubkey());
+        let views = [
+            ("get_auction_state", instruction::get_auction_state(&main)),
+            (
+                "get_bid_status",
+                instruction::get_bid_status(&main, main_slot, &plain.keypair.pubkey()),
+            ),
+        ];
+        for (name, view) in views {
+            self.measure(name, &[view], &[])?;
+        }
+        let settle =
+            instruction::settle_auction(&main, &authority, winner, &authority, main_slot, &[]);
+        self.measure("settle_auction", &[settle], &[])?;
+        let withdraw = instruction::withdraw_deposit(&main, &relayed_key);
+        self.measure("withdraw_deposit", &[withdraw], &[&relayed.keypair])?;
+
+        Ok(Report {
+            measurements: self.measurements,
+            not_measured: NOT_MEASURED
+                .iter()
+                .map(|(name, reason)| (name.to_string(), reason.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        })
+    }
+}
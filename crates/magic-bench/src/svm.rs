@@ -0,0 +1,412 @@
+//! Measures in LiteSVM what a local validator cannot run: the delegation, permission and
+//! crank paths against the harness stubs, a range-proof bid from the proof fixture, whose
+//! bidder key has no known secret, and the layout migrations, whose legacy accounts are
+//! written directly. It loads the same `target/deploy/magic.so` the validator runs. The
+//! stubs charge a nominal amount per CPI, so these numbers cover the program's own work
+//! and not the MagicBlock programs it calls.
+
+use anchor_lang::{
+    prelude::{Pubkey, Rent},
+    solana_program::instruction::Instruction,
+    AccountSerialize, AnchorSerialize, Discriminator, Space,
+};
+use anyhow::{anyhow, Result};
+use magic::constant::BID_SEED;
+use magic_client::{
+    account::{Auction, Bid},
+    instruction,
+    pda::auction_pda,
+    AuctionOptions, AuctionStatus, BidMode, BidProof, CommitmentHash, CommitmentScheme, PROGRAM_ID,
+};
+use magic_harness::{svm::Svm, Failure, GENESIS_TIMESTAMP};
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::{fixture::RangeProofFixture, report::Measurement};
+
+const START: i64 = GENESIS_TIMESTAMP + 10;
+const END: i64 = GENESIS_TIMESTAMP + 100;
+const REVEAL_END: i64 = GENESIS_TIMESTAMP + 200;
+const LAMPORTS: u64 = 10_000_000_000;
+const TASK_ID: u64 = 1;
+
+pub struct SvmBench {
+    svm: Svm,
+    authority: Keypair,
+    measurements: Vec<Measurement>,
+}
+
+impl SvmBench {
+    /// Fails if the program has not been built.
+    pub fn new() -> Result<Self> {
+        let mut svm = Svm::without_sigverify()
+            .map_err(|err| anyhow!("cannot load {}: {err:?}", Svm::program_path().display()))?;
+        let authority = Keypair::new();
+        svm.airdrop(&authority.pubkey(), LAMPORTS);
+        Ok(Self {
+            svm,
+            authority,
+            measurements: Vec::new(),
+        })
+    }
+
+    /// Lands `instructions`, paid by the authority, and returns their compute units.
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<u64> {
+        let mut all = vec![&self.authority];
+        all.extend(signers);
+        let result = self.svm.process(instructions, &all);
+        units(result.map(|meta| meta.compute_units_consumed))
+    }
+
+    /// Lands `instructions` for `payer` without its signature.
+    fn send_unsigned(&mut self, instructions: &[Instruction], payer: &Pubkey) -> Result<u64> {
+        let result = self.svm.process_unsigned(instructions, payer);
+        units(result.map(|meta| meta.compute_units_consumed))
+    }
+
+    fn measure(
+        &mut self,
+        name: &str,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<()> {
+        let units = self
+            .send(instructions, signers)
+            .map_err(|err| anyhow!("{name} failed: {err}"))?;
+        self.record(name, units);
+        Ok(())
+    }
+
+    fn measure_unsigned(
+        &mut self,
+        name: &str,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+    ) -> Result<()> {
+        let units = self
+            .send_unsigned(instructions, payer)
+            .map_err(|err| anyhow!("{name} failed: {err}"))?;
+        self.record(name, units);
+        Ok(())
+    }
+
+    fn record(&mut self, name: &str, units: u64) {
+        println!("{name}: {units} CU (LiteSVM)");
+        self.measurements.push(Measurement {
+            name: name.to_string(),
+            units,
+        });
+    }
+
+    fn funded(&mut self) -> Keypair {
+        let keypair = Keypair::new();
+        self.svm.airdrop(&keypair.pubkey(), LAMPORTS);
+        keypair
+    }
+
+    /// Creates auction `auction_id` on a fresh clock, opening at `START`.
+    fn create(&mut self, auction_id: u64, bid_mode: BidMode) -> Result<(Pubkey, u64)> {
+        self.svm.warp_to_timestamp(GENESIS_TIMESTAMP);
+        let authority = self.authority.pubkey();
+        let options = AuctionOptions {
+            bid_mode,
+            commitment_scheme: CommitmentScheme::V2,
+            commitment_hash: CommitmentHash::Sha256,
+            range_proof: false,
+            encryption_key: None,
+            derived_nonce: false,
+        };
+        let create =
+            instruction::create_auction(&authority, auction_id, START, END, REVEAL_END, 0, options);
+        self.send(&[create], &[])?;
+        let auction = auction_pda(&authority, auction_id).0;
+        let slot = self
+            .svm
+            .get::<Auction>(&auction)
+            .ok_or_else(|| anyhow!("auction {auction} not created"))?
+            .creation_slot;
+        Ok((auction, slot))
+    }
+
+    pub fn run(mut self) -> Result<Vec<Measurement>> {
+        self.permissions_and_crank()?;
+        self.delegation()?;
+        self.range_proof()?;
+        self.migrations()?;
+        Ok(self.measurements)
+    }
+
+    /// A sealed auction with permissions and an auto-finalize task, run to its close.
+    fn permissions_and_crank(&mut self) -> Result<()> {
+        let authority = self.authority.pubkey();
+        let (auction, slot) = self.create(1, BidMode::Sealed)?;
+        self.measure(
+            "create_auction_permission",
+            &[instruction::create_auction_permission(&auction, &authority)],
+            &[],
+        )?;
+        let bidder = self.funded();
+        let key = bidder.pubkey();
+        self.send(
+            &[instruction::initialize_bid_account(
+                &auction, slot, &key, &key,
+            )],
+            &[&bidder],
+        )?;
+        self.measure(
+            "create_bid_permission",
+            &[instruction::create_bid_permission(
+                &auction, slot, &key, &authority,
+            )],
+            &[],
+        )?;
+
+        let task_context = Pubkey::new_unique();
+        let schedule =
+            instruction::schedule_auto_finalize(&auction, &authority, &task_context, TASK_ID);
+        self.measure(
+            "schedule_auto_finalize",
+            std::slice::from_ref(&schedule),
+            &[],
+        )?;
+        self.measure(
+            "cancel_auto_finalize",
+            &[instruction::cancel_auto_finalize(
+                &auction,
+                &authority,
+                &task_context,
+            )],
+            &[],
+        )?;
+        self.send(&[schedule], &[])?;
+
+        self.svm.warp_to_timestamp(END);
+        self.measure(
+            "publish_bid_permission",
+            &[instruction::publish_bid_permission(&auction, slot, &key)],
+            &[],
+        )?;
+        self.svm.warp_to_timestamp(REVEAL_END);
+        self.measure(
+            "auto_finalize",
+            &[instruction::auto_finalize(&auction)],
+            &[],
+        )?;
+        self.measure(
+            "close_auction_permission",
+            &[instruction::close_auction_permission(&auction, &authority)],
+            &[],
+        )?;
+        self.measure(
+            "close_bid_permission",
+            &[instruction::close_bid_permission(&auction, slot, &key)],
+            &[&bidder],
+        )
+    }
+
+    /// Delegating a sealed auction and a bid, and onboarding a private bidder and auction.
+    fn delegation(&mut self) -> Result<()> {
+        let authority = self.authority.pubkey();
+        let (auction, slot) = self.create(2, BidMode::Sealed)?;
+        let bidder = self.funded();
+        let key = bidder.pubkey();
+        self.send(
+            &[instruction::initialize_bid_account(
+                &auction, slot, &key, &key,
+            )],
+            &[&bidder],
+        )?;
+        self.measure(
+            "delegate_bid",
+            &[instruction::delegate_bid(
+                &authority, &auction, slot, &key, None,
+            )],
+            &[],
+        )?;
+        self.measure(
+            "delegate_auction",
+            &[instruction::delegate_auction(
+                &authority, &authority, 2, None,
+            )],
+            &[],
+        )?;
+
+        // The bid goes first, as it reads the auction, which delegation hands over.
+        let (private, slot) = self.create(3, BidMode::Private)?;
+        let bidder = self.funded();
+        let key = bidder.pubkey();
+        self.measure(
+            "prepare_private_bid",
+            &[instruction::prepare_private_bid(
+                &private, slot, &key, &key, None,
+            )],
+            &[&bidder],
+        )?;
+        self.measure(
+            "prepare_private_auction",
+            &[instruction::prepare_private_auction(&authority, 3, None)],
+            &[],
+        )
+    }
+
+    /// The fixture's range proof, against an auction written at the fixture's address.
+    /// Range proofs need Poseidon commitments, so this is the costliest sealed bid.
+    fn range_proof(&mut self) -> Result<()> {
+        let fixture = RangeProofFixture::load()?;
+        let authority = self.authority.pubkey();
+        self.svm.warp_to_timestamp(GENESIS_TIMESTAMP);
+        let auction = Auction {
+            auction_id: 0,
+            authority,
+            start_ts: START,
+            end_ts: END,
+            reveal_end_ts: REVEAL_END,
+            reserve_price: fixture.reserve_price,
+            highest_bid: 0,
+            highest_bidder: None,
+            total_bids: 0,
+            total_revealed: 0,
+            status: AuctionStatus::Scheduled,
+            bump: 0,
+            version: Auction::VERSION,
+            bid_mode: BidMode::Sealed,
+            commitment_scheme: CommitmentScheme::V2,
+            commitment_hash: CommitmentHash::Poseidon,
+            range_proof: true,
+            encryption_key: None,
+            derived_nonce: false,
+            creation_slot: fixture.creation_slot,
+            demand_commitment: [0; 32],
+            aggregate_demand: None,
+            auto_finalize_task: None,
+        };
+        let mut data = Vec::with_capacity(8 + Auction::INIT_SPACE);
+        auction.try_serialize(&mut data)?;
+        data.resize(8 + Auction::INIT_SPACE, 0);
+        self.write(fixture.auction, data);
+        self.send(
+            &[instruction::set_range_proof_key(
+                &fixture.auction,
+                &authority,
+                fixture.vk,
+            )],
+            &[],
+        )?;
+
+        let bidder = fixture.bidder;
+        self.svm.airdrop(&bidder, LAMPORTS);
+        self.svm.warp_to_timestamp(START);
+        self.send_unsigned(
+            &[instruction::deposit_escrow(
+                &fixture.auction,
+                &bidder,
+                fixture.deposit_amount,
+            )],
+            &bidder,
+        )?;
+        self.measure_unsigned(
+            "submit_sealed_bid/range_proof",
+            &[instruction::submit_sealed_bid(
+                &fixture.auction,
+                fixture.creation_slot,
+                &bidder,
+                fixture.bid_hash,
+                Some(BidProof::Range(fixture.proof)),
+            )],
+            &bidder,
+        )?;
+        self.svm.warp_to_timestamp(END);
+        self.measure_unsigned(
+            "reveal_bid/range_proof",
+            &[instruction::reveal_bid(
+                &fixture.auction,
+                fixture.creation_slot,
+                &bidder,
+                fixture.amount,
+                fixture.nonce,
+                false,
+            )],
+            &bidder,
+        )
+    }
+
+    /// An auction and a bid in the layouts from before the version byte, funded for
+    /// their old size so the migration tops them up.
+    fn migrations(&mut self) -> Result<()> {
+        let authority = self.authority.pubkey();
+        let (auction, bump) = auction_pda(&authority, 9);
+        let mut status = Vec::new();
+        AuctionStatus::Bidding.serialize(&mut status)?;
+        let legacy_auction = [
+            Auction::DISCRIMINATOR,
+            &9_u64.to_le_bytes(),
+            authority.as_ref(),
+            &START.to_le_bytes(),
+            &END.to_le_bytes(),
+            &REVEAL_END.to_le_bytes(),
+            &0_u64.to_le_bytes(),
+            &0_u64.to_le_bytes(),
+            &[0],
+            &0_u32.to_le_bytes(),
+            &0_u32.to_le_bytes(),
+            &status,
+            &[bump],
+        ]
+        .concat();
+        self.write(auction, legacy_auction);
+        self.measure(
+            "migrate_auction",
+            &[instruction::migrate_auction(&auction, &authority)],
+            &[],
+        )?;
+
+        let bidder = Pubkey::new_unique();
+        let (bid, bump) = Pubkey::find_program_address(
+            &[BID_SEED, auction.as_ref(), bidder.as_ref()],
+            &PROGRAM_ID,
+        );
+        let legacy_bid = [
+            Bid::DISCRIMINATOR,
+            auction.as_ref(),
+            bidder.as_ref(),
+            &[3; 32],
+            &[1, 0],
+            &0_u64.to_le_bytes(),
+            &[0; 32],
+            &[bump],
+        ]
+        .concat();
+        self.write(bid, legacy_bid);
+        self.measure(
+            "migrate_bid",
+            &[instruction::migrate_bid(&bid, &authority)],
+            &[],
+        )
+    }
+
+    /// Writes a rent-exempt program account holding `data` at `key`.
+    fn write(&mut self, key: Pubkey, data: Vec<u8>) {
+        self.svm.set_account(
+            key,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+}
+
+fn units(result: std::result::Result<u64, Failure>) -> Result<u64> {
+    result.map_err(|failure| {
+        anyhow!(
+            "instruction {}: {:?}\n{}",
+            failure.instruction,
+            failure.error,
+            failure.logs.join("\n")
+        )
+    })
+}
@@ -0,0 +1,220 @@
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Result};
+use magic_client::{
+    account::{bid_mode_name, hash_name, scheme_name, status_name, Bid, Deposit},
+    instruction, outcome,
+    pda::{auction_pda, bid_pda},
+    AuctionOptions, AuctionState, AuctionStatus,
+};
+
+use crate::context::Context;
+
+/// Bids one settlement transaction carries; each adds two or three accounts.
+const BIDS_PER_TRANSACTION: usize = 8;
+
+pub struct CreateArgs {
+    pub auction_id: u64,
+    pub start: String,
+    pub end: String,
+    pub reveal_end: String,
+    pub reserve_price: u64,
+    pub options: AuctionOptions,
+}
+
+pub fn create(ctx: &Context, args: CreateArgs) -> Result<()> {
+    let now = ctx.router.l1.get_unix_timestamp()?;
+    let start_ts = parse_time(&args.start, now)?;
+    let end_ts = parse_time(&args.end, now)?;
+    let reveal_end_ts = parse_time(&args.reveal_end, now)?;
+
+    let authority = ctx.wallet();
+    let (auction, _) = auction_pda(&authority, args.auction_id);
+    let signature = ctx.send(
+        &ctx.router.l1,
+        &[instruction::create_auction(
+            &authority,
+            args.auction_id,
+            start_ts,
+            end_ts,
+            reveal_end_ts,
+            args.reserve_price,
+            args.options,
+        )],
+    )?;
+    println!("auction {auction} created ({signature})");
+    Ok(())
+}
+
+pub fn show(ctx: &Context, key: &Pubkey) -> Result<()> {
+    let delegated = ctx.router.is_delegated(key)?;
+    let auction = ctx.auction(key)?;
+    let state: AuctionState = ctx
+        .router
+        .rpc_for(key)?
+        .view(&ctx.wallet(), instruction::get_auction_state(key))?;
+    println!("auction          {key}");
+    println!("location         {}", if delegated { "ER" } else { "L1" });
+    println!("id               {}", auction.auction_id);
+    println!("authority        {}", auction.authority);
+    println!("status           {}", status_name(&auction.status));
+    let mut current = status_name(&state.status).to_string();
+    if state.finalizable {
+        current.push_str(", finalizable");
+    }
+    if let Some(seconds) = state.seconds_remaining {
+        current.push_str(&format!(" ({seconds}s left)"));
+    }
+    println!("current status   {current}");
+    println!("bid mode         {}", bid_mode_name(&auction.bid_mode));
+    println!(
+        "commitment       {} / {}",
+        scheme_name(&auction.commitment_scheme),
+        hash_name(&auction.commitment_hash)
+    );
+    println!("start / end      {} / {}", auction.start_ts, auction.end_ts);
+    println!("reveal end       {}", auction.reveal_end_ts);
+    println!("reserve price    {}", auction.reserve_price);
+    println!(
+        "bids             {} committed, {} revealed",
+        auction.total_bids, auction.total_revealed
+    );
+    match auction.highest_bidder {
+        Some(bidder) => println!("leader           {bidder} ({})", auction.highest_bid),
+        None => println!("leader           none"),
+    }
+    Ok(())
+}
+
+/// Finalizes on whichever chain holds the auction.
+pub fn finalize(ctx: &Context, key: &Pubkey) -> Result<()> {
+    let rpc = ctx.router.rpc_for(key)?;
+    let signature = ctx.send(rpc, &[instruction::finalize_auction(key, &ctx.wallet())])?;
+    println!("auction {key} finalized ({signature})");
+    Ok(())
+}
+
+/// Cancels on whichever chain holds the auction.
+pub fn cancel(ctx: &Context, key: &Pubkey) -> Result<()> {
+    let rpc = ctx.router.rpc_for(key)?;
+    let signature = ctx.send(rpc, &[instruction::cancel_auction(key, &ctx.wallet())])?;
+    println!("auction {key} cancelled ({signature})");
+    Ok(())
+}
+
+/// A delegated auction is committed back with `finalize_and_settle` on the ER, which
+/// schedules the L1 payout and takes the delegated bids along (further ones follow with
+/// `undelegate_bids`); an auction already on L1 is paid out with `settle_auction`, which
+/// also closes the permissions already back on L1.
+pub fn settle(ctx: &Context, key: &Pubkey, with_permission: bool) -> Result<()> {
+    let auction = ctx.auction(key)?;
+    if auction.status == AuctionStatus::Settled {
+        bail!("auction {key} is already settled");
+    }
+
+    let wallet = ctx.wallet();
+    let slot = auction.creation_slot;
+    let bidders: Vec<Pubkey> = ctx
+        .program_accounts::<Bid>()?
+        .into_iter()
+        .filter(|bid| bid.auction == *key && bid.auction_slot == slot)
+        .map(|bid| bid.bidder)
+        .collect();
+    let signature = if ctx.router.is_delegated(key)? {
+        let rpc = ctx.router.rpc_for(key)?;
+        let mut delegated = Vec::new();
+        for bidder in bidders {
+            if ctx.router.is_delegated(&bid_pda(key, slot, &bidder).0)? {
+                delegated.push(bidder);
+            }
+        }
+        let mut batches = delegated.chunks(BIDS_PER_TRANSACTION);
+        let signature = ctx.send(
+            rpc,
+            &[instruction::finalize_and_settle(
+                key,
+                &wallet,
+                &wallet,
+                with_permission,
+                slot,
+                batches.next().unwrap_or_default(),
+            )],
+        )?;
+        for batch in batches {
+            ctx.send(
+                rpc,
+                &[instruction::undelegate_bids(key, &wallet, slot, batch)],
+            )?;
+        }
+        signature
+    } else {
+        ctx.send(
+            &ctx.router.l1,
+            &[instruction::settle_auction(
+                key,
+                &auction.authority,
+                auction.highest_bidder,
+                &wallet,
+                slot,
+                &bidders[..bidders.len().min(BIDS_PER_TRANSACTION)],
+            )],
+        )?
+    };
+    println!("auction {key} settled ({signature})");
+    Ok(())
+}
+
+/// Recomputes the outcome from the auction's bid and deposit accounts and reports every
+/// difference from the on-chain result. Until settlement, also prints the refunds deposits imply.
+pub fn verify(ctx: &Context, key: &Pubkey) -> Result<()> {
+    let auction = ctx.auction(key)?;
+    let bids = ctx.program_accounts::<Bid>()?;
+    let deposits: Vec<Deposit> = ctx
+        .program_accounts::<Deposit>()?
+        .into_iter()
+        .filter(|deposit| deposit.auction == *key)
+        .collect();
+    let audit = outcome::audit_accounts(key, &auction, &bids, &deposits);
+    let expected = &audit.expected;
+
+    println!(
+        "bids             {} committed, {} revealed",
+        expected.total_bids, expected.total_revealed
+    );
+    match expected.winner {
+        Some(winner) => println!("winner           {winner} pays {}", expected.clearing_price),
+        None => println!(
+            "winner           none (highest bid {})",
+            expected.highest_bid
+        ),
+    }
+    if auction.status == AuctionStatus::Finalized {
+        let settlement = expected.settlement(&deposits);
+        println!(
+            "payment          {} (shortfall {})",
+            settlement.paid, settlement.shortfall
+        );
+        for refund in &settlement.refunds {
+            println!("refund           {} {}", refund.bidder, refund.amount);
+        }
+    }
+
+    if audit.is_clean() {
+        println!("auction {key} matches the recomputed outcome");
+        return Ok(());
+    }
+    for discrepancy in &audit.discrepancies {
+        println!("mismatch         {discrepancy}");
+    }
+    bail!(
+        "auction {key} differs from the recomputed outcome in {} way(s)",
+        audit.discrepancies.len()
+    )
+}
+
+/// Accepts unix seconds, or `+N` for N seconds after the cluster's current time.
+pub fn parse_time(value: &str, now: i64) -> Result<i64> {
+    match value.strip_prefix('+') {
+        Some(offset) => Ok(now + offset.parse::<i64>()?),
+        None => Ok(value.parse()?),
+    }
+}
@@ -0,0 +1,209 @@
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Result};
+use magic_client::{
+    account::{decode, Auction, Deposit},
+    commitment::{bid_proof, derive_bid_nonce, nonce_derivation_message, seal_bid},
+    instruction,
+    pda::{bid_pda, deposit_pda},
+    BidMode, CommitmentHash,
+};
+use rand::{rngs::OsRng, RngCore};
+
+use crate::{context::Context, store::StoredBid};
+
+/// Seals `amount`, stores the secret locally, then commits it. Private-mode bids are
+/// prepared and delegated on L1 first and submitted to the ER. Without a deposit the bid
+/// could not win, so one of `deposit` (default `amount`) is locked first; an existing
+/// deposit must already cover `amount`, as deposits cannot be topped up.
+pub fn commit(
+    ctx: &Context,
+    auction_key: &Pubkey,
+    amount: u64,
+    deposit: Option<u64>,
+    force: bool,
+) -> Result<()> {
+    let auction = ctx.auction(auction_key)?;
+    if auction.bid_mode == BidMode::Encrypted {
+        bail!("encrypted auctions take ciphertexts, which the CLI does not produce");
+    }
+
+    let bidder = ctx.wallet();
+    let escrow = match (existing_deposit(ctx, auction_key, &bidder)?, deposit) {
+        (Some(existing), _) if existing.amount < amount => bail!(
+            "the existing deposit of {} does not cover a bid of {amount}, which could not win",
+            existing.amount
+        ),
+        (Some(_), Some(_)) => bail!("a deposit already exists for this auction"),
+        (Some(_), None) => None,
+        (None, Some(deposit)) if deposit < amount => {
+            bail!("a deposit of {deposit} does not cover a bid of {amount}, which could not win")
+        }
+        (None, deposit) => Some(instruction::deposit_escrow(
+            auction_key,
+            &bidder,
+            deposit.unwrap_or(amount),
+        )),
+    };
+    let nonce = bid_nonce(ctx, &auction, auction_key);
+    let bid_hash = seal_bid(&auction, auction_key, &bidder, amount, &nonce)?;
+    let proof = bid_proof(&auction, auction_key, &bidder, amount, &nonce)?;
+    let stored = StoredBid::new(auction_key, &bidder, amount, &nonce);
+    let path = ctx.store.save(&stored, auction_key, &bidder, force)?;
+    println!("bid secret saved to {}", path.display());
+
+    // Private bids are plaintext inside the ER; sealed bids never send the amount.
+    let plaintext = (auction.bid_mode == BidMode::Private).then_some(amount);
+    let (bid, _) = bid_pda(auction_key, auction.creation_slot, &bidder);
+    let signature = if ctx.router.l1.get_account(&bid)?.is_some() {
+        if let Some(escrow) = escrow {
+            ctx.send(&ctx.router.l1, &[escrow])?;
+        }
+        let rpc = ctx.router.rpc_for(&bid)?;
+        ctx.send(
+            rpc,
+            &[instruction::submit_sealed_bid_delegated(
+                auction_key,
+                auction.creation_slot,
+                &bidder,
+                bid_hash,
+                plaintext,
+                proof.clone(),
+            )],
+        )?
+    } else if auction.bid_mode == BidMode::Private {
+        let prepare = instruction::prepare_private_bid(
+            auction_key,
+            auction.creation_slot,
+            &bidder,
+            &bidder,
+            None,
+        );
+        ctx.send(
+            &ctx.router.l1,
+            &escrow.into_iter().chain([prepare]).collect::<Vec<_>>(),
+        )?;
+        let rpc = ctx.router.rpc_for(&bid)?;
+        ctx.send(
+            rpc,
+            &[instruction::submit_sealed_bid_delegated(
+                auction_key,
+                auction.creation_slot,
+                &bidder,
+                bid_hash,
+                plaintext,
+                proof.clone(),
+            )],
+        )?
+    } else {
+        let submit = instruction::submit_sealed_bid(
+            auction_key,
+            auction.creation_slot,
+            &bidder,
+            bid_hash,
+            proof,
+        );
+        ctx.send(
+            &ctx.router.l1,
+            &escrow.into_iter().chain([submit]).collect::<Vec<_>>(),
+        )?
+    };
+    println!("bid committed to {auction_key} ({signature})");
+    Ok(())
+}
+
+/// Reveals the stored bid, re-signing the nonce derivation message when the auction
+/// requires derived nonces.
+pub fn reveal(ctx: &Context, auction_key: &Pubkey) -> Result<()> {
+    let auction = ctx.auction(auction_key)?;
+    if auction.bid_mode != BidMode::Sealed {
+        bail!("only sealed bids are revealed; private and encrypted bids are resolved in bulk");
+    }
+
+    let bidder = ctx.wallet();
+    let stored = ctx.store.load(auction_key, &bidder)?;
+    let nonce = stored.nonce()?;
+
+    let mut instructions = Vec::new();
+    if auction.derived_nonce {
+        let message = nonce_derivation_message(auction_key);
+        instructions.push(instruction::ed25519_verify(
+            &bidder,
+            &ctx.sign(&message),
+            &message,
+        ));
+    }
+    instructions.push(instruction::reveal_bid(
+        auction_key,
+        auction.creation_slot,
+        &bidder,
+        stored.amount,
+        nonce,
+        false,
+    ));
+
+    let rpc = ctx.router.rpc_for(auction_key)?;
+    let signature = ctx.send(rpc, &instructions)?;
+    println!(
+        "bid of {} revealed on {auction_key} ({signature})",
+        stored.amount
+    );
+    Ok(())
+}
+
+/// Locks `amount` as the wallet's escrow for the auction. Deposits always live on L1.
+pub fn deposit(ctx: &Context, auction_key: &Pubkey, amount: u64) -> Result<()> {
+    let bidder = ctx.wallet();
+    if existing_deposit(ctx, auction_key, &bidder)?.is_some() {
+        bail!("a deposit already exists for {auction_key}; withdraw it after the auction");
+    }
+    let signature = ctx.send(
+        &ctx.router.l1,
+        &[instruction::deposit_escrow(auction_key, &bidder, amount)],
+    )?;
+    println!("deposited {amount} for {auction_key} ({signature})");
+    Ok(())
+}
+
+/// Returns the wallet's escrow once the auction is settled, or finalized for losers.
+pub fn withdraw(ctx: &Context, auction_key: &Pubkey) -> Result<()> {
+    let bidder = ctx.wallet();
+    let Some(existing) = existing_deposit(ctx, auction_key, &bidder)? else {
+        bail!("no deposit for {auction_key}");
+    };
+    let signature = ctx.send(
+        &ctx.router.l1,
+        &[instruction::withdraw_deposit(auction_key, &bidder)],
+    )?;
+    println!(
+        "withdrew {} from {auction_key} ({signature})",
+        existing.amount
+    );
+    Ok(())
+}
+
+fn existing_deposit(
+    ctx: &Context,
+    auction_key: &Pubkey,
+    bidder: &Pubkey,
+) -> Result<Option<Deposit>> {
+    let (key, _) = deposit_pda(auction_key, bidder);
+    Ok(ctx
+        .router
+        .l1
+        .get_account(&key)?
+        .map(|account| decode(&account.data))
+        .transpose()?)
+}
+
+fn bid_nonce(ctx: &Context, auction: &Auction, auction_key: &Pubkey) -> [u8; 32] {
+    if auction.derived_nonce {
+        return derive_bid_nonce(&ctx.sign(&nonce_derivation_message(auction_key)));
+    }
+    let mut nonce = [0_u8; 32];
+    OsRng.fill_bytes(&mut nonce);
+    // Pedersen blindings must be canonical scalars; 252 bits always are.
+    if auction.commitment_hash == CommitmentHash::Pedersen {
+        nonce[31] &= 0x0f;
+    }
+    nonce
+}
@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, AccountDeserialize, Discriminator,
+};
+use anyhow::{Context as _, Result};
+use magic_client::{
+    account::{decode, decode_auction, Auction},
+    rpc::{Router, RpcClient},
+};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::store::BidStore;
+
+/// Everything a command needs: the L1/ER router, the signing wallet and the bid store.
+pub struct Context {
+    pub router: Router,
+    pub payer: Keypair,
+    pub store: BidStore,
+}
+
+impl Context {
+    pub fn wallet(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    /// Signs `instructions` with the wallet and lands them on `rpc`.
+    pub fn send(&self, rpc: &RpcClient, instructions: &[Instruction]) -> Result<String> {
+        let blockhash = rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        let signature = rpc
+            .send_and_confirm(&transaction)
+            .with_context(|| format!("transaction failed on {}", rpc.url()))?;
+        Ok(signature)
+    }
+
+    /// Fetches and decodes an auction from wherever it currently lives.
+    pub fn auction(&self, key: &Pubkey) -> Result<Auction> {
+        let account = self
+            .router
+            .get_account(key)?
+            .with_context(|| format!("auction {key} not found"))?;
+        Ok(decode_auction(&account.data)?)
+    }
+
+    /// Signature over `message`, as used for derived bid nonces.
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.payer.sign_message(message).into()
+    }
+
+    /// Every `T` account on L1 and the ER. Accounts found on L1 shadow the ER's read-only
+    /// clones of them; delegated accounts are only listed by the ER.
+    pub fn program_accounts<T: AccountDeserialize + Discriminator>(&self) -> Result<Vec<T>> {
+        let mut accounts = Vec::new();
+        let mut on_l1 = HashSet::new();
+        for (on_er, rpc) in [
+            (false, Some(&self.router.l1)),
+            (true, self.router.er.as_ref()),
+        ] {
+            let Some(rpc) = rpc else { continue };
+            for (key, account) in rpc.get_program_accounts(T::DISCRIMINATOR)? {
+                if on_er && on_l1.contains(&key) {
+                    continue;
+                }
+                if !on_er {
+                    on_l1.insert(key);
+                }
+                if let Ok(decoded) = decode(&account.data) {
+                    accounts.push(decoded);
+                }
+            }
+        }
+        Ok(accounts)
+    }
+}
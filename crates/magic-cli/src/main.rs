@@ -0,0 +1,239 @@
+//! `magic-cli`: create, inspect, bid in, finalize and settle magic auctions.
+
+mod auction;
+mod bid;
+mod context;
+mod store;
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Context as _, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use magic_client::{
+    rpc::{Router, RpcClient},
+    AuctionOptions, BidMode, CommitmentHash, CommitmentScheme,
+};
+
+use crate::{context::Context, store::BidStore};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Solana L1 RPC endpoint.
+    #[arg(long, env = "MAGIC_RPC", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Ephemeral Rollup RPC; reads and writes for delegated accounts go here.
+    #[arg(long, env = "MAGIC_ER_RPC")]
+    er_rpc: Option<String>,
+    /// Wallet keypair file; defaults to the Solana CLI keypair.
+    #[arg(long)]
+    keypair: Option<PathBuf>,
+    /// Directory holding bid secrets; defaults to `~/.config/magic-cli/bids`.
+    #[arg(long)]
+    store: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(subcommand)]
+    Auction(AuctionCommand),
+    #[command(subcommand)]
+    Bid(BidCommand),
+}
+
+#[derive(Subcommand)]
+enum AuctionCommand {
+    /// Create an auction owned by the wallet. Times are unix seconds or `+N` from now.
+    Create {
+        #[arg(long)]
+        id: u64,
+        #[arg(long)]
+        start: String,
+        #[arg(long)]
+        end: String,
+        #[arg(long)]
+        reveal_end: String,
+        #[arg(long, default_value_t = 0)]
+        reserve: u64,
+        #[arg(long, value_enum, default_value_t = Mode::Sealed)]
+        mode: Mode,
+        #[arg(long, value_enum, default_value_t = Scheme::V2)]
+        scheme: Scheme,
+        #[arg(long, value_enum, default_value_t = Hash::Sha256)]
+        hash: Hash,
+        /// Require reveals to prove the nonce was derived from the bidder's signature.
+        #[arg(long)]
+        derived_nonce: bool,
+        /// Hex-encoded Ristretto public key, required for encrypted auctions.
+        #[arg(long)]
+        encryption_key: Option<String>,
+    },
+    /// Print an auction's state.
+    Show { auction: Pubkey },
+    /// Finalize an auction after its reveal window.
+    Finalize { auction: Pubkey },
+    /// Cancel an auction that has no bids yet.
+    Cancel { auction: Pubkey },
+    /// Commit a delegated auction back to L1 and pay the winner's bid out.
+    Settle {
+        auction: Pubkey,
+        /// Also return the auction permission to L1.
+        #[arg(long)]
+        with_permission: bool,
+    },
+    /// Recompute the winner, price and refunds from the bid accounts and diff them
+    /// against the auction.
+    Verify { auction: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum BidCommand {
+    /// Seal and submit a bid; the nonce is stored locally for the reveal.
+    Commit {
+        auction: Pubkey,
+        #[arg(long)]
+        amount: u64,
+        /// Escrow to lock if there is no deposit yet; defaults to the amount. Deposits
+        /// are public, so a larger one hides the bid better.
+        #[arg(long)]
+        deposit: Option<u64>,
+        /// Replace an existing stored secret for this auction.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Reveal the stored bid.
+    Reveal { auction: Pubkey },
+    /// Lock the settlement escrow a bid needs to win.
+    Deposit {
+        auction: Pubkey,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Reclaim the escrow once the auction no longer needs it.
+    Withdraw { auction: Pubkey },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    Sealed,
+    Private,
+    Encrypted,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Scheme {
+    Legacy,
+    V1,
+    V2,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Hash {
+    Sha256,
+    Keccak256,
+    Poseidon,
+    Pedersen,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let keypair = cli
+        .keypair
+        .or_else(|| {
+            home.as_ref()
+                .map(|home| home.join(".config/solana/id.json"))
+        })
+        .ok_or_else(|| anyhow!("no --keypair given and HOME is not set"))?;
+    let store = cli
+        .store
+        .or_else(|| {
+            home.as_ref()
+                .map(|home| home.join(".config/magic-cli/bids"))
+        })
+        .ok_or_else(|| anyhow!("no --store given and HOME is not set"))?;
+
+    let ctx = Context {
+        router: Router::new(RpcClient::new(cli.url), cli.er_rpc.map(RpcClient::new)),
+        payer: solana_keypair::read_keypair_file(&keypair)
+            .map_err(|err| anyhow!("cannot read keypair {}: {err}", keypair.display()))?,
+        store: BidStore::new(store),
+    };
+
+    match cli.command {
+        Command::Auction(AuctionCommand::Create {
+            id,
+            start,
+            end,
+            reveal_end,
+            reserve,
+            mode,
+            scheme,
+            hash,
+            derived_nonce,
+            encryption_key,
+        }) => {
+            let encryption_key = encryption_key
+                .map(|key| -> Result<[u8; 32]> {
+                    hex::decode(key)
+                        .context("encryption key is not hex")?
+                        .try_into()
+                        .map_err(|_| anyhow!("encryption key must be 32 bytes"))
+                })
+                .transpose()?;
+            auction::create(
+                &ctx,
+                auction::CreateArgs {
+                    auction_id: id,
+                    start,
+                    end,
+                    reveal_end,
+                    reserve_price: reserve,
+                    options: AuctionOptions {
+                        bid_mode: match mode {
+                            Mode::Sealed => BidMode::Sealed,
+                            Mode::Private => BidMode::Private,
+                            Mode::Encrypted => BidMode::Encrypted,
+                        },
+                        commitment_scheme: match scheme {
+                            Scheme::Legacy => CommitmentScheme::Legacy,
+                            Scheme::V1 => CommitmentScheme::V1,
+                            Scheme::V2 => CommitmentScheme::V2,
+                        },
+                        commitment_hash: match hash {
+                            Hash::Sha256 => CommitmentHash::Sha256,
+                            Hash::Keccak256 => CommitmentHash::Keccak256,
+                            Hash::Poseidon => CommitmentHash::Poseidon,
+                            Hash::Pedersen => CommitmentHash::Pedersen,
+                        },
+                        range_proof: false,
+                        encryption_key,
+                        derived_nonce,
+                    },
+                },
+            )
+        }
+        Command::Auction(AuctionCommand::Show { auction }) => auction::show(&ctx, &auction),
+        Command::Auction(AuctionCommand::Finalize { auction }) => auction::finalize(&ctx, &auction),
+        Command::Auction(AuctionCommand::Cancel { auction }) => auction::cancel(&ctx, &auction),
+        Command::Auction(AuctionCommand::Settle {
+            auction,
+            with_permission,
+        }) => auction::settle(&ctx, &auction, with_permission),
+        Command::Auction(AuctionCommand::Verify { auction }) => auction::verify(&ctx, &auction),
+        Command::Bid(BidCommand::Commit {
+            auction,
+            amount,
+            deposit,
+            force,
+        }) => bid::commit(&ctx, &auction, amount, deposit, force),
+        Command::Bid(BidCommand::Reveal { auction }) => bid::reveal(&ctx, &auction),
+        Command::Bid(BidCommand::Deposit { auction, amount }) => {
+            bid::deposit(&ctx, &auction, amount)
+        }
+        Command::Bid(BidCommand::Withdraw { auction }) => bid::withdraw(&ctx, &auction),
+    }
+}
@@ -0,0 +1,126 @@
+//! Local storage for bid secrets. A lost nonce makes a sealed bid unrevealable, so the
+//! secret is written (owner-only) before the commit transaction is sent.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredBid {
+    pub auction: String,
+    pub bidder: String,
+    pub amount: u64,
+    /// Hex-encoded 32-byte nonce.
+    pub nonce: String,
+}
+
+impl StoredBid {
+    pub fn new(auction: &Pubkey, bidder: &Pubkey, amount: u64, nonce: &[u8; 32]) -> Self {
+        Self {
+            auction: auction.to_string(),
+            bidder: bidder.to_string(),
+            amount,
+            nonce: hex::encode(nonce),
+        }
+    }
+
+    pub fn nonce(&self) -> Result<[u8; 32]> {
+        let bytes = hex::decode(&self.nonce).context("stored nonce is not hex")?;
+        bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("stored nonce is not 32 bytes"))
+    }
+}
+
+pub struct BidStore {
+    dir: PathBuf,
+}
+
+impl BidStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, auction: &Pubkey, bidder: &Pubkey) -> PathBuf {
+        self.dir.join(format!("{auction}-{bidder}.json"))
+    }
+
+    /// Refuses to replace an existing secret unless `force`, since it may belong to a
+    /// bid that is already committed on-chain.
+    pub fn save(
+        &self,
+        bid: &StoredBid,
+        auction: &Pubkey,
+        bidder: &Pubkey,
+        force: bool,
+    ) -> Result<PathBuf> {
+        create_private_dir(&self.dir)?;
+        let path = self.path(auction, bidder);
+        if path.exists() && !force {
+            bail!(
+                "{} already holds a bid secret; pass --force to replace it",
+                path.display()
+            );
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("cannot write {}", path.display()))?;
+        file.write_all(&serde_json::to_vec_pretty(bid)?)?;
+        file.sync_all()?;
+        Ok(path)
+    }
+
+    pub fn load(&self, auction: &Pubkey, bidder: &Pubkey) -> Result<StoredBid> {
+        let path = self.path(auction, bidder);
+        let data = fs::read(&path)
+            .with_context(|| format!("no stored bid secret at {}", path.display()))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
+
+fn create_private_dir(dir: &PathBuf) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(dir)
+        .with_context(|| format!("cannot create {}", dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_round_trip_and_are_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BidStore::new(dir.path().join("bids"));
+        let (auction, bidder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let bid = StoredBid::new(&auction, &bidder, 100, &[9; 32]);
+
+        let path = store.save(&bid, &auction, &bidder, false).unwrap();
+        assert_eq!(store.load(&auction, &bidder).unwrap(), bid);
+        assert_eq!(bid.nonce().unwrap(), [9; 32]);
+        assert!(store.save(&bid, &auction, &bidder, false).is_err());
+        assert!(store.save(&bid, &auction, &bidder, true).is_ok());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
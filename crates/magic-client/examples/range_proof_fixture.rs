@@ -0,0 +1,201 @@
+//! Generates `programs/magic/fixtures/range_proof.json`, the Groth16 range proof the
+//! program's verifier is tested against:
+//!
+//! ```sh
+//! cargo run -p magic-client --example range_proof_fixture > programs/magic/fixtures/range_proof.json
+//! ```
+//!
+//! The circuit proves, for a V2 Poseidon bid commitment, that the committed amount lies
+//! in `[reserve_price, deposit_amount]`. Public inputs are `[bid_hash, reserve_price,
+//! deposit_amount]`, as `helpers::range_proof_public_inputs` lays them out; the whole
+//! commitment preimage is private. Setup and proving use a fixed seed, so the output is
+//! reproducible.
+
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::Groth16;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use light_poseidon::parameters::bn254_x5::get_poseidon_parameters;
+use magic::{
+    helpers::{compute_bid_commitment, poseidon_commitment_inputs},
+    CommitmentHash, CommitmentScheme,
+};
+use serde_json::json;
+
+/// Bits the two range differences are decomposed into; amounts are `u64`.
+const AMOUNT_BITS: usize = 64;
+
+#[derive(Clone)]
+struct RangeCircuit {
+    /// Field elements of the commitment preimage, amount second to last.
+    preimage: Vec<Fr>,
+    amount: u64,
+    bid_hash: Fr,
+    reserve_price: u64,
+    deposit_amount: u64,
+}
+
+impl ConstraintSynthesizer<Fr> for RangeCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let bid_hash = FpVar::new_input(cs.clone(), || Ok(self.bid_hash))?;
+        let reserve_price = FpVar::new_input(cs.clone(), || Ok(Fr::from(self.reserve_price)))?;
+        let deposit_amount = FpVar::new_input(cs.clone(), || Ok(Fr::from(self.deposit_amount)))?;
+
+        let preimage = self
+            .preimage
+            .iter()
+            .map(|input| FpVar::new_witness(cs.clone(), || Ok(*input)))
+            .collect::<Result<Vec<_>, _>>()?;
+        poseidon(&preimage)?.enforce_equal(&bid_hash)?;
+
+        let amount = &preimage[preimage.len() - 2];
+        enforce_u64(
+            cs.clone(),
+            amount - &reserve_price,
+            self.amount.wrapping_sub(self.reserve_price),
+        )?;
+        enforce_u64(
+            cs,
+            &deposit_amount - amount,
+            self.deposit_amount.wrapping_sub(self.amount),
+        )
+    }
+}
+
+/// Constrains `value` to `[0, 2^64)` by decomposing the witness `bits` into booleans.
+fn enforce_u64(
+    cs: ConstraintSystemRef<Fr>,
+    value: FpVar<Fr>,
+    bits: u64,
+) -> Result<(), SynthesisError> {
+    let bits = (0..AMOUNT_BITS)
+        .map(|i| Boolean::new_witness(cs.clone(), || Ok(bits >> i & 1 == 1)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(&value)
+}
+
+/// Circom Poseidon (BN254, x^5), the permutation `light_poseidon` and the `sol_poseidon`
+/// syscall compute, with a zero domain tag in the first state element.
+fn poseidon(inputs: &[FpVar<Fr>]) -> Result<FpVar<Fr>, SynthesisError> {
+    let params = get_poseidon_parameters::<Fr>(inputs.len() as u8 + 1)
+        .map_err(|_| SynthesisError::Unsatisfiable)?;
+    let width = params.width;
+    let half_full = params.full_rounds / 2;
+
+    let mut state: Vec<FpVar<Fr>> = std::iter::once(FpVar::zero())
+        .chain(inputs.iter().cloned())
+        .collect();
+    for round in 0..params.full_rounds + params.partial_rounds {
+        for (i, element) in state.iter_mut().enumerate() {
+            *element += params.ark[round * width + i];
+        }
+        let full = round < half_full || round >= half_full + params.partial_rounds;
+        for element in state.iter_mut().take(if full { width } else { 1 }) {
+            let square = element.square()?;
+            *element = square.square()? * &*element;
+        }
+        state = params
+            .mds
+            .iter()
+            .map(|row| {
+                state
+                    .iter()
+                    .zip(row)
+                    .fold(FpVar::zero(), |sum, (element, m)| sum + element * *m)
+            })
+            .collect();
+    }
+    Ok(state.swap_remove(0))
+}
+
+fn field_hex(element: &Fq) -> Vec<u8> {
+    element.into_bigint().to_bytes_be()
+}
+
+fn g1_hex(point: &G1Affine) -> String {
+    hex::encode([field_hex(&point.x), field_hex(&point.y)].concat())
+}
+
+/// The alt_bn128 syscalls take G2 coordinates imaginary part first.
+fn g2_hex(point: &G2Affine) -> String {
+    hex::encode(
+        [
+            field_hex(&point.x.c1),
+            field_hex(&point.x.c0),
+            field_hex(&point.y.c1),
+            field_hex(&point.y.c0),
+        ]
+        .concat(),
+    )
+}
+
+fn main() {
+    let auction = Pubkey::new_from_array([2; 32]);
+    let bidder = Pubkey::new_from_array([1; 32]);
+    let creation_slot = 42;
+    let amount = 120;
+    let nonce = [7; 32];
+    let (reserve_price, deposit_amount) = (50, 200);
+
+    let scheme = CommitmentScheme::V2;
+    let bid_hash = compute_bid_commitment(
+        &CommitmentHash::Poseidon,
+        &scheme,
+        amount,
+        &nonce,
+        &bidder,
+        &auction,
+        creation_slot,
+    )
+    .expect("Poseidon commitment");
+    let preimage =
+        poseidon_commitment_inputs(&scheme, amount, &nonce, &bidder, &auction, creation_slot)
+            .iter()
+            .map(|input| Fr::from_be_bytes_mod_order(input))
+            .collect();
+    let circuit = RangeCircuit {
+        preimage,
+        amount,
+        bid_hash: Fr::from_be_bytes_mod_order(&bid_hash),
+        reserve_price,
+        deposit_amount,
+    };
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let (pk, vk) = Groth16::<Bn254>::setup(circuit.clone(), &mut rng).expect("setup");
+    let proof = Groth16::<Bn254>::prove(&pk, circuit.clone(), &mut rng).expect("prove");
+    let public_inputs = [
+        circuit.bid_hash,
+        Fr::from(reserve_price),
+        Fr::from(deposit_amount),
+    ];
+    assert!(Groth16::<Bn254>::verify(&vk, &public_inputs, &proof).expect("verify"));
+
+    let fixture = json!({
+        "auction": hex::encode(auction),
+        "bidder": hex::encode(bidder),
+        "creation_slot": creation_slot,
+        "amount": amount,
+        "nonce": hex::encode(nonce),
+        "reserve_price": reserve_price,
+        "deposit_amount": deposit_amount,
+        "bid_hash": hex::encode(bid_hash),
+        "vk": {
+            "alpha_g1": g1_hex(&vk.alpha_g1),
+            "beta_g2": g2_hex(&vk.beta_g2),
+            "gamma_g2": g2_hex(&vk.gamma_g2),
+            "delta_g2": g2_hex(&vk.delta_g2),
+            "ic": vk.gamma_abc_g1.iter().map(g1_hex).collect::<Vec<_>>(),
+        },
+        "proof": {
+            "a": g1_hex(&proof.a),
+            "b": g2_hex(&proof.b),
+            "c": g1_hex(&proof.c),
+        },
+    });
+    println!("{}", serde_json::to_string_pretty(&fixture).unwrap());
+}
@@ -0,0 +1,54 @@
+use anchor_lang::{AccountDeserialize, Result};
+use magic::{AuctionStatus, BidMode, CommitmentHash, CommitmentScheme};
+
+pub use magic::state::{Auction, Bid, Deposit, RangeProofKey};
+
+/// Decodes raw account data (discriminator included) as returned by `getAccountInfo`.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_auction(data: &[u8]) -> Result<Auction> {
+    decode(data)
+}
+
+pub fn decode_bid(data: &[u8]) -> Result<Bid> {
+    decode(data)
+}
+
+/// Lower-case names of the program's enums, as the CLI prints and the indexer stores them.
+pub fn status_name(status: &AuctionStatus) -> &'static str {
+    match status {
+        AuctionStatus::Scheduled => "scheduled",
+        AuctionStatus::Bidding => "bidding",
+        AuctionStatus::Revealing => "revealing",
+        AuctionStatus::Finalized => "finalized",
+        AuctionStatus::Settled => "settled",
+        AuctionStatus::Cancelled => "cancelled",
+    }
+}
+
+pub fn bid_mode_name(mode: &BidMode) -> &'static str {
+    match mode {
+        BidMode::Sealed => "sealed",
+        BidMode::Private => "private",
+        BidMode::Encrypted => "encrypted",
+    }
+}
+
+pub fn scheme_name(scheme: &CommitmentScheme) -> &'static str {
+    match scheme {
+        CommitmentScheme::Legacy => "legacy",
+        CommitmentScheme::V1 => "v1",
+        CommitmentScheme::V2 => "v2",
+    }
+}
+
+pub fn hash_name(hash: &CommitmentHash) -> &'static str {
+    match hash {
+        CommitmentHash::Sha256 => "sha256",
+        CommitmentHash::Keccak256 => "keccak256",
+        CommitmentHash::Poseidon => "poseidon",
+        CommitmentHash::Pedersen => "pedersen",
+    }
+}
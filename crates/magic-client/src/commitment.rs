@@ -0,0 +1,147 @@
+use anchor_lang::prelude::{Pubkey, Result};
+use curve25519_dalek::{constants::RISTRETTO_BASEPOINT_POINT, RistrettoPoint, Scalar};
+use magic::{
+    constant::{OPENING_PROOF_DOMAIN, PEDERSEN_H_DOMAIN},
+    error::AuctionError,
+    helpers::opening_challenge,
+};
+use sha2::{Digest, Sha512};
+
+pub use magic::helpers::{
+    compute_bid_commitment, compute_bid_hash, compute_bid_hash_v1, derive_bid_nonce,
+    nonce_derivation_message, pedersen_commitment, signed_bid_message,
+};
+
+use crate::{account::Auction, BidMode, BidProof, CommitmentHash, OpeningProof};
+
+/// Commitment to submit for a bid on `auction`, using the scheme and hash the auction
+/// was created with.
+pub fn seal_bid(
+    auction: &Auction,
+    auction_key: &Pubkey,
+    bidder: &Pubkey,
+    amount: u64,
+    nonce: &[u8; 32],
+) -> Result<[u8; 32]> {
+    compute_bid_commitment(
+        &auction.commitment_hash,
+        &auction.commitment_scheme,
+        amount,
+        nonce,
+        bidder,
+        auction_key,
+        auction.creation_slot,
+    )
+}
+
+/// Proof to send along with a sealed bid sealed by `seal_bid`: an opening proof on
+/// Pedersen auctions, `None` elsewhere. Range proofs come from the auction's circuit.
+pub fn bid_proof(
+    auction: &Auction,
+    auction_key: &Pubkey,
+    bidder: &Pubkey,
+    amount: u64,
+    nonce: &[u8; 32],
+) -> Result<Option<BidProof>> {
+    if auction.bid_mode != BidMode::Sealed || auction.commitment_hash != CommitmentHash::Pedersen {
+        return Ok(None);
+    }
+    prove_opening(auction_key, bidder, amount, nonce).map(|proof| Some(BidProof::Opening(proof)))
+}
+
+/// Proves knowledge of the opening `(amount, blinding)` of a Pedersen bid commitment.
+/// The proof nonces are derived from the opening, so the same bid always gets the same
+/// proof and no randomness is needed.
+pub fn prove_opening(
+    auction: &Pubkey,
+    bidder: &Pubkey,
+    amount: u64,
+    blinding: &[u8; 32],
+) -> Result<OpeningProof> {
+    let blinding = Option::<Scalar>::from(Scalar::from_canonical_bytes(*blinding))
+        .ok_or(AuctionError::InvalidPedersenCommitment)?;
+    let amount = Scalar::from(amount);
+    let generator = RistrettoPoint::hash_from_bytes::<Sha512>(PEDERSEN_H_DOMAIN);
+    let commit = |amount: Scalar, blinding: Scalar| {
+        (amount * RISTRETTO_BASEPOINT_POINT + blinding * generator)
+            .compress()
+            .to_bytes()
+    };
+    let proof_nonce = |tag: u8| {
+        Scalar::from_hash(
+            Sha512::new()
+                .chain_update(OPENING_PROOF_DOMAIN)
+                .chain_update([tag])
+                .chain_update(auction)
+                .chain_update(bidder)
+                .chain_update(amount.as_bytes())
+                .chain_update(blinding.as_bytes()),
+        )
+    };
+    let (amount_nonce, blinding_nonce) = (proof_nonce(0), proof_nonce(1));
+
+    let nonce_commitment = commit(amount_nonce, blinding_nonce);
+    let challenge = Scalar::from_bytes_mod_order(opening_challenge(
+        &commit(amount, blinding),
+        &nonce_commitment,
+        auction,
+        bidder,
+    ));
+    Ok(OpeningProof {
+        nonce_commitment,
+        amount_response: (amount_nonce + challenge * amount).to_bytes(),
+        blinding_response: (blinding_nonce + challenge * blinding).to_bytes(),
+    })
+}
+
+/// The blinding that opens the sum of Pedersen commitments made with `blindings`, as
+/// `open_aggregate_demand` takes it.
+pub fn sum_blindings(blindings: &[[u8; 32]]) -> Result<[u8; 32]> {
+    let sum = blindings.iter().try_fold(Scalar::ZERO, |sum, blinding| {
+        Option::<Scalar>::from(Scalar::from_canonical_bytes(*blinding))
+            .map(|blinding| sum + blinding)
+            .ok_or(AuctionError::InvalidPedersenCommitment)
+    })?;
+    Ok(sum.to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use magic::helpers::{add_pedersen_commitments, verify_opening_proof};
+
+    use super::*;
+
+    #[test]
+    fn opening_proofs_verify_only_for_their_commitment_and_bidder() {
+        let (auction, bidder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let blinding = [3; 32];
+        let commitment = pedersen_commitment(250, &blinding).unwrap();
+        let proof = prove_opening(&auction, &bidder, 250, &blinding).unwrap();
+        verify_opening_proof(&commitment, &proof, &auction, &bidder).unwrap();
+
+        // Copying someone else's commitment and proof fails for another bidder.
+        let copier = Pubkey::new_unique();
+        assert!(verify_opening_proof(&commitment, &proof, &auction, &copier).is_err());
+
+        let other = pedersen_commitment(251, &blinding).unwrap();
+        assert!(verify_opening_proof(&other, &proof, &auction, &bidder).is_err());
+
+        let mut forged = proof;
+        forged.amount_response[0] ^= 1;
+        assert!(verify_opening_proof(&commitment, &forged, &auction, &bidder).is_err());
+    }
+
+    #[test]
+    fn summed_blindings_open_the_summed_commitments() {
+        let mut second = [0xff; 32];
+        second[31] = 0x0f;
+        let sum = add_pedersen_commitments(
+            &pedersen_commitment(250, &[3; 32]).unwrap(),
+            &pedersen_commitment(100, &second).unwrap(),
+        )
+        .unwrap();
+        let blinding = sum_blindings(&[[3; 32], second]).unwrap();
+        assert_eq!(pedersen_commitment(350, &blinding).unwrap(), sum);
+        assert!(sum_blindings(&[[0xff; 32]]).is_err());
+    }
+}
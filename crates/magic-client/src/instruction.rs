@@ -0,0 +1,901 @@
+//! One builder per program entrypoint. PDAs are derived from the identities passed in;
+//! optional accounts the program accepts are filled in where the arguments imply them.
+//! Builders that touch a bid take the auction's `creation_slot` as `auction_slot`.
+
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::instruction::Instruction,
+    system_program, InstructionData, ToAccountMetas,
+};
+use ephemeral_rollups_sdk::consts::{MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID, PERMISSION_PROGRAM_ID};
+use magic::{accounts, instruction as ix};
+use solana_sdk_ids::sysvar::instructions::ID as INSTRUCTIONS_SYSVAR_ID;
+
+use crate::pda::{
+    action_escrow_pda, auction_pda, bid_pda, delegation_pdas, deposit_pda, permission_pda,
+    range_proof_key_pda,
+};
+use crate::{AuctionOptions, BidCiphertext, BidProof, BidReveal, RangeProofVerifyingKey};
+
+/// Ed25519 program instruction carrying `signer`'s `signature` over `message`, in the
+/// single-signature layout the program reads: header, public key, signature, message.
+/// Place it right before `submit_sealed_bid_signed` or a `derived_nonce` reveal; a
+/// `derived_nonce` batch takes one per reveal, in order, right before the batch.
+pub fn ed25519_verify(signer: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    const PUBLIC_KEY_OFFSET: u16 = 16;
+    const SIGNATURE_OFFSET: u16 = PUBLIC_KEY_OFFSET + 32;
+    const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+    const SAME_INSTRUCTION: u16 = u16::MAX;
+
+    let mut data = vec![1_u8, 0];
+    for field in [
+        SIGNATURE_OFFSET,
+        SAME_INSTRUCTION,
+        PUBLIC_KEY_OFFSET,
+        SAME_INSTRUCTION,
+        MESSAGE_OFFSET,
+        message.len() as u16,
+        SAME_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: solana_sdk_ids::ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: magic::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Appends each bidder's writable bid account followed by its deposit PDA, which the
+/// batch instructions read as remaining accounts.
+fn with_bids(
+    mut instruction: Instruction,
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidders: &[Pubkey],
+) -> Instruction {
+    instruction
+        .accounts
+        .extend(bidders.iter().flat_map(|bidder| {
+            [
+                AccountMeta::new(bid_pda(auction, auction_slot, bidder).0, false),
+                AccountMeta::new_readonly(deposit_pda(auction, bidder).0, false),
+            ]
+        }));
+    instruction
+}
+
+/// Appends each bidder's writable bid account followed by its permission PDA, which the
+/// undelegating instructions read as remaining accounts.
+fn with_bid_permissions(
+    mut instruction: Instruction,
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidders: &[Pubkey],
+) -> Instruction {
+    instruction
+        .accounts
+        .extend(bidders.iter().flat_map(|bidder| {
+            let bid = bid_pda(auction, auction_slot, bidder).0;
+            [
+                AccountMeta::new(bid, false),
+                AccountMeta::new(permission_pda(&bid).0, false),
+            ]
+        }));
+    instruction
+}
+
+/// Range proofs are checked against the registered key and the bidder's deposit.
+fn proof_accounts(
+    auction: &Pubkey,
+    bidder: &Pubkey,
+    proof: Option<&BidProof>,
+) -> (Option<Pubkey>, Option<Pubkey>) {
+    match proof {
+        Some(BidProof::Range(_)) => (
+            Some(range_proof_key_pda(auction).0),
+            Some(deposit_pda(auction, bidder).0),
+        ),
+        _ => (None, None),
+    }
+}
+
+fn permission_accounts(account: &Pubkey, enabled: bool) -> (Option<Pubkey>, Option<Pubkey>) {
+    if enabled {
+        (Some(permission_pda(account).0), Some(PERMISSION_PROGRAM_ID))
+    } else {
+        (None, None)
+    }
+}
+
+pub fn create_auction(
+    authority: &Pubkey,
+    auction_id: u64,
+    start_ts: i64,
+    end_ts: i64,
+    reveal_end_ts: i64,
+    reserve_price: u64,
+    options: AuctionOptions,
+) -> Instruction {
+    build(
+        accounts::CreateAuction {
+            auction: auction_pda(authority, auction_id).0,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        ix::CreateAuction {
+            auction_id,
+            start_ts,
+            end_ts,
+            reveal_end_ts,
+            reserve_price,
+            options,
+        },
+    )
+}
+
+pub fn set_range_proof_key(
+    auction: &Pubkey,
+    authority: &Pubkey,
+    vk: RangeProofVerifyingKey,
+) -> Instruction {
+    build(
+        accounts::SetRangeProofKey {
+            auction: *auction,
+            range_proof_key: range_proof_key_pda(auction).0,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        ix::SetRangeProofKey { vk },
+    )
+}
+
+pub fn submit_sealed_bid(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    bid_hash: [u8; 32],
+    proof: Option<BidProof>,
+) -> Instruction {
+    let (range_proof_key, deposit) = proof_accounts(auction, bidder, proof.as_ref());
+    build(
+        accounts::SubmitSealedBid {
+            auction: *auction,
+            bid: bid_pda(auction, auction_slot, bidder).0,
+            bidder: *bidder,
+            range_proof_key,
+            deposit,
+            system_program: system_program::ID,
+        },
+        ix::SubmitSealedBid { bid_hash, proof },
+    )
+}
+
+/// Must be preceded by an Ed25519 program instruction in which `bidder` signs
+/// `commitment::signed_bid_message(auction, auction_slot, bid_hash)`.
+pub fn submit_sealed_bid_signed(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    relayer: &Pubkey,
+    bid_hash: [u8; 32],
+    proof: Option<BidProof>,
+) -> Instruction {
+    let (range_proof_key, deposit) = proof_accounts(auction, bidder, proof.as_ref());
+    build(
+        accounts::SubmitSealedBidSigned {
+            auction: *auction,
+            bid: bid_pda(auction, auction_slot, bidder).0,
+            bidder: *bidder,
+            relayer: *relayer,
+            range_proof_key,
+            deposit,
+            instructions: INSTRUCTIONS_SYSVAR_ID,
+            system_program: system_program::ID,
+        },
+        ix::SubmitSealedBidSigned { bid_hash, proof },
+    )
+}
+
+pub fn initialize_bid_account(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::InitializeBidAccount {
+            auction: *auction,
+            bid: bid_pda(auction, auction_slot, bidder).0,
+            bidder: *bidder,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        ix::InitializeBidAccount {},
+    )
+}
+
+/// Sent to the ER once the bid is delegated.
+pub fn submit_sealed_bid_delegated(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    bid_hash: [u8; 32],
+    amount: Option<u64>,
+    proof: Option<BidProof>,
+) -> Instruction {
+    let (range_proof_key, deposit) = proof_accounts(auction, bidder, proof.as_ref());
+    build(
+        accounts::SubmitSealedBidDelegated {
+            auction: *auction,
+            bid: bid_pda(auction, auction_slot, bidder).0,
+            bidder: *bidder,
+            range_proof_key,
+            deposit,
+        },
+        ix::SubmitSealedBidDelegated {
+            bid_hash,
+            amount,
+            proof,
+        },
+    )
+}
+
+/// Always passes the instructions sysvar, so `derived_nonce` reveals only need the
+/// Ed25519 instruction placed in front. `publish_permission` makes the bid permission
+/// public in the same call.
+pub fn reveal_bid(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    amount: u64,
+    nonce: [u8; 32],
+    publish_permission: bool,
+) -> Instruction {
+    let bid = bid_pda(auction, auction_slot, bidder).0;
+    let (permission, permission_program) = permission_accounts(&bid, publish_permission);
+    build(
+        accounts::RevealBid {
+            auction: *auction,
+            bid,
+            bidder: *bidder,
+            deposit: deposit_pda(auction, bidder).0,
+            permission,
+            permission_program,
+            instructions: Some(INSTRUCTIONS_SYSVAR_ID),
+        },
+        ix::RevealBid { amount, nonce },
+    )
+}
+
+pub fn reveal_bid_for(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    revealer: &Pubkey,
+    amount: u64,
+    nonce: [u8; 32],
+    publish_permission: bool,
+) -> Instruction {
+    let bid = bid_pda(auction, auction_slot, bidder).0;
+    let (permission, permission_program) = permission_accounts(&bid, publish_permission);
+    build(
+        accounts::RevealBidFor {
+            auction: *auction,
+            bid,
+            revealer: *revealer,
+            deposit: deposit_pda(auction, bidder).0,
+            permission,
+            permission_program,
+            instructions: Some(INSTRUCTIONS_SYSVAR_ID),
+        },
+        ix::RevealBidFor { amount, nonce },
+    )
+}
+
+/// `reveals[i]` opens the bid of `bidders[i]`. Passes the instructions sysvar like
+/// `reveal_bid`, for the nonce signatures of `derived_nonce` auctions.
+pub fn reveal_bids_batch(
+    auction: &Pubkey,
+    auction_slot: u64,
+    revealer: &Pubkey,
+    bidders: &[Pubkey],
+    reveals: Vec<BidReveal>,
+) -> Instruction {
+    with_bids(
+        build(
+            accounts::RevealBidsBatch {
+                auction: *auction,
+                revealer: *revealer,
+                instructions: Some(INSTRUCTIONS_SYSVAR_ID),
+            },
+            ix::RevealBidsBatch { reveals },
+        ),
+        auction,
+        auction_slot,
+        bidders,
+    )
+}
+
+pub fn set_reveal_agent(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    agent: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::SetRevealAgent {
+            bid: bid_pda(auction, auction_slot, bidder).0,
+            bidder: *bidder,
+        },
+        ix::SetRevealAgent { agent },
+    )
+}
+
+pub fn finalize_auction(auction: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::FinalizeAuction {
+            auction: *auction,
+            authority: *authority,
+        },
+        ix::FinalizeAuction {},
+    )
+}
+
+pub fn cancel_auction(auction: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelAuction {
+            auction: *auction,
+            authority: *authority,
+        },
+        ix::CancelAuction {},
+    )
+}
+
+/// Sent to the ER; the bids of `bidders` are resolved in this call.
+pub fn resolve_private_auction(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidders: &[Pubkey],
+) -> Instruction {
+    with_bids(
+        build(
+            accounts::ResolvePrivateAuction { auction: *auction },
+            ix::ResolvePrivateAuction {},
+        ),
+        auction,
+        auction_slot,
+        bidders,
+    )
+}
+
+pub fn submit_encrypted_bid(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    ciphertext: BidCiphertext,
+) -> Instruction {
+    build(
+        accounts::SubmitEncryptedBid {
+            auction: *auction,
+            bid: bid_pda(auction, auction_slot, bidder).0,
+            bidder: *bidder,
+        },
+        ix::SubmitEncryptedBid { ciphertext },
+    )
+}
+
+pub fn decrypt_and_reveal(
+    auction: &Pubkey,
+    auction_slot: u64,
+    secret_key: [u8; 32],
+    bidders: &[Pubkey],
+) -> Instruction {
+    with_bids(
+        build(
+            accounts::DecryptAndReveal { auction: *auction },
+            ix::DecryptAndReveal { secret_key },
+        ),
+        auction,
+        auction_slot,
+        bidders,
+    )
+}
+
+pub fn open_aggregate_demand(auction: &Pubkey, total: u64, blinding: [u8; 32]) -> Instruction {
+    build(
+        accounts::OpenAggregateDemand { auction: *auction },
+        ix::OpenAggregateDemand { total, blinding },
+    )
+}
+
+pub fn create_auction_permission(auction: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::CreateAuctionPermission {
+            auction: *auction,
+            permission: permission_pda(auction).0,
+            payer: *payer,
+            permission_program: PERMISSION_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        ix::CreateAuctionPermission {},
+    )
+}
+
+pub fn create_bid_permission(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let bid = bid_pda(auction, auction_slot, bidder).0;
+    build(
+        accounts::CreateBidPermission {
+            bid,
+            permission: permission_pda(&bid).0,
+            payer: *payer,
+            permission_program: PERMISSION_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        ix::CreateBidPermission {},
+    )
+}
+
+pub fn publish_bid_permission(auction: &Pubkey, auction_slot: u64, bidder: &Pubkey) -> Instruction {
+    let bid = bid_pda(auction, auction_slot, bidder).0;
+    build(
+        accounts::PublishBidPermission {
+            auction: *auction,
+            bid,
+            permission: permission_pda(&bid).0,
+            permission_program: PERMISSION_PROGRAM_ID,
+        },
+        ix::PublishBidPermission {},
+    )
+}
+
+pub fn close_auction_permission(auction: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseAuctionPermission {
+            auction: *auction,
+            permission: permission_pda(auction).0,
+            authority: *authority,
+            permission_program: PERMISSION_PROGRAM_ID,
+        },
+        ix::CloseAuctionPermission {},
+    )
+}
+
+pub fn close_bid_permission(auction: &Pubkey, auction_slot: u64, bidder: &Pubkey) -> Instruction {
+    let bid = bid_pda(auction, auction_slot, bidder).0;
+    build(
+        accounts::CloseBidPermission {
+            auction: *auction,
+            bid,
+            permission: permission_pda(&bid).0,
+            bidder: *bidder,
+            permission_program: PERMISSION_PROGRAM_ID,
+        },
+        ix::CloseBidPermission {},
+    )
+}
+
+pub fn delegate_auction(
+    payer: &Pubkey,
+    authority: &Pubkey,
+    auction_id: u64,
+    validator: Option<Pubkey>,
+) -> Instruction {
+    let auction = auction_pda(authority, auction_id).0;
+    let delegation = delegation_pdas(&auction, &magic::ID);
+    build(
+        accounts::DelegateAuction {
+            payer: *payer,
+            validator,
+            auction,
+            buffer_auction: delegation.buffer,
+            delegation_record_auction: delegation.record,
+            delegation_metadata_auction: delegation.metadata,
+            owner_program: magic::ID,
+            delegation_program: ephemeral_rollups_sdk::id(),
+            system_program: system_program::ID,
+        },
+        ix::DelegateAuction {
+            authority: *authority,
+            auction_id,
+        },
+    )
+}
+
+pub fn delegate_bid(
+    payer: &Pubkey,
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    validator: Option<Pubkey>,
+) -> Instruction {
+    let bid = bid_pda(auction, auction_slot, bidder).0;
+    let delegation = delegation_pdas(&bid, &magic::ID);
+    build(
+        accounts::DelegateBid {
+            payer: *payer,
+            validator,
+            bid,
+            buffer_bid: delegation.buffer,
+            delegation_record_bid: delegation.record,
+            delegation_metadata_bid: delegation.metadata,
+            owner_program: magic::ID,
+            delegation_program: ephemeral_rollups_sdk::id(),
+            system_program: system_program::ID,
+        },
+        ix::DelegateBid {
+            auction: *auction,
+            auction_slot,
+            bidder: *bidder,
+        },
+    )
+}
+
+pub fn prepare_private_bid(
+    auction: &Pubkey,
+    auction_slot: u64,
+    bidder: &Pubkey,
+    payer: &Pubkey,
+    validator: Option<Pubkey>,
+) -> Instruction {
+    let bid = bid_pda(auction, auction_slot, bidder).0;
+    let delegation = delegation_pdas(&bid, &magic::ID);
+    let permission = permission_pda(&bid).0;
+    let permission_delegation = delegation_pdas(&permission, &PERMISSION_PROGRAM_ID);
+    build(
+        accounts::PreparePrivateBid {
+            auction: *auction,
+            bid,
+            bidder: *bidder,
+            payer: *payer,
+            permission,
+            permission_delegation_buffer: permission_delegation.buffer,
+            permission_delegation_record: permission_delegation.record,
+            permission_delegation_metadata: permission_delegation.metadata,
+            permission_program: PERMISSION_PROGRAM_ID,
+            validator,
+            buffer_bid: delegation.buffer,
+            delegation_record_bid: delegation.record,
+            delegation_metadata_bid: delegation.metadata,
+            owner_program: magic::ID,
+            delegation_program: ephemeral_rollups_sdk::id(),
+            system_program: system_program::ID,
+        },
+        ix::PreparePrivateBid {},
+    )
+}
+
+pub fn prepare_private_auction(
+    authority: &Pubkey,
+    auction_id: u64,
+    validator: Option<Pubkey>,
+) -> Instruction {
+    let auction = auction_pda(authority, auction_id).0;
+    let delegation = delegation_pdas(&auction, &magic::ID);
+    let permission = permission_pda(&auction).0;
+    let permission_delegation = delegation_pdas(&permission, &PERMISSION_PROGRAM_ID);
+    build(
+        accounts::PreparePrivateAuction {
+            authority: *authority,
+            auction,
+            permission,
+            permission_delegation_buffer: permission_delegation.buffer,
+            permission_delegation_record: permission_delegation.record,
+            permission_delegation_metadata: permission_delegation.metadata,
+            permission_program: PERMISSION_PROGRAM_ID,
+            validator,
+            buffer_auction: delegation.buffer,
+            delegation_record_auction: delegation.record,
+            delegation_metadata_auction: delegation.metadata,
+            owner_program: magic::ID,
+            delegation_program: ephemeral_rollups_sdk::id(),
+            system_program: system_program::ID,
+        },
+        ix::PreparePrivateAuction { auction_id },
+    )
+}
+
+/// Sent to the ER. `with_permission` also returns the auction permission to L1, and the
+/// `bidders`' delegated bids and their permissions are undelegated along with the auction.
+pub fn finalize_and_settle(
+    auction: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    with_permission: bool,
+    auction_slot: u64,
+    bidders: &[Pubkey],
+) -> Instruction {
+    let instruction = build(
+        accounts::FinalizeAndSettle {
+            auction: *auction,
+            authority: *authority,
+            payer: *payer,
+            permission: with_permission.then(|| permission_pda(auction).0),
+            permission_program: (with_permission || !bidders.is_empty())
+                .then_some(PERMISSION_PROGRAM_ID),
+            magic_program: MAGIC_PROGRAM_ID,
+            magic_context: MAGIC_CONTEXT_ID,
+        },
+        ix::FinalizeAndSettle {},
+    );
+    with_bid_permissions(instruction, auction, auction_slot, bidders)
+}
+
+/// Sent to the ER once the auction is closed: undelegates the `bidders`' delegated bids
+/// and their permissions.
+pub fn undelegate_bids(
+    auction: &Pubkey,
+    payer: &Pubkey,
+    auction_slot: u64,
+    bidders: &[Pubkey],
+) -> Instruction {
+    with_bid_permissions(
+        build(
+            accounts::UndelegateBids {
+                auction: *auction,
+                payer: *payer,
+                permission_program: PERMISSION_PROGRAM_ID,
+                magic_program: MAGIC_PROGRAM_ID,
+                magic_context: MAGIC_CONTEXT_ID,
+            },
+            ix::UndelegateBids {},
+        ),
+        auction,
+        auction_slot,
+        bidders,
+    )
+}
+
+pub fn deposit_escrow(auction: &Pubkey, bidder: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::DepositEscrow {
+            auction: *auction,
+            deposit: deposit_pda(auction, bidder).0,
+            bidder: *bidder,
+            system_program: system_program::ID,
+        },
+        ix::DepositEscrow { amount },
+    )
+}
+
+pub fn withdraw_deposit(auction: &Pubkey, bidder: &Pubkey) -> Instruction {
+    build(
+        accounts::WithdrawDeposit {
+            auction: *auction,
+            deposit: deposit_pda(auction, bidder).0,
+            bidder: *bidder,
+        },
+        ix::WithdrawDeposit {},
+    )
+}
+
+/// Normally scheduled by `finalize_and_settle`; `winner` is the auction's highest bidder
+/// and `escrow_authority` pays for the manual call. The auction permission and the
+/// `bidders`' bid permissions are closed if they are back on L1.
+pub fn settle_auction(
+    auction: &Pubkey,
+    authority: &Pubkey,
+    winner: Option<Pubkey>,
+    escrow_authority: &Pubkey,
+    auction_slot: u64,
+    bidders: &[Pubkey],
+) -> Instruction {
+    let mut instruction = build(
+        accounts::SettleAuction {
+            auction: *auction,
+            winner_deposit: winner.map(|winner| deposit_pda(auction, &winner).0),
+            authority: *authority,
+            permission: Some(permission_pda(auction).0),
+            permission_program: Some(PERMISSION_PROGRAM_ID),
+            escrow_auth: *escrow_authority,
+            escrow: action_escrow_pda(escrow_authority, 0),
+        },
+        ix::SettleAuction {},
+    );
+    instruction
+        .accounts
+        .extend(bidders.iter().flat_map(|bidder| {
+            let bid = bid_pda(auction, auction_slot, bidder).0;
+            [
+                AccountMeta::new(bid, false),
+                AccountMeta::new(permission_pda(&bid).0, false),
+                AccountMeta::new(*bidder, false),
+            ]
+        }));
+    instruction
+}
+
+pub fn schedule_auto_finalize(
+    auction: &Pubkey,
+    authority: &Pubkey,
+    task_context: &Pubkey,
+    task_id: u64,
+) -> Instruction {
+    build(
+        accounts::ScheduleAutoFinalize {
+            auction: *auction,
+            authority: *authority,
+            task_context: *task_context,
+            magic_context: MAGIC_CONTEXT_ID,
+            magic_program: MAGIC_PROGRAM_ID,
+        },
+        ix::ScheduleAutoFinalize { task_id },
+    )
+}
+
+pub fn cancel_auto_finalize(
+    auction: &Pubkey,
+    authority: &Pubkey,
+    task_context: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CancelAutoFinalize {
+            auction: *auction,
+            authority: *authority,
+            task_context: *task_context,
+            magic_program: MAGIC_PROGRAM_ID,
+        },
+        ix::CancelAutoFinalize {},
+    )
+}
+
+pub fn auto_finalize(auction: &Pubkey) -> Instruction {
+    build(
+        accounts::AutoFinalize {
+            auction: *auction,
+            permission: permission_pda(auction).0,
+            permission_program: PERMISSION_PROGRAM_ID,
+            magic_program: MAGIC_PROGRAM_ID,
+            magic_context: MAGIC_CONTEXT_ID,
+        },
+        ix::AutoFinalize {},
+    )
+}
+
+/// Takes the key of an auction created before the layout version byte.
+pub fn migrate_auction(auction: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateAuction {
+            auction: *auction,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        ix::MigrateAuction {},
+    )
+}
+
+/// Takes the key of a bid created before the layout version byte, which is not derived
+/// from the auction's `creation_slot`.
+pub fn migrate_bid(bid: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateBid {
+            bid: *bid,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        ix::MigrateBid {},
+    )
+}
+
+/// View: simulate and decode the return data as `AuctionState`.
+pub fn get_auction_state(auction: &Pubkey) -> Instruction {
+    build(
+        accounts::GetAuctionState { auction: *auction },
+        ix::GetAuctionState {},
+    )
+}
+
+/// View: simulate and decode the return data as `BidStatus`.
+pub fn get_bid_status(auction: &Pubkey, auction_slot: u64, bidder: &Pubkey) -> Instruction {
+    build(
+        accounts::GetBidStatus {
+            auction: *auction,
+            bid: bid_pda(auction, auction_slot, bidder).0,
+        },
+        ix::GetBidStatus { bidder: *bidder },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RangeProof;
+
+    #[test]
+    fn batch_bids_follow_the_fixed_accounts() {
+        let auction = Pubkey::new_unique();
+        let revealer = Pubkey::new_unique();
+        let bidders = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let reveals = bidders
+            .iter()
+            .map(|_| BidReveal {
+                amount: 1,
+                nonce: [0; 32],
+            })
+            .collect();
+
+        let instruction = reveal_bids_batch(&auction, 7, &revealer, &bidders, reveals);
+        let remaining = &instruction.accounts[3..];
+        assert_eq!(remaining.len(), 4);
+        for (pair, bidder) in remaining.chunks(2).zip(&bidders) {
+            assert_eq!(pair[0].pubkey, bid_pda(&auction, 7, bidder).0);
+            assert!(pair[0].is_writable && !pair[0].is_signer);
+            assert_eq!(pair[1].pubkey, deposit_pda(&auction, bidder).0);
+            assert!(!pair[1].is_writable && !pair[1].is_signer);
+        }
+    }
+
+    #[test]
+    fn settlement_bids_follow_the_fixed_accounts() {
+        let auction = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let bidders = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let undelegate = undelegate_bids(&auction, &payer, 7, &bidders);
+        let remaining = &undelegate.accounts[5..];
+        assert_eq!(remaining.len(), 4);
+        for (pair, bidder) in remaining.chunks(2).zip(&bidders) {
+            let bid = bid_pda(&auction, 7, bidder).0;
+            assert_eq!(pair[0].pubkey, bid);
+            assert_eq!(pair[1].pubkey, permission_pda(&bid).0);
+            assert!(pair.iter().all(|meta| meta.is_writable && !meta.is_signer));
+        }
+
+        let settle = settle_auction(&auction, &payer, None, &payer, 7, &bidders);
+        let remaining = &settle.accounts[7..];
+        assert_eq!(remaining.len(), 6);
+        for (triple, bidder) in remaining.chunks(3).zip(&bidders) {
+            let bid = bid_pda(&auction, 7, bidder).0;
+            assert_eq!(triple[0].pubkey, bid);
+            assert_eq!(triple[1].pubkey, permission_pda(&bid).0);
+            assert_eq!(triple[2].pubkey, *bidder);
+        }
+    }
+
+    #[test]
+    fn ed25519_verify_matches_the_program_parser() {
+        let signer = Pubkey::new_unique();
+        let message = magic::helpers::nonce_derivation_message(&Pubkey::new_unique());
+        let instruction = ed25519_verify(&signer, &[7; 64], &message);
+        assert_eq!(
+            magic::helpers::verify_ed25519_instruction(&instruction, &signer, &message).unwrap(),
+            [7; 64]
+        );
+    }
+
+    #[test]
+    fn range_proof_accounts_follow_the_proof() {
+        let auction = Pubkey::new_unique();
+        let bidder = Pubkey::new_unique();
+
+        // Anchor encodes an omitted optional account as the program id.
+        let without = submit_sealed_bid(&auction, 7, &bidder, [0; 32], None);
+        assert_eq!(without.accounts[3].pubkey, magic::ID);
+        assert_eq!(without.accounts[4].pubkey, magic::ID);
+
+        let proof = BidProof::Range(RangeProof {
+            a: [0; 64],
+            b: [0; 128],
+            c: [0; 64],
+        });
+        let with = submit_sealed_bid(&auction, 7, &bidder, [0; 32], Some(proof));
+        assert_eq!(with.accounts[3].pubkey, range_proof_key_pda(&auction).0);
+        assert_eq!(with.accounts[4].pubkey, deposit_pda(&auction, &bidder).0);
+    }
+}
@@ -0,0 +1,17 @@
+//! Off-chain client for the `magic` auction program: PDA derivation, instruction
+//! builders, account decoding and bid commitments. Builds against the program crate
+//! with `no-entrypoint`, so it can be linked into any backend.
+
+pub mod account;
+pub mod commitment;
+pub mod instruction;
+pub mod outcome;
+pub mod pda;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+pub use magic::{
+    AuctionOptions, AuctionState, AuctionStatus, BidCiphertext, BidMode, BidProof, BidReveal,
+    BidStatus, CommitmentHash, CommitmentScheme, OpeningProof, RangeProof, RangeProofVerifyingKey,
+    ID as PROGRAM_ID,
+};
@@ -0,0 +1,728 @@
+//! Off-chain recomputation of an auction's outcome under the program's rules, for
+//! auditing what `finalize_auction` and `settle_auction` (or `finalize_and_settle`)
+//! left on chain.
+//!
+//! Ties go to the lower bidder key in every bid mode, so account snapshots and event
+//! replays break them the same way. Bids revealed above their bidder's deposit count as
+//! revealed but never lead, as in the program.
+
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use magic::{
+    event::{AuctionFinalized, AuctionPaidOut, BidCommitted, BidRevealed, BidUncovered},
+    helpers::outbids,
+};
+
+use crate::{
+    account::{Auction, Bid, Deposit},
+    commitment::compute_bid_commitment,
+    AuctionStatus, BidMode,
+};
+
+/// The result the program should have reached from the counted bids.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    /// Highest revealed bidder whose deposit covers the bid, before the reserve is applied.
+    pub leader: Option<Pubkey>,
+    pub highest_bid: u64,
+    /// The leader, if the highest bid meets the reserve.
+    pub winner: Option<Pubkey>,
+    /// Price the winner pays: its own bid. Zero without a winner.
+    pub clearing_price: u64,
+    pub total_bids: u32,
+    pub total_revealed: u32,
+}
+
+impl Outcome {
+    /// Payment and refunds this outcome implies for deposits as they stood before
+    /// settlement. Losers get their whole deposit back; the winner pays up to the
+    /// clearing price and gets the rest.
+    pub fn settlement(&self, deposits: &[Deposit]) -> Settlement {
+        let mut settlement = Settlement {
+            paid: 0,
+            shortfall: self.clearing_price,
+            refunds: Vec::new(),
+        };
+        for deposit in deposits {
+            let mut refund = deposit.amount;
+            if Some(deposit.bidder) == self.winner {
+                settlement.paid = deposit.amount.min(self.clearing_price);
+                settlement.shortfall = self.clearing_price - settlement.paid;
+                refund -= settlement.paid;
+            }
+            settlement.refunds.push(Refund {
+                bidder: deposit.bidder,
+                amount: refund,
+            });
+        }
+        settlement
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settlement {
+    /// Lamports moved from the winner's deposit to the authority.
+    pub paid: u64,
+    /// Part of the clearing price the winner's deposit did not cover.
+    pub shortfall: u64,
+    pub refunds: Vec<Refund>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Refund {
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
+
+/// A way the on-chain state differs from the recomputed outcome.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Discrepancy {
+    Leader {
+        expected: Option<Pubkey>,
+        actual: Option<Pubkey>,
+    },
+    Winner {
+        expected: Option<Pubkey>,
+        actual: Option<Pubkey>,
+    },
+    HighestBid {
+        expected: u64,
+        actual: u64,
+    },
+    TotalBids {
+        expected: u32,
+        actual: u32,
+    },
+    TotalRevealed {
+        expected: u32,
+        actual: u32,
+    },
+    /// A revealed sealed bid whose amount and nonce do not open its commitment.
+    InvalidReveal {
+        bidder: Pubkey,
+    },
+    /// The payout charged something other than the clearing price.
+    Payment {
+        expected: u64,
+        actual: u64,
+    },
+    /// A payout was recorded for an auction that is not settled, or the reverse.
+    Settled {
+        expected: bool,
+        actual: bool,
+    },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn bidder(key: &Option<Pubkey>) -> String {
+            key.map_or("none".to_string(), |key| key.to_string())
+        }
+        match self {
+            Self::Leader { expected, actual } => write!(
+                f,
+                "leader is {}, expected {}",
+                bidder(actual),
+                bidder(expected)
+            ),
+            Self::Winner { expected, actual } => write!(
+                f,
+                "winner is {}, expected {}",
+                bidder(actual),
+                bidder(expected)
+            ),
+            Self::HighestBid { expected, actual } => {
+                write!(f, "highest bid is {actual}, expected {expected}")
+            }
+            Self::TotalBids { expected, actual } => {
+                write!(f, "{actual} bids counted, expected {expected}")
+            }
+            Self::TotalRevealed { expected, actual } => {
+                write!(f, "{actual} reveals counted, expected {expected}")
+            }
+            Self::InvalidReveal { bidder } => {
+                write!(f, "reveal of {bidder} does not open its commitment")
+            }
+            Self::Payment { expected, actual } => {
+                write!(f, "winner charged {actual}, expected {expected}")
+            }
+            Self::Settled { expected, actual } => {
+                write!(f, "settled is {actual}, expected {expected}")
+            }
+        }
+    }
+}
+
+/// The recomputed outcome and every difference from the on-chain state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Audit {
+    pub expected: Outcome,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl Audit {
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Lifecycle events an audit replays, in the order the program emitted them.
+pub enum Event {
+    BidCommitted(BidCommitted),
+    BidRevealed(BidRevealed),
+    BidUncovered(BidUncovered),
+    Finalized(AuctionFinalized),
+    PaidOut(AuctionPaidOut),
+}
+
+/// A counted reveal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reveal {
+    pub bidder: Pubkey,
+    pub amount: u64,
+    /// The bidder's deposit covered the amount when it was revealed.
+    pub covered: bool,
+}
+
+/// Recomputes the outcome from the counted reveals, in any order.
+pub fn recompute(auction: &Auction, total_bids: u32, revealed: &[Reveal]) -> Outcome {
+    let mut leader: Option<Pubkey> = None;
+    let mut highest_bid = 0;
+    for reveal in revealed.iter().filter(|reveal| reveal.covered) {
+        if outbids(reveal.amount, &reveal.bidder, highest_bid, leader.as_ref()) {
+            highest_bid = reveal.amount;
+            leader = Some(reveal.bidder);
+        }
+    }
+
+    let winner = leader.filter(|_| highest_bid >= auction.reserve_price);
+    Outcome {
+        leader,
+        highest_bid,
+        winner,
+        clearing_price: if winner.is_some() { highest_bid } else { 0 },
+        total_bids,
+        total_revealed: revealed.len() as u32,
+    }
+}
+
+/// Audits an auction snapshot against its `Bid` and `Deposit` accounts. Bids placed in an
+/// earlier instance of the auction are ignored, as the program ignores them. Coverage is
+/// recomputed from the escrowed amounts rather than read from `Bid::uncovered`; a deposit
+/// withdrawn after finalization reads as uncovered, which cannot move the result as only
+/// losers withdraw before settlement, and the amount settlement took from the winner is
+/// added back.
+pub fn audit_accounts(
+    auction_key: &Pubkey,
+    auction: &Auction,
+    bids: &[Bid],
+    deposits: &[Deposit],
+) -> Audit {
+    let current: Vec<&Bid> = bids
+        .iter()
+        .filter(|bid| bid.auction == *auction_key && bid.auction_slot == auction.creation_slot)
+        .collect();
+    let total_bids = current.iter().filter(|bid| bid.committed).count() as u32;
+    let revealed: Vec<&Bid> = current.iter().copied().filter(|bid| bid.revealed).collect();
+
+    let mut discrepancies = Vec::new();
+    if auction.bid_mode == BidMode::Sealed {
+        for bid in &revealed {
+            let opens = compute_bid_commitment(
+                &auction.commitment_hash,
+                &auction.commitment_scheme,
+                bid.amount,
+                &bid.nonce,
+                &bid.bidder,
+                auction_key,
+                auction.creation_slot,
+            )
+            .is_ok_and(|commitment| commitment == bid.bid_hash);
+            if !opens {
+                discrepancies.push(Discrepancy::InvalidReveal { bidder: bid.bidder });
+            }
+        }
+    }
+
+    let reveals: Vec<_> = revealed
+        .iter()
+        .map(|bid| Reveal {
+            bidder: bid.bidder,
+            amount: bid.amount,
+            covered: bid.amount <= escrowed(auction_key, auction, deposits, &bid.bidder),
+        })
+        .collect();
+    let expected = recompute(auction, total_bids, &reveals);
+    compare_counts(
+        &expected,
+        auction.total_bids,
+        auction.total_revealed,
+        &mut discrepancies,
+    );
+    compare_result(
+        &expected,
+        matches!(
+            auction.status,
+            AuctionStatus::Finalized | AuctionStatus::Settled
+        ),
+        auction.highest_bidder,
+        auction.highest_bid,
+        &mut discrepancies,
+    );
+    Audit {
+        expected,
+        discrepancies,
+    }
+}
+
+/// The amount `bidder` escrowed for the auction, including what settlement paid out of it.
+fn escrowed(auction_key: &Pubkey, auction: &Auction, deposits: &[Deposit], bidder: &Pubkey) -> u64 {
+    let held = deposits
+        .iter()
+        .find(|deposit| deposit.auction == *auction_key && deposit.bidder == *bidder)
+        .map_or(0, |deposit| deposit.amount);
+    let paid =
+        if auction.status == AuctionStatus::Settled && auction.highest_bidder == Some(*bidder) {
+            auction.highest_bid
+        } else {
+            0
+        };
+    held.saturating_add(paid)
+}
+
+/// Audits an auction by replaying its events, checking the `AuctionFinalized` and
+/// `AuctionPaidOut` events against the outcome and whether `auction` is settled.
+pub fn audit_events(auction_key: &Pubkey, auction: &Auction, events: &[Event]) -> Audit {
+    let mut total_bids = 0;
+    let mut revealed = Vec::new();
+    let mut finalized = None;
+    let mut paid_out = None;
+    for event in events {
+        match event {
+            Event::BidCommitted(event) if event.auction == *auction_key => total_bids += 1,
+            Event::BidRevealed(event) if event.auction == *auction_key => revealed.push(Reveal {
+                bidder: event.bidder,
+                amount: event.amount,
+                covered: true,
+            }),
+            Event::BidUncovered(event) if event.auction == *auction_key => revealed.push(Reveal {
+                bidder: event.bidder,
+                amount: event.amount,
+                covered: false,
+            }),
+            Event::Finalized(event) if event.auction == *auction_key => finalized = Some(event),
+            Event::PaidOut(event) if event.auction == *auction_key => paid_out = Some(event),
+            _ => {}
+        }
+    }
+
+    let expected = recompute(auction, total_bids, &revealed);
+    let mut discrepancies = Vec::new();
+    match finalized {
+        Some(event) => {
+            compare_counts(
+                &expected,
+                event.total_bids,
+                event.total_revealed,
+                &mut discrepancies,
+            );
+            compare_result(
+                &expected,
+                true,
+                event.winner,
+                event.highest_bid,
+                &mut discrepancies,
+            );
+        }
+        None => compare_counts(
+            &expected,
+            auction.total_bids,
+            auction.total_revealed,
+            &mut discrepancies,
+        ),
+    }
+    if let Some(event) = paid_out {
+        if event.winner != expected.winner {
+            discrepancies.push(Discrepancy::Winner {
+                expected: expected.winner,
+                actual: event.winner,
+            });
+        }
+        let charged = event.amount + event.shortfall;
+        if charged != expected.clearing_price {
+            discrepancies.push(Discrepancy::Payment {
+                expected: expected.clearing_price,
+                actual: charged,
+            });
+        }
+    }
+    let settled = auction.status == AuctionStatus::Settled;
+    if paid_out.is_some() != settled {
+        discrepancies.push(Discrepancy::Settled {
+            expected: paid_out.is_some(),
+            actual: settled,
+        });
+    }
+    Audit {
+        expected,
+        discrepancies,
+    }
+}
+
+fn compare_counts(
+    expected: &Outcome,
+    total_bids: u32,
+    total_revealed: u32,
+    discrepancies: &mut Vec<Discrepancy>,
+) {
+    if total_bids != expected.total_bids {
+        discrepancies.push(Discrepancy::TotalBids {
+            expected: expected.total_bids,
+            actual: total_bids,
+        });
+    }
+    if total_revealed != expected.total_revealed {
+        discrepancies.push(Discrepancy::TotalRevealed {
+            expected: expected.total_revealed,
+            actual: total_revealed,
+        });
+    }
+}
+
+/// Before finalization the program tracks the running leader; after it, the winner.
+fn compare_result(
+    expected: &Outcome,
+    finalized: bool,
+    bidder: Option<Pubkey>,
+    highest_bid: u64,
+    discrepancies: &mut Vec<Discrepancy>,
+) {
+    if finalized && bidder != expected.winner {
+        discrepancies.push(Discrepancy::Winner {
+            expected: expected.winner,
+            actual: bidder,
+        });
+    } else if !finalized && bidder != expected.leader {
+        discrepancies.push(Discrepancy::Leader {
+            expected: expected.leader,
+            actual: bidder,
+        });
+    }
+    if highest_bid != expected.highest_bid {
+        discrepancies.push(Discrepancy::HighestBid {
+            expected: expected.highest_bid,
+            actual: highest_bid,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commitment::seal_bid, CommitmentHash, CommitmentScheme};
+
+    const SLOT: u64 = 7;
+
+    fn auction(bid_mode: BidMode, reserve_price: u64) -> Auction {
+        Auction {
+            auction_id: 1,
+            authority: Pubkey::new_unique(),
+            start_ts: 0,
+            end_ts: 10,
+            reveal_end_ts: 20,
+            reserve_price,
+            highest_bid: 0,
+            highest_bidder: None,
+            total_bids: 0,
+            total_revealed: 0,
+            status: AuctionStatus::Bidding,
+            bump: 255,
+            version: Auction::VERSION,
+            bid_mode,
+            commitment_scheme: CommitmentScheme::V2,
+            commitment_hash: CommitmentHash::Sha256,
+            range_proof: false,
+            encryption_key: None,
+            derived_nonce: false,
+            creation_slot: SLOT,
+            demand_commitment: [0; 32],
+            aggregate_demand: None,
+            auto_finalize_task: None,
+        }
+    }
+
+    fn revealed_bid(auction: &Auction, key: &Pubkey, bidder: Pubkey, amount: u64) -> Bid {
+        let nonce = bidder.to_bytes();
+        Bid {
+            auction: *key,
+            bidder,
+            bid_hash: seal_bid(auction, key, &bidder, amount, &nonce).unwrap(),
+            committed: true,
+            revealed: true,
+            amount,
+            nonce,
+            bump: 255,
+            version: Bid::VERSION,
+            uncovered: false,
+            ciphertext: None,
+            reveal_agent: None,
+            auction_slot: SLOT,
+        }
+    }
+
+    /// A deposit of exactly each bid's amount, so every bid is covered.
+    fn covering(key: &Pubkey, bids: &[Bid]) -> Vec<Deposit> {
+        bids.iter()
+            .map(|bid| Deposit {
+                auction: *key,
+                bidder: bid.bidder,
+                amount: bid.amount,
+                bump: 255,
+            })
+            .collect()
+    }
+
+    /// `auction` as the program leaves it after counting `bids` in order and finalizing.
+    fn finalized(mut auction: Auction, bids: &[Bid]) -> Auction {
+        for bid in bids {
+            auction.total_bids += 1;
+            auction.total_revealed += 1;
+            if outbids(
+                bid.amount,
+                &bid.bidder,
+                auction.highest_bid,
+                auction.highest_bidder.as_ref(),
+            ) {
+                auction.highest_bid = bid.amount;
+                auction.highest_bidder = Some(bid.bidder);
+            }
+        }
+        if auction.highest_bid < auction.reserve_price {
+            auction.highest_bidder = None;
+        }
+        auction.status = AuctionStatus::Finalized;
+        auction
+    }
+
+    #[test]
+    fn a_faithful_finalization_audits_clean_and_implies_the_refunds() {
+        let key = Pubkey::new_unique();
+        let base = auction(BidMode::Sealed, 100);
+        let (low, high) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let bids = [
+            revealed_bid(&base, &key, low, 120),
+            revealed_bid(&base, &key, high, 300),
+        ];
+        let on_chain = finalized(base, &bids);
+
+        let audit = audit_accounts(&key, &on_chain, &bids, &covering(&key, &bids));
+        assert!(audit.is_clean(), "{:?}", audit.discrepancies);
+        assert_eq!(audit.expected.winner, Some(high));
+        assert_eq!(audit.expected.clearing_price, 300);
+
+        let deposit = |bidder, amount| Deposit {
+            auction: key,
+            bidder,
+            amount,
+            bump: 255,
+        };
+        let settlement = audit
+            .expected
+            .settlement(&[deposit(low, 150), deposit(high, 250)]);
+        assert_eq!((settlement.paid, settlement.shortfall), (250, 50));
+        assert_eq!(
+            settlement.refunds,
+            [
+                Refund {
+                    bidder: low,
+                    amount: 150
+                },
+                Refund {
+                    bidder: high,
+                    amount: 0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_winners_reserves_and_forged_reveals_are_reported() {
+        let key = Pubkey::new_unique();
+        let base = auction(BidMode::Sealed, 500);
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut bids = [
+            revealed_bid(&base, &key, a, 200),
+            revealed_bid(&base, &key, b, 300),
+        ];
+        let mut on_chain = finalized(base, &bids);
+        on_chain.highest_bidder = Some(b);
+        bids[0].amount = 400;
+
+        let audit = audit_accounts(&key, &on_chain, &bids, &covering(&key, &bids));
+        assert_eq!(audit.expected.winner, None);
+        assert_eq!(
+            audit.discrepancies,
+            [
+                Discrepancy::InvalidReveal { bidder: a },
+                Discrepancy::Winner {
+                    expected: None,
+                    actual: Some(b)
+                },
+                Discrepancy::HighestBid {
+                    expected: 400,
+                    actual: 300
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ties_go_to_the_lower_bidder_key_in_every_mode() {
+        let key = Pubkey::new_unique();
+        let (mut high_key, mut low_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        if high_key < low_key {
+            std::mem::swap(&mut high_key, &mut low_key);
+        }
+
+        let sealed = auction(BidMode::Sealed, 0);
+        let bids = [
+            revealed_bid(&sealed, &key, high_key, 100),
+            revealed_bid(&sealed, &key, low_key, 100),
+        ];
+        let on_chain = finalized(sealed, &bids);
+        assert_eq!(on_chain.highest_bidder, Some(low_key));
+        let deposits = covering(&key, &bids);
+        assert!(audit_accounts(&key, &on_chain, &bids, &deposits).is_clean());
+        let mut swapped = on_chain.clone();
+        swapped.highest_bidder = Some(high_key);
+        assert!(!audit_accounts(&key, &swapped, &bids, &deposits).is_clean());
+
+        let events: Vec<_> = [high_key, low_key]
+            .into_iter()
+            .flat_map(|bidder| {
+                [
+                    Event::BidCommitted(BidCommitted {
+                        auction: key,
+                        bidder,
+                    }),
+                    Event::BidRevealed(BidRevealed {
+                        auction: key,
+                        bidder,
+                        amount: 100,
+                    }),
+                ]
+            })
+            .collect();
+        let replay = audit_events(&key, &on_chain, &events);
+        assert_eq!(replay.expected.winner, Some(low_key));
+        assert!(replay.is_clean(), "{:?}", replay.discrepancies);
+
+        for mode in [BidMode::Private, BidMode::Encrypted] {
+            let reveals = [(high_key, 100), (low_key, 100)].map(|(bidder, amount)| Reveal {
+                bidder,
+                amount,
+                covered: true,
+            });
+            assert_eq!(
+                recompute(&auction(mode, 0), 2, &reveals).winner,
+                Some(low_key)
+            );
+        }
+    }
+
+    #[test]
+    fn uncovered_bids_count_as_revealed_but_never_win() {
+        let covered = Pubkey::new_unique();
+        let uncovered = Pubkey::new_unique();
+        let reveals = [
+            Reveal {
+                bidder: covered,
+                amount: 40,
+                covered: true,
+            },
+            Reveal {
+                bidder: uncovered,
+                amount: 90,
+                covered: false,
+            },
+        ];
+        let outcome = recompute(&auction(BidMode::Sealed, 0), 2, &reveals);
+        assert_eq!(outcome.winner, Some(covered));
+        assert_eq!(outcome.highest_bid, 40);
+        assert_eq!(outcome.total_revealed, 2);
+    }
+
+    #[test]
+    fn coverage_is_recomputed_from_the_deposits() {
+        let key = Pubkey::new_unique();
+        let base = auction(BidMode::Sealed, 0);
+        let (low, high) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let bids = [
+            revealed_bid(&base, &key, low, 120),
+            revealed_bid(&base, &key, high, 300),
+        ];
+        let mut deposits = covering(&key, &bids);
+        deposits[1].amount = 299;
+
+        // The program marked `high` covered and let it win, but its deposit falls short.
+        let on_chain = finalized(base, &bids);
+        let audit = audit_accounts(&key, &on_chain, &bids, &deposits);
+        assert_eq!(audit.expected.winner, Some(low));
+        assert!(!audit.is_clean());
+
+        // Settlement drains the winner's deposit, which may then be withdrawn; what it
+        // paid still counts as escrowed.
+        let mut settled = on_chain;
+        settled.status = AuctionStatus::Settled;
+        deposits[1].amount = 0;
+        let audit = audit_accounts(&key, &settled, &bids, &deposits);
+        assert!(audit.is_clean(), "{:?}", audit.discrepancies);
+        deposits.truncate(1);
+        assert!(audit_accounts(&key, &settled, &bids, &deposits).is_clean());
+    }
+
+    #[test]
+    fn payouts_must_charge_the_clearing_price() {
+        let key = Pubkey::new_unique();
+        let winner = Pubkey::new_unique();
+        let mut on_chain = auction(BidMode::Sealed, 0);
+        on_chain.status = AuctionStatus::Settled;
+        let paid_out = |amount, shortfall| {
+            vec![
+                Event::BidCommitted(BidCommitted {
+                    auction: key,
+                    bidder: winner,
+                }),
+                Event::BidRevealed(BidRevealed {
+                    auction: key,
+                    bidder: winner,
+                    amount: 300,
+                }),
+                Event::Finalized(AuctionFinalized {
+                    auction: key,
+                    winner: Some(winner),
+                    highest_bid: 300,
+                    reserve_price: 0,
+                    total_bids: 1,
+                    total_revealed: 1,
+                }),
+                Event::PaidOut(AuctionPaidOut {
+                    auction: key,
+                    winner: Some(winner),
+                    amount,
+                    shortfall,
+                }),
+            ]
+        };
+
+        assert!(audit_events(&key, &on_chain, &paid_out(250, 50)).is_clean());
+        assert_eq!(
+            audit_events(&key, &on_chain, &paid_out(200, 0)).discrepancies,
+            [Discrepancy::Payment {
+                expected: 300,
+                actual: 200
+            }]
+        );
+    }
+}
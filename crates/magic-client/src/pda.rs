@@ -0,0 +1,82 @@
+use anchor_lang::prelude::Pubkey;
+use ephemeral_rollups_sdk::access_control::structs::Permission;
+use ephemeral_rollups_sdk::pda::{
+    DELEGATE_BUFFER_TAG, DELEGATION_METADATA_TAG, DELEGATION_RECORD_TAG, EPHEMERAL_BALANCE_TAG,
+};
+use magic::constant::{AUCTION_SEED, BID_SEED, DEPOSIT_SEED, RANGE_PROOF_KEY_SEED};
+
+pub fn auction_pda(authority: &Pubkey, auction_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AUCTION_SEED, authority.as_ref(), &auction_id.to_le_bytes()],
+        &magic::ID,
+    )
+}
+
+/// `auction_slot` is the auction's `creation_slot`, so an auction re-created at the same
+/// address gets fresh bid PDAs.
+pub fn bid_pda(auction: &Pubkey, auction_slot: u64, bidder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            BID_SEED,
+            auction.as_ref(),
+            &auction_slot.to_le_bytes(),
+            bidder.as_ref(),
+        ],
+        &magic::ID,
+    )
+}
+
+pub fn deposit_pda(auction: &Pubkey, bidder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DEPOSIT_SEED, auction.as_ref(), bidder.as_ref()],
+        &magic::ID,
+    )
+}
+
+pub fn range_proof_key_pda(auction: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RANGE_PROOF_KEY_SEED, auction.as_ref()], &magic::ID)
+}
+
+/// Permission Program PDA guarding an auction or bid account.
+pub fn permission_pda(permissioned_account: &Pubkey) -> (Pubkey, u8) {
+    Permission::find_pda(permissioned_account)
+}
+
+/// Delegation program accounts created when `delegated` (owned by `owner_program`) is
+/// delegated to an ER validator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DelegationPdas {
+    pub buffer: Pubkey,
+    pub record: Pubkey,
+    pub metadata: Pubkey,
+}
+
+pub fn delegation_pdas(delegated: &Pubkey, owner_program: &Pubkey) -> DelegationPdas {
+    let delegation_program = ephemeral_rollups_sdk::id();
+    DelegationPdas {
+        buffer: Pubkey::find_program_address(
+            &[DELEGATE_BUFFER_TAG, delegated.as_ref()],
+            owner_program,
+        )
+        .0,
+        record: Pubkey::find_program_address(
+            &[DELEGATION_RECORD_TAG, delegated.as_ref()],
+            &delegation_program,
+        )
+        .0,
+        metadata: Pubkey::find_program_address(
+            &[DELEGATION_METADATA_TAG, delegated.as_ref()],
+            &delegation_program,
+        )
+        .0,
+    }
+}
+
+/// Escrow the delegation program signs L1 actions with on behalf of `escrow_authority`.
+pub fn action_escrow_pda(escrow_authority: &Pubkey, index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[EPHEMERAL_BALANCE_TAG, escrow_authority.as_ref(), &[index]],
+        &ephemeral_rollups_sdk::id(),
+    )
+    .0
+}
@@ -0,0 +1,388 @@
+//! Minimal blocking JSON-RPC transport (`rpc` feature): enough to read program accounts,
+//! land transactions and route delegated accounts to an Ephemeral Rollup.
+
+use std::{str::FromStr, thread, time::Duration};
+
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, AnchorDeserialize};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use solana_hash::Hash;
+use solana_transaction::Transaction;
+
+/// Polls for confirmation every `CONFIRM_POLL` up to `CONFIRM_ATTEMPTS` times (~30s).
+const CONFIRM_POLL: Duration = Duration::from_millis(500);
+const CONFIRM_ATTEMPTS: u32 = 60;
+/// `unix_timestamp` offset in the bincode-encoded `Clock` sysvar.
+const CLOCK_UNIX_TIMESTAMP_OFFSET: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RpcError {
+    #[error("transport error: {0}")]
+    Transport(#[from] Box<ureq::Error>),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("rpc error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("unexpected rpc response: {0}")]
+    Response(String),
+    #[error("transaction {signature} failed: {err}")]
+    TransactionFailed { signature: String, err: String },
+    #[error("transaction {0} was not confirmed in time")]
+    Unconfirmed(String),
+    #[error("account {0} is delegated but no ER RPC is configured")]
+    NoEphemeralRpc(Pubkey),
+}
+
+impl RpcError {
+    /// Whether retrying the same request may succeed (network or node-side hiccups).
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            RpcError::Transport(_) | RpcError::Io(_) | RpcError::Unconfirmed(_)
+        ) || matches!(self, RpcError::Rpc { code, .. } if *code == -32005 || *code <= -32099)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, RpcError>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcAccount {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+/// A block's program logs, as returned by `getBlock`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcBlock {
+    pub slot: u64,
+    pub blockhash: String,
+    pub previous_blockhash: String,
+    pub parent_slot: u64,
+    pub transactions: Vec<RpcTransactionLogs>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcTransactionLogs {
+    pub signature: String,
+    /// Failed transactions still carry logs, but none of their state changes landed.
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+/// Outcome of `simulateTransaction`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcSimulation {
+    pub units_consumed: u64,
+    pub err: Option<String>,
+    pub logs: Vec<String>,
+    /// Data the program set with `set_return_data`, e.g. by a view instruction.
+    pub return_data: Option<Vec<u8>>,
+}
+
+pub struct RpcClient {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response: Value = self
+            .agent
+            .post(&self.url)
+            .send_json(request)
+            .map_err(Box::new)?
+            .into_json()?;
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        Ok(response["result"].take())
+    }
+
+    pub fn get_account(&self, key: &Pubkey) -> Result<Option<RpcAccount>> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([key.to_string(), { "encoding": "base64", "commitment": "confirmed" }]),
+        )?;
+        match &result["value"] {
+            Value::Null => Ok(None),
+            value => parse_account(value).map(Some),
+        }
+    }
+
+    /// All `magic` accounts whose data starts with `discriminator`.
+    pub fn get_program_accounts(&self, discriminator: &[u8]) -> Result<Vec<(Pubkey, RpcAccount)>> {
+        let filter = json!({
+            "memcmp": { "offset": 0, "bytes": BASE64.encode(discriminator), "encoding": "base64" }
+        });
+        let result = self.call(
+            "getProgramAccounts",
+            json!([
+                magic::ID.to_string(),
+                { "encoding": "base64", "commitment": "confirmed", "filters": [filter] }
+            ]),
+        )?;
+        let entries = result
+            .as_array()
+            .ok_or_else(|| RpcError::Response(result.to_string()))?;
+        entries
+            .iter()
+            .map(|entry| {
+                Ok((
+                    parse_pubkey(&entry["pubkey"])?,
+                    parse_account(&entry["account"])?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Cluster time from the `Clock` sysvar, which is what the program compares against.
+    pub fn get_unix_timestamp(&self) -> Result<i64> {
+        let clock = self
+            .get_account(&solana_sdk_ids::sysvar::clock::ID)?
+            .ok_or_else(|| RpcError::Response("missing clock sysvar".to_string()))?;
+        let bytes = clock
+            .data
+            .get(CLOCK_UNIX_TIMESTAMP_OFFSET..CLOCK_UNIX_TIMESTAMP_OFFSET + 8)
+            .ok_or_else(|| RpcError::Response("short clock sysvar".to_string()))?;
+        Ok(i64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": "confirmed" }]))?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| RpcError::Response(result.to_string()))?;
+        Hash::from_str(blockhash).map_err(|err| RpcError::Response(err.to_string()))
+    }
+
+    pub fn get_slot(&self, commitment: &str) -> Result<u64> {
+        let result = self.call("getSlot", json!([{ "commitment": commitment }]))?;
+        result
+            .as_u64()
+            .ok_or_else(|| RpcError::Response(result.to_string()))
+    }
+
+    /// Confirmed, non-skipped slots in `start..=end`.
+    pub fn get_blocks(&self, start: u64, end: u64) -> Result<Vec<u64>> {
+        let result = self.call(
+            "getBlocks",
+            json!([start, end, { "commitment": "confirmed" }]),
+        )?;
+        serde_json::from_value(result.clone()).map_err(|_| RpcError::Response(result.to_string()))
+    }
+
+    /// The confirmed block at `slot`, or `None` if the slot was skipped.
+    pub fn get_block(&self, slot: u64) -> Result<Option<RpcBlock>> {
+        let result = self.call(
+            "getBlock",
+            json!([slot, {
+                "commitment": "confirmed",
+                "encoding": "json",
+                "transactionDetails": "full",
+                "rewards": false,
+                "maxSupportedTransactionVersion": 0
+            }]),
+        );
+        match result {
+            Ok(Value::Null) => Ok(None),
+            Ok(block) => parse_block(slot, &block).map(Some),
+            // Slot skipped, or skipped/missing in long-term storage.
+            Err(RpcError::Rpc { code, .. }) if code == -32007 || code == -32009 => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Simulates `transaction` against the latest blockhash without checking signatures.
+    pub fn simulate(&self, transaction: &Transaction) -> Result<RpcSimulation> {
+        let wire =
+            bincode::serialize(transaction).map_err(|err| RpcError::Response(err.to_string()))?;
+        let result = self.call(
+            "simulateTransaction",
+            json!([BASE64.encode(wire), {
+                "encoding": "base64",
+                "commitment": "confirmed",
+                "sigVerify": false,
+                "replaceRecentBlockhash": true,
+            }]),
+        )?;
+        let value = &result["value"];
+        Ok(RpcSimulation {
+            units_consumed: value["unitsConsumed"]
+                .as_u64()
+                .ok_or_else(|| RpcError::Response(result.to_string()))?,
+            err: (!value["err"].is_null()).then(|| value["err"].to_string()),
+            logs: value["logs"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|line| line.as_str().map(str::to_string))
+                .collect(),
+            return_data: value["returnData"]["data"][0]
+                .as_str()
+                .and_then(|data| BASE64.decode(data).ok()),
+        })
+    }
+
+    /// Simulates a view instruction such as `get_auction_state` and decodes its return
+    /// data. `payer` only has to exist; nothing is signed or sent.
+    pub fn view<T: AnchorDeserialize>(
+        &self,
+        payer: &Pubkey,
+        instruction: Instruction,
+    ) -> Result<T> {
+        let simulation =
+            self.simulate(&Transaction::new_with_payer(&[instruction], Some(payer)))?;
+        if let Some(err) = simulation.err {
+            return Err(RpcError::Response(format!("view failed: {err}")));
+        }
+        let data = simulation
+            .return_data
+            .ok_or_else(|| RpcError::Response("view returned no data".to_string()))?;
+        T::try_from_slice(&data).map_err(|err| RpcError::Response(err.to_string()))
+    }
+
+    /// Sends a signed transaction and waits until it is confirmed.
+    pub fn send_and_confirm(&self, transaction: &Transaction) -> Result<String> {
+        let wire =
+            bincode::serialize(transaction).map_err(|err| RpcError::Response(err.to_string()))?;
+        let result = self.call(
+            "sendTransaction",
+            json!([BASE64.encode(wire), { "encoding": "base64", "preflightCommitment": "confirmed" }]),
+        )?;
+        let signature = result
+            .as_str()
+            .ok_or_else(|| RpcError::Response(result.to_string()))?
+            .to_string();
+
+        for _ in 0..CONFIRM_ATTEMPTS {
+            let statuses = self.call("getSignatureStatuses", json!([[signature]]))?;
+            let status = &statuses["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    return Err(RpcError::TransactionFailed {
+                        signature,
+                        err: status["err"].to_string(),
+                    });
+                }
+                if matches!(
+                    status["confirmationStatus"].as_str(),
+                    Some("confirmed" | "finalized")
+                ) {
+                    return Ok(signature);
+                }
+            }
+            thread::sleep(CONFIRM_POLL);
+        }
+        Err(RpcError::Unconfirmed(signature))
+    }
+}
+
+/// Routes account reads and transactions between L1 and an Ephemeral Rollup: accounts
+/// owned by the delegation program on L1 live on the ER.
+pub struct Router {
+    pub l1: RpcClient,
+    pub er: Option<RpcClient>,
+}
+
+impl Router {
+    pub fn new(l1: RpcClient, er: Option<RpcClient>) -> Self {
+        Self { l1, er }
+    }
+
+    pub fn is_delegated(&self, key: &Pubkey) -> Result<bool> {
+        Ok(self
+            .l1
+            .get_account(key)?
+            .is_some_and(|account| account.owner == ephemeral_rollups_sdk::id()))
+    }
+
+    /// The RPC that holds the live state of `key`.
+    pub fn rpc_for(&self, key: &Pubkey) -> Result<&RpcClient> {
+        if self.is_delegated(key)? {
+            self.er.as_ref().ok_or(RpcError::NoEphemeralRpc(*key))
+        } else {
+            Ok(&self.l1)
+        }
+    }
+
+    pub fn get_account(&self, key: &Pubkey) -> Result<Option<RpcAccount>> {
+        self.rpc_for(key)?.get_account(key)
+    }
+}
+
+fn parse_pubkey(value: &Value) -> Result<Pubkey> {
+    value
+        .as_str()
+        .and_then(|key| Pubkey::from_str(key).ok())
+        .ok_or_else(|| RpcError::Response(value.to_string()))
+}
+
+fn parse_account(value: &Value) -> Result<RpcAccount> {
+    let data = value["data"][0]
+        .as_str()
+        .and_then(|data| BASE64.decode(data).ok())
+        .ok_or_else(|| RpcError::Response(value.to_string()))?;
+    Ok(RpcAccount {
+        owner: parse_pubkey(&value["owner"])?,
+        lamports: value["lamports"].as_u64().unwrap_or_default(),
+        data,
+    })
+}
+
+/// Parses a `getBlock` result (`encoding: "json"`, full transaction details).
+pub fn parse_block(slot: u64, value: &Value) -> Result<RpcBlock> {
+    let response = || RpcError::Response(value.to_string());
+    let transactions = match &value["transactions"] {
+        Value::Null => Vec::new(),
+        transactions => transactions
+            .as_array()
+            .ok_or_else(response)?
+            .iter()
+            .map(|entry| {
+                let logs = match &entry["meta"]["logMessages"] {
+                    Value::Null => Vec::new(),
+                    logs => serde_json::from_value(logs.clone()).map_err(|_| response())?,
+                };
+                Ok(RpcTransactionLogs {
+                    signature: entry["transaction"]["signatures"][0]
+                        .as_str()
+                        .ok_or_else(response)?
+                        .to_string(),
+                    failed: !entry["meta"]["err"].is_null(),
+                    logs,
+                })
+            })
+            .collect::<Result<_>>()?,
+    };
+    Ok(RpcBlock {
+        slot,
+        blockhash: value["blockhash"]
+            .as_str()
+            .ok_or_else(response)?
+            .to_string(),
+        previous_blockhash: value["previousBlockhash"]
+            .as_str()
+            .ok_or_else(response)?
+            .to_string(),
+        parent_slot: value["parentSlot"].as_u64().ok_or_else(response)?,
+        transactions,
+    })
+}
@@ -0,0 +1,536 @@
+//! In-process runtime for testing `magic` without a validator or Ephemeral Rollup.
+//!
+//! Instructions run natively through the program's Anchor entrypoint over the same
+//! serialized input the BPF loader builds, so account reallocation and ownership changes
+//! behave as on-chain. The `Clock` is set directly and a transaction's account changes
+//! are applied only if every instruction succeeds. Compute units are not metered.
+//!
+//! Anchor issues CPIs through `solana-invoke`, which has no native fallback, so any
+//! instruction that makes one (`init` constraints, delegation, permissions) fails with
+//! [`ExecutionError::UnsupportedCpi`]. Tests seed the accounts those instructions would
+//! create with [`Harness::set_state`] instead, or run them against the compiled program
+//! with [`svm::Svm`].
+//!
+//! [`Harness::delegate`] stands in for the delegation program: on [`Chain::L1`] a delegated
+//! account is owned by the delegation program and cannot be modified, while on
+//! [`Chain::Er`] only delegated accounts are writable.
+
+mod stubs;
+pub mod svm;
+
+use std::{
+    collections::{HashMap, HashSet},
+    mem::size_of,
+    panic::{self, AssertUnwindSafe},
+};
+
+use anchor_lang::{
+    prelude::{AccountMeta, Clock, ProgramError, Pubkey, Rent},
+    solana_program::{
+        entrypoint::{deserialize, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER},
+        instruction::{BorrowedAccountMeta, BorrowedInstruction, Instruction},
+    },
+    AccountDeserialize, AccountSerialize, Owner, Space,
+};
+use ephemeral_rollups_sdk::consts::{MAGIC_PROGRAM_ID, PERMISSION_PROGRAM_ID};
+use solana_instructions_sysvar::construct_instructions_data;
+use solana_sdk_ids::sysvar;
+use solana_transaction_error::TransactionError;
+
+/// Unix time the harness clock starts at.
+pub const GENESIS_TIMESTAMP: i64 = 1_700_000_000;
+
+/// What `solana-invoke` panics with when asked to CPI off-chain.
+const CPI_PANIC: &str = "only supported with `target_os = \"solana\"";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExecutionError {
+    Program(ProgramError),
+    Panicked(String),
+    /// A delegated account was modified on L1.
+    ExternalAccountModified(Pubkey),
+    /// A non-delegated account was writable on the ER.
+    NotDelegated(Pubkey),
+    /// The program attempted a CPI, which cannot run natively.
+    UnsupportedCpi,
+    MissingSignature(Pubkey),
+    UnsupportedProgram(Pubkey),
+    ReadonlyModified(Pubkey),
+    UnbalancedLamports,
+    /// Rejected by LiteSVM outside the program's own errors, see [`svm::Svm`].
+    Transaction(TransactionError),
+}
+
+/// Why a transaction failed, with the logs up to the failure.
+#[derive(Debug)]
+pub struct Failure {
+    pub instruction: usize,
+    pub error: ExecutionError,
+    pub logs: Vec<String>,
+}
+
+impl Failure {
+    /// The custom error code the program returned, e.g. `u32::from(AuctionError::X)`.
+    pub fn code(&self) -> Option<u32> {
+        match self.error {
+            ExecutionError::Program(ProgramError::Custom(code)) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+/// Where a transaction runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chain {
+    L1,
+    Er,
+}
+
+pub struct Harness {
+    accounts: HashMap<Pubkey, Account>,
+    delegated: HashSet<Pubkey>,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        stubs::install();
+        stubs::with(|context| {
+            *context = Default::default();
+            context.clock = Clock {
+                slot: 1,
+                unix_timestamp: GENESIS_TIMESTAMP,
+                ..Clock::default()
+            };
+        });
+
+        let mut harness = Self {
+            accounts: HashMap::new(),
+            delegated: HashSet::new(),
+        };
+        let programs = [
+            (magic::ID, solana_sdk_ids::bpf_loader_upgradeable::ID),
+            (
+                solana_sdk_ids::system_program::ID,
+                solana_sdk_ids::native_loader::ID,
+            ),
+            (
+                ephemeral_rollups_sdk::id(),
+                solana_sdk_ids::bpf_loader_upgradeable::ID,
+            ),
+            (
+                PERMISSION_PROGRAM_ID,
+                solana_sdk_ids::bpf_loader_upgradeable::ID,
+            ),
+            (MAGIC_PROGRAM_ID, solana_sdk_ids::native_loader::ID),
+        ];
+        for (program, loader) in programs {
+            harness.set_account(
+                program,
+                Account {
+                    lamports: 1,
+                    data: Vec::new(),
+                    owner: loader,
+                    executable: true,
+                },
+            );
+        }
+        harness
+    }
+
+    pub fn clock(&self) -> Clock {
+        stubs::with(|context| context.clock.clone())
+    }
+
+    /// Moves the clock to `unix_timestamp`, advancing the slot so each warp is distinct.
+    pub fn warp_to_timestamp(&mut self, unix_timestamp: i64) {
+        stubs::with(|context| {
+            context.clock.unix_timestamp = unix_timestamp;
+            context.clock.slot += 1;
+        });
+    }
+
+    pub fn warp_to_slot(&mut self, slot: u64) {
+        stubs::with(|context| context.clock.slot = slot);
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        let account = self.accounts.entry(*key).or_insert_with(|| Account {
+            owner: solana_sdk_ids::system_program::ID,
+            ..Account::default()
+        });
+        account.lamports += lamports;
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&Account> {
+        self.accounts.get(key)
+    }
+
+    /// Stores `state` as a rent-exempt account owned by its program, sized like `init`'s
+    /// `8 + T::INIT_SPACE`.
+    pub fn set_state<T: AccountSerialize + Owner + Space>(&mut self, key: Pubkey, state: &T) {
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.resize(8 + T::INIT_SPACE, 0);
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.set_account(
+            key,
+            Account {
+                lamports,
+                data,
+                owner: T::owner(),
+                executable: false,
+            },
+        );
+    }
+
+    /// Hands `key` to the ER. Its state is shared with L1, so undelegating commits it.
+    pub fn delegate(&mut self, key: &Pubkey) {
+        self.delegated.insert(*key);
+    }
+
+    pub fn undelegate(&mut self, key: &Pubkey) {
+        self.delegated.remove(key);
+    }
+
+    pub fn is_delegated(&self, key: &Pubkey) -> bool {
+        self.delegated.contains(key)
+    }
+
+    /// Decodes an Anchor account, checking its discriminator.
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> Option<T> {
+        let account = self.accounts.get(key)?;
+        T::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// Logs of the last transaction, in validator format.
+    pub fn logs(&self) -> Vec<String> {
+        stubs::with(|context| context.logs.clone())
+    }
+
+    pub fn return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        stubs::with(|context| context.return_data.clone())
+    }
+
+    /// Runs `instructions` on L1 as one transaction signed by `signers`.
+    pub fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> Result<(), Failure> {
+        self.process_on(Chain::L1, instructions, signers)
+    }
+
+    pub fn process_on(
+        &mut self,
+        chain: Chain,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> Result<(), Failure> {
+        stubs::with(|context| {
+            context.logs.clear();
+            context.return_data = None;
+        });
+
+        let mut accounts = self.accounts.clone();
+        for index in 0..instructions.len() {
+            let view = View {
+                chain,
+                delegated: &self.delegated,
+            };
+            execute(&mut accounts, &view, instructions, index, signers).map_err(|error| {
+                Failure {
+                    instruction: index,
+                    error,
+                    logs: self.logs(),
+                }
+            })?;
+        }
+        // The runtime purges accounts left without lamports.
+        accounts.retain(|_, account| account.lamports > 0);
+        self.accounts = accounts;
+        Ok(())
+    }
+}
+
+/// What the runtime executing a transaction sees of delegation.
+struct View<'a> {
+    chain: Chain,
+    delegated: &'a HashSet<Pubkey>,
+}
+
+impl View<'_> {
+    /// Delegated accounts are owned by the delegation program on L1.
+    fn is_external(&self, key: &Pubkey) -> bool {
+        self.chain == Chain::L1 && self.delegated.contains(key)
+    }
+}
+
+fn execute(
+    accounts: &mut HashMap<Pubkey, Account>,
+    view: &View,
+    instructions: &[Instruction],
+    index: usize,
+    signers: &[Pubkey],
+) -> Result<(), ExecutionError> {
+    let instruction = &instructions[index];
+    if instruction.program_id != magic::ID {
+        return Err(ExecutionError::UnsupportedProgram(instruction.program_id));
+    }
+    for meta in &instruction.accounts {
+        if meta.is_signer && !signers.contains(&meta.pubkey) {
+            return Err(ExecutionError::MissingSignature(meta.pubkey));
+        }
+        // Fee payers aside, the ER only writes accounts it holds.
+        if view.chain == Chain::Er
+            && meta.is_writable
+            && !meta.is_signer
+            && !view.delegated.contains(&meta.pubkey)
+        {
+            return Err(ExecutionError::NotDelegated(meta.pubkey));
+        }
+    }
+
+    let mut keys: Vec<Pubkey> = Vec::new();
+    for meta in &instruction.accounts {
+        if !keys.contains(&meta.pubkey) {
+            keys.push(meta.pubkey);
+        }
+    }
+    let inputs: Vec<Input> = keys
+        .iter()
+        .map(|key| {
+            let metas = || {
+                instruction
+                    .accounts
+                    .iter()
+                    .filter(|meta| meta.pubkey == *key)
+            };
+            let mut account = load(accounts, key, instructions, index);
+            if view.is_external(key) {
+                account.owner = ephemeral_rollups_sdk::id();
+            }
+            Input {
+                key: *key,
+                is_signer: metas().any(|meta| meta.is_signer),
+                is_writable: metas().any(|meta| meta.is_writable),
+                account,
+            }
+        })
+        .collect();
+
+    let mut buffer = Buffer::serialize(instruction, &keys, &inputs);
+    stubs::log(format!("Program {} invoke [1]", magic::ID));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let (program_id, account_infos, data) = unsafe { deserialize(buffer.as_mut_ptr()) };
+        magic::entry(program_id, &account_infos, data)
+    }));
+    match result {
+        Ok(Ok(())) => stubs::log(format!("Program {} success", magic::ID)),
+        Ok(Err(err)) => {
+            stubs::log(format!("Program {} failed: {err}", magic::ID));
+            return Err(ExecutionError::Program(err));
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| {
+                    panic
+                        .downcast_ref::<&str>()
+                        .map(|message| message.to_string())
+                })
+                .unwrap_or_default();
+            if message.contains(CPI_PANIC) {
+                return Err(ExecutionError::UnsupportedCpi);
+            }
+            return Err(ExecutionError::Panicked(message));
+        }
+    }
+
+    let outputs = buffer.read(&inputs);
+    let before: u128 = inputs
+        .iter()
+        .map(|input| input.account.lamports as u128)
+        .sum();
+    let after: u128 = outputs.iter().map(|output| output.lamports as u128).sum();
+    if before != after {
+        return Err(ExecutionError::UnbalancedLamports);
+    }
+    for (input, output) in inputs.iter().zip(outputs) {
+        if view.is_external(&input.key) {
+            if output != input.account {
+                return Err(ExecutionError::ExternalAccountModified(input.key));
+            }
+            continue;
+        }
+        if !input.is_writable {
+            if output != input.account {
+                return Err(ExecutionError::ReadonlyModified(input.key));
+            }
+            continue;
+        }
+        accounts.insert(input.key, output);
+    }
+    Ok(())
+}
+
+/// The stored account, or the sysvar contents for sysvar keys the program reads as accounts.
+fn load(
+    accounts: &HashMap<Pubkey, Account>,
+    key: &Pubkey,
+    instructions: &[Instruction],
+    index: usize,
+) -> Account {
+    let sysvar = |data: Vec<u8>| Account {
+        lamports: 1,
+        data,
+        owner: sysvar::ID,
+        executable: false,
+    };
+    if *key == sysvar::instructions::ID {
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|instruction| BorrowedInstruction {
+                program_id: &instruction.program_id,
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|meta: &AccountMeta| BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: &instruction.data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        let len = data.len();
+        data[len - 2..].copy_from_slice(&(index as u16).to_le_bytes());
+        return sysvar(data);
+    }
+    if *key == sysvar::clock::ID {
+        let clock = stubs::with(|context| context.clock.clone());
+        return sysvar(bincode::serialize(&clock).unwrap());
+    }
+    if *key == sysvar::rent::ID {
+        return sysvar(bincode::serialize(&Rent::default()).unwrap());
+    }
+    accounts.get(key).cloned().unwrap_or_else(|| Account {
+        owner: solana_sdk_ids::system_program::ID,
+        ..Account::default()
+    })
+}
+
+struct Input {
+    key: Pubkey,
+    is_signer: bool,
+    is_writable: bool,
+    account: Account,
+}
+
+/// The loader's serialized program input: accounts (with realloc headroom), instruction
+/// data and program id. Backed by `u64`s for the alignment `deserialize` expects.
+struct Buffer {
+    words: Vec<u64>,
+    /// Offset of each unique account's key.
+    key_offsets: Vec<usize>,
+}
+
+impl Buffer {
+    fn serialize(instruction: &Instruction, keys: &[Pubkey], inputs: &[Input]) -> Self {
+        let mut bytes = Vec::new();
+        let mut key_offsets = Vec::new();
+        bytes.extend_from_slice(&(instruction.accounts.len() as u64).to_le_bytes());
+        let mut seen = HashSet::new();
+        for meta in &instruction.accounts {
+            let position = keys.iter().position(|key| *key == meta.pubkey).unwrap();
+            if !seen.insert(position) {
+                let first = instruction
+                    .accounts
+                    .iter()
+                    .position(|other| other.pubkey == meta.pubkey)
+                    .unwrap();
+                bytes.push(first as u8);
+                bytes.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            let input = &inputs[position];
+            bytes.push(NON_DUP_MARKER);
+            bytes.push(input.is_signer as u8);
+            bytes.push(input.is_writable as u8);
+            bytes.push(input.account.executable as u8);
+            bytes.extend_from_slice(&[0; 4]);
+            key_offsets.push(bytes.len());
+            bytes.extend_from_slice(input.key.as_ref());
+            bytes.extend_from_slice(input.account.owner.as_ref());
+            bytes.extend_from_slice(&input.account.lamports.to_le_bytes());
+            bytes.extend_from_slice(&(input.account.data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&input.account.data);
+            bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            bytes.resize(bytes.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+            bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(instruction.data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&instruction.data);
+        bytes.extend_from_slice(instruction.program_id.as_ref());
+
+        let mut words = vec![0_u64; bytes.len().div_ceil(size_of::<u64>())];
+        let view =
+            unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, bytes.len()) };
+        view.copy_from_slice(&bytes);
+        Self { words, key_offsets }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.words.as_mut_ptr() as *mut u8
+    }
+
+    fn bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.words.as_ptr() as *const u8,
+                self.words.len() * size_of::<u64>(),
+            )
+        }
+    }
+
+    /// The accounts as the program left them, in `inputs` order.
+    fn read(&self, inputs: &[Input]) -> Vec<Account> {
+        let bytes = self.bytes();
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        inputs
+            .iter()
+            .zip(&self.key_offsets)
+            .map(|(input, &key)| {
+                let owner = key + 32;
+                let (lamports, data_len, data) = (owner + 32, owner + 40, owner + 48);
+                Account {
+                    lamports: u64_at(lamports),
+                    data: bytes[data..data + u64_at(data_len) as usize].to_vec(),
+                    owner: Pubkey::try_from(&bytes[owner..owner + 32]).unwrap(),
+                    executable: input.account.executable,
+                }
+            })
+            .collect()
+    }
+}
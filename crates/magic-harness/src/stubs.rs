@@ -0,0 +1,82 @@
+//! Syscall stubs for running `magic` natively. The stubs are process-wide, so the state they
+//! read (clock, logs, return data) is kept per thread, one harness per test thread.
+
+use std::{cell::RefCell, sync::Once};
+
+use anchor_lang::prelude::{Clock, Pubkey, Rent};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_epoch_schedule::EpochSchedule;
+use solana_sysvar::program_stubs::{set_syscall_stubs, SyscallStubs};
+
+const SUCCESS: u64 = 0;
+
+#[derive(Default)]
+pub(crate) struct Context {
+    pub clock: Clock,
+    pub logs: Vec<String>,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::default());
+}
+
+pub(crate) fn with<R>(f: impl FnOnce(&mut Context) -> R) -> R {
+    CONTEXT.with(|context| f(&mut context.borrow_mut()))
+}
+
+pub(crate) fn log(message: String) {
+    with(|context| context.logs.push(message));
+}
+
+pub(crate) fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        log(format!("Program log: {message}"));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<String> = fields.iter().map(|field| BASE64.encode(field)).collect();
+        log(format!("Program data: {}", fields.join(" ")));
+    }
+
+    fn sol_log_compute_units(&self) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = with(|context| context.clock.clone());
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut EpochSchedule) = EpochSchedule::default() };
+        SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with(|context| context.return_data.clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        with(|context| {
+            context.return_data = (!data.is_empty()).then(|| (magic::ID, data.to_vec()));
+        });
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        1
+    }
+}
@@ -0,0 +1,204 @@
+//! [`Svm`] runs the compiled program in LiteSVM, for the instructions the native [`Harness`]
+//! cannot: everything that makes a CPI, such as `init`, delegation and permissions.
+//!
+//! It loads the program `anchor build` leaves in `target/deploy/magic.so` (or the file
+//! `MAGIC_PROGRAM_SO` names) next to three stubs. The delegation stub copies the delegate
+//! buffer back into the delegated account, so a delegated PDA keeps its state and is owned
+//! by the delegation program as on-chain; every other delegation instruction succeeds
+//! without effect. The permission and magic (commit and crank scheduling) stubs accept
+//! every instruction without effect.
+//!
+//! [`Harness`]: crate::Harness
+
+use std::path::PathBuf;
+
+use anchor_lang::{
+    prelude::{Clock, ProgramError, Pubkey},
+    solana_program::instruction::Instruction,
+    AccountDeserialize,
+};
+use ephemeral_rollups_sdk::consts::{
+    DELEGATION_PROGRAM_ID, MAGIC_PROGRAM_ID, PERMISSION_PROGRAM_ID,
+};
+use litesvm::{
+    error::LiteSVMError,
+    types::{FailedTransactionMetadata, TransactionMetadata},
+    LiteSVM,
+};
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_program_runtime::{
+    declare_process_instruction, invoke_context::BuiltinFunctionWithContext,
+};
+use solana_sdk_ids::native_loader;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+use crate::{ExecutionError, Failure, GENESIS_TIMESTAMP};
+
+/// Builtins must consume compute units; the stubs charge a nominal amount.
+const STUB_COMPUTE_UNITS: u64 = 1_000;
+/// Discriminator of the delegation program's `delegate` instruction.
+const DELEGATE: [u8; 8] = [0; 8];
+/// Account indices of `delegate`: `[payer, delegated, owner_program, buffer, ..]`.
+const DELEGATED_ACCOUNT: u16 = 1;
+const DELEGATE_BUFFER: u16 = 3;
+
+declare_process_instruction!(DelegationStub, STUB_COMPUTE_UNITS, |invoke_context| {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction = transaction_context.get_current_instruction_context()?;
+    if !instruction.get_instruction_data().starts_with(&DELEGATE) {
+        return Ok(());
+    }
+    let state = instruction
+        .try_borrow_instruction_account(transaction_context, DELEGATE_BUFFER)?
+        .get_data()
+        .to_vec();
+    instruction
+        .try_borrow_instruction_account(transaction_context, DELEGATED_ACCOUNT)?
+        .set_data_from_slice(&state)
+});
+
+declare_process_instruction!(PermissionStub, STUB_COMPUTE_UNITS, |_invoke_context| {
+    Ok(())
+});
+
+declare_process_instruction!(MagicStub, STUB_COMPUTE_UNITS, |_invoke_context| { Ok(()) });
+
+pub struct Svm {
+    svm: LiteSVM,
+}
+
+impl Svm {
+    /// The compiled program: `MAGIC_PROGRAM_SO` if set, else `target/deploy/magic.so`.
+    pub fn program_path() -> PathBuf {
+        std::env::var_os("MAGIC_PROGRAM_SO")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/magic.so")
+            })
+    }
+
+    /// Fails if the program has not been built.
+    pub fn new() -> Result<Self, LiteSVMError> {
+        Self::with_sigverify(true)
+    }
+
+    /// Like [`Svm::new`], but signatures are not checked, so [`Svm::process_unsigned`] can
+    /// act for keys whose secret is unknown, such as the bidder a fixture proof is for.
+    pub fn without_sigverify() -> Result<Self, LiteSVMError> {
+        Self::with_sigverify(false)
+    }
+
+    fn with_sigverify(sigverify: bool) -> Result<Self, LiteSVMError> {
+        let mut svm = LiteSVM::new().with_sigverify(sigverify);
+        svm.add_program_from_file(magic::ID, Self::program_path())?;
+        let mut svm = Self { svm };
+        svm.add_stub(DELEGATION_PROGRAM_ID, DelegationStub::vm);
+        svm.add_stub(PERMISSION_PROGRAM_ID, PermissionStub::vm);
+        svm.add_stub(MAGIC_PROGRAM_ID, MagicStub::vm);
+        svm.warp_to_timestamp(GENESIS_TIMESTAMP);
+        Ok(svm)
+    }
+
+    /// Registers `entrypoint` as a builtin. LiteSVM leaves the program account owned by the
+    /// BPF loader, which cannot run builtins, so it is handed to the native loader.
+    fn add_stub(&mut self, program_id: Pubkey, entrypoint: BuiltinFunctionWithContext) {
+        self.svm.add_builtin(program_id, entrypoint);
+        self.set_account(
+            program_id,
+            Account {
+                lamports: 1,
+                data: Vec::new(),
+                owner: native_loader::ID,
+                executable: true,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.svm.get_sysvar()
+    }
+
+    /// Moves the clock to `unix_timestamp`, advancing the slot so each warp is distinct.
+    pub fn warp_to_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock();
+        clock.unix_timestamp = unix_timestamp;
+        clock.slot += 1;
+        self.svm.set_sysvar(&clock);
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        self.svm.airdrop(key, lamports).expect("airdrop");
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.svm.set_account(key, account).expect("set_account");
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<Account> {
+        self.svm.get_account(key)
+    }
+
+    /// Decodes `key` as `T`, if it exists and holds one.
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> Option<T> {
+        let account = self.account(key)?;
+        T::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// Sends `instructions` in one transaction paid by the first signer.
+    pub fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<TransactionMetadata, Failure> {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        self.send(transaction)
+    }
+
+    /// Sends `instructions` paid by `payer` with placeholder signatures. Only an `Svm`
+    /// made by [`Svm::without_sigverify`] accepts them.
+    pub fn process_unsigned(
+        &mut self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+    ) -> Result<TransactionMetadata, Failure> {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(payer));
+        transaction.message.recent_blockhash = self.svm.latest_blockhash();
+        self.send(transaction)
+    }
+
+    fn send(&mut self, transaction: Transaction) -> Result<TransactionMetadata, Failure> {
+        let result = self.svm.send_transaction(transaction);
+        // Identical transactions would otherwise be rejected as already processed.
+        self.svm.expire_blockhash();
+        result.map_err(failure)
+    }
+}
+
+fn failure(failed: FailedTransactionMetadata) -> Failure {
+    let (instruction, error) = match failed.err {
+        TransactionError::InstructionError(index, error) => (
+            usize::from(index),
+            ProgramError::try_from(error).map_or_else(
+                |error| {
+                    ExecutionError::Transaction(TransactionError::InstructionError(index, error))
+                },
+                ExecutionError::Program,
+            ),
+        ),
+        error => (0, ExecutionError::Transaction(error)),
+    };
+    Failure {
+        instruction,
+        error,
+        logs: failed.meta.logs,
+    }
+}
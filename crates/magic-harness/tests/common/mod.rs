@@ -0,0 +1,105 @@
+//! Seeds the accounts whose `init` needs a CPI, exactly as the program would create them.
+
+#![allow(dead_code)]
+
+use anchor_lang::prelude::Pubkey;
+use magic::{
+    state::{Auction, Bid, Deposit},
+    AuctionStatus, BidMode, CommitmentHash, CommitmentScheme,
+};
+use magic_client::pda::{auction_pda, bid_pda, deposit_pda};
+use magic_harness::Harness;
+
+pub const LAMPORTS: u64 = 10_000_000_000;
+
+#[derive(Debug)]
+pub struct Schedule {
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub reveal_end_ts: i64,
+    pub reserve_price: u64,
+}
+
+/// A sealed SHA-256/V2 auction as `create_auction` leaves it, created in the current slot.
+pub fn seed_auction(harness: &mut Harness, authority: &Pubkey, schedule: &Schedule) -> Pubkey {
+    let (auction, bump) = auction_pda(authority, 1);
+    let status = if harness.clock().unix_timestamp < schedule.start_ts {
+        AuctionStatus::Scheduled
+    } else {
+        AuctionStatus::Bidding
+    };
+    harness.set_state(
+        auction,
+        &Auction {
+            auction_id: 1,
+            authority: *authority,
+            start_ts: schedule.start_ts,
+            end_ts: schedule.end_ts,
+            reveal_end_ts: schedule.reveal_end_ts,
+            reserve_price: schedule.reserve_price,
+            highest_bid: 0,
+            highest_bidder: None,
+            total_bids: 0,
+            total_revealed: 0,
+            status,
+            bump,
+            version: Auction::VERSION,
+            bid_mode: BidMode::Sealed,
+            commitment_scheme: CommitmentScheme::V2,
+            commitment_hash: CommitmentHash::Sha256,
+            range_proof: false,
+            encryption_key: None,
+            derived_nonce: false,
+            creation_slot: harness.clock().slot,
+            demand_commitment: [0; 32],
+            aggregate_demand: None,
+            auto_finalize_task: None,
+        },
+    );
+    auction
+}
+
+/// An empty bid account, as `initialize_bid_account` leaves it.
+pub fn seed_bid(harness: &mut Harness, auction: &Pubkey, bidder: &Pubkey) -> Pubkey {
+    let auction_slot = harness.get::<Auction>(auction).unwrap().creation_slot;
+    let (bid, bump) = bid_pda(auction, auction_slot, bidder);
+    harness.set_state(
+        bid,
+        &Bid {
+            auction: *auction,
+            bidder: *bidder,
+            bid_hash: [0; 32],
+            committed: false,
+            revealed: false,
+            amount: 0,
+            nonce: [0; 32],
+            bump,
+            version: Bid::VERSION,
+            uncovered: false,
+            ciphertext: None,
+            reveal_agent: None,
+            auction_slot,
+        },
+    );
+    bid
+}
+
+/// A deposit escrowing `amount` lamports, as `deposit_escrow` leaves it.
+pub fn seed_deposit(harness: &mut Harness, auction: &Pubkey, bidder: &Pubkey, amount: u64) {
+    let (deposit, bump) = deposit_pda(auction, bidder);
+    harness.set_state(
+        deposit,
+        &Deposit {
+            auction: *auction,
+            bidder: *bidder,
+            amount,
+            bump,
+        },
+    );
+    harness.airdrop(&deposit, amount);
+}
+
+/// The nonce tests seal every bid with.
+pub fn nonce(bidder: &Pubkey) -> [u8; 32] {
+    bidder.to_bytes()
+}
@@ -0,0 +1,515 @@
+//! Model-based property test: random interleavings of commits, reveals, finalization,
+//! settlement, delegation and clock warps, checked step by step against a reference model.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{nonce, seed_auction, seed_bid, seed_deposit, Schedule, LAMPORTS};
+use magic::{
+    error::AuctionError,
+    handlers::status_at,
+    state::{Auction, Bid},
+    AuctionStatus,
+};
+use magic_client::{commitment::seal_bid, instruction, pda::bid_pda};
+use magic_harness::{Chain, Harness, GENESIS_TIMESTAMP};
+use proptest::prelude::*;
+
+const BIDDERS: usize = 4;
+const DEPOSIT: u64 = 150;
+
+#[derive(Clone, Copy, Debug)]
+enum Mark {
+    Start,
+    End,
+    RevealEnd,
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Warp(i64),
+    /// Warps to just around a schedule boundary, never backwards.
+    WarpTo(Mark, i64),
+    Delegate,
+    Undelegate,
+    Commit {
+        bidder: usize,
+        amount: u64,
+    },
+    Reveal {
+        bidder: usize,
+        honest: bool,
+        chain: Chain,
+    },
+    Finalize {
+        chain: Chain,
+    },
+    Settle,
+}
+
+fn chain() -> impl Strategy<Value = Chain> {
+    prop_oneof![Just(Chain::L1), Just(Chain::Er)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        2 => (0_i64..5).prop_map(Op::Warp),
+        1 => Just(Op::Delegate),
+        1 => Just(Op::Undelegate),
+        // Coarse amounts so ties and reserve-price boundaries come up.
+        4 => (0..BIDDERS, 0_u64..12)
+            .prop_map(|(bidder, tens)| Op::Commit { bidder, amount: tens * 10 }),
+        8 => (0..BIDDERS, prop::bool::weighted(0.8), chain())
+            .prop_map(|(bidder, honest, chain)| Op::Reveal { bidder, honest, chain }),
+        1 => chain().prop_map(|chain| Op::Finalize { chain }),
+        1 => Just(Op::Settle),
+    ]
+}
+
+/// Random operations in each phase, each phase entered at (or just around) its boundary.
+/// Time only moves forward, so without the phases most runs would skip past the reveal.
+fn ops() -> impl Strategy<Value = Vec<Op>> {
+    let phase = || (-1_i64..=1, prop::collection::vec(op(), 0..30));
+    (phase(), phase(), phase()).prop_map(|phases| {
+        let (bidding, revealing, after) = phases;
+        [
+            (Mark::Start, bidding),
+            (Mark::End, revealing),
+            (Mark::RevealEnd, after),
+        ]
+        .into_iter()
+        .flat_map(|(mark, (offset, ops))| std::iter::once(Op::WarpTo(mark, offset)).chain(ops))
+        .collect()
+    })
+}
+
+/// What the program should do with an operation.
+#[derive(Debug, PartialEq)]
+enum Expect {
+    Ok,
+    Code(u32),
+    /// Rejected by the runtime or Anchor's account checks, e.g. an account on the wrong chain.
+    Rejected,
+}
+
+fn fails(error: AuctionError) -> Expect {
+    Expect::Code(error.into())
+}
+
+#[derive(Clone, Copy, Default)]
+struct BidModel {
+    committed: bool,
+    revealed: bool,
+    amount: u64,
+}
+
+struct Model {
+    schedule: Schedule,
+    delegated: bool,
+    bids: [BidModel; BIDDERS],
+    /// Bidder keys, which break ties.
+    keys: Vec<Pubkey>,
+    highest: Option<(u64, usize)>,
+    finalized: bool,
+    settled: bool,
+}
+
+impl Model {
+    fn total_bids(&self) -> u32 {
+        self.bids.iter().filter(|bid| bid.committed).count() as u32
+    }
+
+    fn total_revealed(&self) -> u32 {
+        self.bids.iter().filter(|bid| bid.revealed).count() as u32
+    }
+
+    fn winner(&self) -> Option<usize> {
+        match self.highest {
+            Some((amount, bidder)) if !self.finalized || amount >= self.schedule.reserve_price => {
+                Some(bidder)
+            }
+            _ => None,
+        }
+    }
+
+    /// Ties go to the lower bidder key, whatever the reveal order.
+    fn outbids(&self, amount: u64, bidder: usize) -> bool {
+        match self.highest {
+            Some((highest, leader)) => {
+                amount > highest || (amount == highest && self.keys[bidder] < self.keys[leader])
+            }
+            None => amount > 0,
+        }
+    }
+
+    /// The status the program should report at `now`.
+    fn status(&self, now: i64) -> AuctionStatus {
+        if self.settled {
+            AuctionStatus::Settled
+        } else if self.finalized {
+            AuctionStatus::Finalized
+        } else if now < self.schedule.start_ts {
+            AuctionStatus::Scheduled
+        } else if now < self.schedule.end_ts {
+            AuctionStatus::Bidding
+        } else {
+            AuctionStatus::Revealing
+        }
+    }
+
+    fn on(&self, chain: Chain) -> bool {
+        (chain == Chain::Er) == self.delegated
+    }
+
+    /// Applies `op` at `now` and returns what the program should do.
+    fn step(&mut self, op: &Op, now: i64) -> Expect {
+        let schedule = &self.schedule;
+        match *op {
+            Op::Warp(_) | Op::WarpTo(..) => Expect::Ok,
+            Op::Delegate | Op::Undelegate => {
+                self.delegated = matches!(op, Op::Delegate);
+                Expect::Ok
+            }
+            Op::Commit { bidder, amount } => {
+                if !self.delegated {
+                    return Expect::Rejected;
+                }
+                if now < schedule.start_ts {
+                    return fails(AuctionError::AuctionNotStarted);
+                }
+                if now >= schedule.end_ts {
+                    return fails(AuctionError::BiddingClosed);
+                }
+                let bid = &mut self.bids[bidder];
+                if bid.committed {
+                    return fails(AuctionError::BidAlreadyCommitted);
+                }
+                bid.committed = true;
+                bid.amount = amount;
+                Expect::Ok
+            }
+            Op::Reveal {
+                bidder,
+                honest,
+                chain,
+            } => {
+                if !self.on(chain) {
+                    return Expect::Rejected;
+                }
+                if now < schedule.end_ts {
+                    return fails(AuctionError::RevealNotStarted);
+                }
+                if now >= schedule.reveal_end_ts {
+                    return fails(AuctionError::RevealClosed);
+                }
+                let bid = &mut self.bids[bidder];
+                if !bid.committed {
+                    return fails(AuctionError::BidNotCommitted);
+                }
+                if bid.revealed {
+                    return fails(AuctionError::AlreadyRevealed);
+                }
+                if !honest {
+                    return fails(AuctionError::InvalidReveal);
+                }
+                bid.revealed = true;
+                let amount = bid.amount;
+                if self.outbids(amount, bidder) {
+                    self.highest = Some((amount, bidder));
+                }
+                Expect::Ok
+            }
+            Op::Finalize { chain } => {
+                if !self.on(chain) {
+                    return Expect::Rejected;
+                }
+                if now < schedule.reveal_end_ts {
+                    return fails(AuctionError::RevealStillOpen);
+                }
+                if self.finalized {
+                    return fails(AuctionError::AuctionAlreadyFinalized);
+                }
+                self.finalized = true;
+                Expect::Ok
+            }
+            Op::Settle => {
+                if self.delegated {
+                    return Expect::Rejected;
+                }
+                if !self.finalized {
+                    return fails(AuctionError::AuctionNotFinalized);
+                }
+                if self.settled {
+                    return fails(AuctionError::AuctionAlreadySettled);
+                }
+                self.settled = true;
+                Expect::Ok
+            }
+        }
+    }
+}
+
+struct World {
+    harness: Harness,
+    authority: Pubkey,
+    auction: Pubkey,
+    /// The auction's `creation_slot`, which seeds its bid PDAs.
+    slot: u64,
+    bidders: Vec<Pubkey>,
+}
+
+impl World {
+    fn new(schedule: &Schedule) -> Self {
+        let mut harness = Harness::new();
+        let authority = Pubkey::new_unique();
+        harness.airdrop(&authority, LAMPORTS);
+        let auction = seed_auction(&mut harness, &authority, schedule);
+        let bidders: Vec<Pubkey> = (0..BIDDERS).map(|_| Pubkey::new_unique()).collect();
+        for bidder in &bidders {
+            harness.airdrop(bidder, LAMPORTS);
+            seed_bid(&mut harness, &auction, bidder);
+            seed_deposit(&mut harness, &auction, bidder, DEPOSIT);
+        }
+        let slot = harness.get::<Auction>(&auction).unwrap().creation_slot;
+        let mut world = Self {
+            harness,
+            authority,
+            auction,
+            slot,
+            bidders,
+        };
+        // Bidding starts on the ER, as `delegate_auction` and `delegate_bid` leave it.
+        for key in world.delegated_accounts() {
+            world.harness.delegate(&key);
+        }
+        world
+    }
+
+    fn auction(&self) -> Auction {
+        self.harness.get(&self.auction).unwrap()
+    }
+
+    fn bid(&self, bidder: usize) -> Bid {
+        let key = bid_pda(&self.auction, self.slot, &self.bidders[bidder]).0;
+        self.harness.get(&key).unwrap()
+    }
+
+    /// The accounts a delegated auction hands to the ER: the auction and its bids.
+    fn delegated_accounts(&self) -> Vec<Pubkey> {
+        let bids = self
+            .bidders
+            .iter()
+            .map(|bidder| bid_pda(&self.auction, self.slot, bidder).0);
+        std::iter::once(self.auction).chain(bids).collect()
+    }
+
+    fn apply(&mut self, op: &Op, model: &Model) -> Result<(), Option<u32>> {
+        let now = self.harness.clock().unix_timestamp;
+        let run = |world: &mut Self, chain, instruction, signer| {
+            world
+                .harness
+                .process_on(chain, &[instruction], &[signer])
+                .map_err(|failure| failure.code())
+        };
+        match *op {
+            Op::Warp(seconds) => {
+                self.harness.warp_to_timestamp(now + seconds);
+                Ok(())
+            }
+            Op::WarpTo(mark, offset) => {
+                let schedule = &model.schedule;
+                let target = match mark {
+                    Mark::Start => schedule.start_ts,
+                    Mark::End => schedule.end_ts,
+                    Mark::RevealEnd => schedule.reveal_end_ts,
+                };
+                self.harness.warp_to_timestamp(now.max(target + offset));
+                Ok(())
+            }
+            Op::Delegate => {
+                for key in self.delegated_accounts() {
+                    self.harness.delegate(&key);
+                }
+                Ok(())
+            }
+            Op::Undelegate => {
+                for key in self.delegated_accounts() {
+                    self.harness.undelegate(&key);
+                }
+                Ok(())
+            }
+            Op::Commit { bidder, amount } => {
+                let key = self.bidders[bidder];
+                let bid_hash =
+                    seal_bid(&self.auction(), &self.auction, &key, amount, &nonce(&key)).unwrap();
+                let instruction = instruction::submit_sealed_bid_delegated(
+                    &self.auction,
+                    self.slot,
+                    &key,
+                    bid_hash,
+                    None,
+                    None,
+                );
+                run(self, Chain::Er, instruction, key)
+            }
+            Op::Reveal {
+                bidder,
+                honest,
+                chain,
+            } => {
+                let key = self.bidders[bidder];
+                let amount = model.bids[bidder].amount + u64::from(!honest);
+                let instruction = instruction::reveal_bid(
+                    &self.auction,
+                    self.slot,
+                    &key,
+                    amount,
+                    nonce(&key),
+                    false,
+                );
+                run(self, chain, instruction, key)
+            }
+            Op::Finalize { chain } => {
+                let instruction = instruction::finalize_auction(&self.auction, &self.authority);
+                run(self, chain, instruction, self.authority)
+            }
+            Op::Settle => {
+                let winner = self.auction().highest_bidder;
+                let instruction = instruction::settle_auction(
+                    &self.auction,
+                    &self.authority,
+                    winner,
+                    &self.authority,
+                    self.auction().creation_slot,
+                    &[],
+                );
+                run(self, Chain::L1, instruction, self.authority)
+            }
+        }
+    }
+}
+
+fn schedule() -> impl Strategy<Value = Schedule> {
+    (
+        0_i64..20,
+        1_i64..40,
+        1_i64..40,
+        (0_u64..12).prop_map(|tens| tens * 10),
+    )
+        .prop_map(|(delay, bidding, revealing, reserve_price)| {
+            let start_ts = GENESIS_TIMESTAMP + delay;
+            Schedule {
+                start_ts,
+                end_ts: start_ts + bidding,
+                reveal_end_ts: start_ts + bidding + revealing,
+                reserve_price,
+            }
+        })
+}
+
+fn check_invariants(world: &World, model: &Model) -> Result<(), TestCaseError> {
+    let auction = world.auction();
+    prop_assert!(auction.total_revealed <= auction.total_bids);
+    prop_assert_eq!(auction.total_bids, model.total_bids());
+    prop_assert_eq!(auction.total_revealed, model.total_revealed());
+
+    // No double reveals: every counted reveal is a distinct revealed bid.
+    let bids: Vec<Bid> = (0..BIDDERS).map(|bidder| world.bid(bidder)).collect();
+    let revealed = bids.iter().filter(|bid| bid.revealed).count() as u32;
+    prop_assert_eq!(revealed, auction.total_revealed);
+    for (bid, expected) in bids.iter().zip(&model.bids) {
+        prop_assert_eq!(bid.committed, expected.committed);
+        prop_assert_eq!(bid.revealed, expected.revealed);
+    }
+
+    // The leader is the highest revealed amount, and a finalized winner meets the reserve.
+    let max_revealed = bids
+        .iter()
+        .filter(|bid| bid.revealed)
+        .map(|bid| bid.amount)
+        .max()
+        .unwrap_or(0);
+    prop_assert_eq!(auction.highest_bid, max_revealed);
+    let winner = model.winner().map(|bidder| world.bidders[bidder]);
+    prop_assert_eq!(auction.highest_bidder, winner);
+    if let (true, Some(_)) = (model.finalized, auction.highest_bidder) {
+        prop_assert!(auction.highest_bid >= auction.reserve_price);
+    }
+
+    // Stored statuses may lag the clock, never lead it or skip a handler's transition.
+    let now = world.harness.clock().unix_timestamp;
+    prop_assert_eq!(status_at(&auction, now), model.status(now));
+    Ok(())
+}
+
+/// Everything but the settled status, which settlement is the one step allowed to change.
+fn frozen(world: &World) -> Vec<u8> {
+    let mut auction = world.auction();
+    if auction.status == AuctionStatus::Settled {
+        auction.status = AuctionStatus::Finalized;
+    }
+    let mut bytes = borsh_bytes(&auction);
+    for bidder in 0..BIDDERS {
+        bytes.extend(borsh_bytes(&world.bid(bidder)));
+    }
+    bytes
+}
+
+fn borsh_bytes<T: anchor_lang::AnchorSerialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value.serialize(&mut bytes).unwrap();
+    bytes
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn random_interleavings_preserve_auction_invariants(
+        schedule in schedule(),
+        ops in ops(),
+    ) {
+        let mut world = World::new(&schedule);
+        let mut model = Model {
+            schedule,
+            delegated: true,
+            bids: [BidModel::default(); BIDDERS],
+            keys: world.bidders.clone(),
+            highest: None,
+            finalized: false,
+            settled: false,
+        };
+        let mut finalized_state = None;
+
+        for op in &ops {
+            let now = world.harness.clock().unix_timestamp;
+            let authority_before = world.harness.account(&world.authority).unwrap().lamports;
+            let before = frozen(&world);
+
+            let result = world.apply(op, &model);
+            let expected = model.step(op, now);
+            match (&expected, &result) {
+                (Expect::Ok, Ok(())) => {}
+                (Expect::Code(code), Err(Some(actual))) => prop_assert_eq!(code, actual, "{:?}", op),
+                (Expect::Rejected, Err(_)) => {}
+                _ => prop_assert!(false, "{:?}: expected {:?}, got {:?}", op, expected, result),
+            }
+            if result.is_err() {
+                prop_assert_eq!(&frozen(&world), &before, "failed {:?} changed state", op);
+            }
+
+            if let (Op::Settle, Ok(())) = (op, &result) {
+                let auction = world.auction();
+                let paid = auction.highest_bidder.map_or(0, |_| auction.highest_bid.min(DEPOSIT));
+                let authority_after = world.harness.account(&world.authority).unwrap().lamports;
+                prop_assert_eq!(authority_after, authority_before + paid);
+            }
+
+            check_invariants(&world, &model)?;
+            if model.finalized {
+                let state = finalized_state.get_or_insert_with(|| frozen(&world));
+                prop_assert_eq!(&frozen(&world), state, "finalized auction mutated by {:?}", op);
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Instructions that make CPIs, run against the compiled program in LiteSVM. They need
+//! `target/deploy/magic.so` from `anchor build` (or `MAGIC_PROGRAM_SO`), and pass with a
+//! note on stderr when it is missing.
+
+use anchor_lang::prelude::Pubkey;
+use ephemeral_rollups_sdk::consts::DELEGATION_PROGRAM_ID;
+use magic::{
+    constant::RANGE_PROOF_PUBLIC_INPUTS,
+    error::AuctionError,
+    state::{Auction, Bid},
+    AuctionOptions, AuctionStatus, BidMode, CommitmentHash, CommitmentScheme,
+    RangeProofVerifyingKey,
+};
+use magic_client::{
+    instruction,
+    pda::{auction_pda, bid_pda, range_proof_key_pda},
+};
+use magic_harness::{svm::Svm, GENESIS_TIMESTAMP};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+const START: i64 = GENESIS_TIMESTAMP + 10;
+const END: i64 = GENESIS_TIMESTAMP + 100;
+const REVEAL_END: i64 = GENESIS_TIMESTAMP + 200;
+const RESERVE: u64 = 50;
+const LAMPORTS: u64 = 10_000_000_000;
+
+fn options(commitment_hash: CommitmentHash, range_proof: bool) -> AuctionOptions {
+    AuctionOptions {
+        bid_mode: BidMode::Sealed,
+        commitment_scheme: CommitmentScheme::V2,
+        commitment_hash,
+        range_proof,
+        encryption_key: None,
+        derived_nonce: false,
+    }
+}
+
+/// A funded authority in a fresh `Svm`, or `None` when the program is not built.
+fn setup() -> Option<(Svm, Keypair)> {
+    let path = Svm::program_path();
+    if !path.exists() {
+        eprintln!("skipped: {} not found, run `anchor build`", path.display());
+        return None;
+    }
+    let mut svm = Svm::new().expect("load the compiled program");
+    let authority = Keypair::new();
+    svm.airdrop(&authority.pubkey(), LAMPORTS);
+    Some((svm, authority))
+}
+
+fn create(svm: &mut Svm, authority: &Keypair, start_ts: i64, options: AuctionOptions) -> Pubkey {
+    svm.process(
+        &[instruction::create_auction(
+            &authority.pubkey(),
+            1,
+            start_ts,
+            END,
+            REVEAL_END,
+            RESERVE,
+            options,
+        )],
+        &[authority],
+    )
+    .unwrap();
+    auction_pda(&authority.pubkey(), 1).0
+}
+
+fn verifying_key() -> RangeProofVerifyingKey {
+    RangeProofVerifyingKey {
+        alpha_g1: [0; 64],
+        beta_g2: [0; 128],
+        gamma_g2: [0; 128],
+        delta_g2: [0; 128],
+        ic: [[0; 64]; RANGE_PROOF_PUBLIC_INPUTS + 1],
+    }
+}
+
+#[test]
+fn create_auction_schedules_a_future_auction() {
+    let Some((mut svm, authority)) = setup() else {
+        return;
+    };
+    let auction = create(
+        &mut svm,
+        &authority,
+        START,
+        options(CommitmentHash::Sha256, false),
+    );
+
+    let state = svm.get::<Auction>(&auction).unwrap();
+    assert_eq!(state.status, AuctionStatus::Scheduled);
+    assert_eq!(state.authority, authority.pubkey());
+    assert_eq!(state.start_ts, START);
+    assert_eq!(state.creation_slot, svm.clock().slot);
+}
+
+#[test]
+fn create_auction_rejects_a_start_in_the_past() {
+    let Some((mut svm, authority)) = setup() else {
+        return;
+    };
+    let failure = svm
+        .process(
+            &[instruction::create_auction(
+                &authority.pubkey(),
+                1,
+                GENESIS_TIMESTAMP - 1,
+                END,
+                REVEAL_END,
+                RESERVE,
+                options(CommitmentHash::Sha256, false),
+            )],
+            &[&authority],
+        )
+        .unwrap_err();
+    assert_eq!(failure.code(), Some(AuctionError::StartInPast.into()));
+    assert!(svm
+        .account(&auction_pda(&authority.pubkey(), 1).0)
+        .is_none());
+
+    // The current second is not in the past.
+    create(
+        &mut svm,
+        &authority,
+        GENESIS_TIMESTAMP,
+        options(CommitmentHash::Sha256, false),
+    );
+}
+
+#[test]
+fn range_proof_key_is_set_once_before_bidding() {
+    let Some((mut svm, authority)) = setup() else {
+        return;
+    };
+    let auction = create(
+        &mut svm,
+        &authority,
+        START,
+        options(CommitmentHash::Poseidon, true),
+    );
+    let set_key = |svm: &mut Svm| {
+        svm.process(
+            &[instruction::set_range_proof_key(
+                &auction,
+                &authority.pubkey(),
+                verifying_key(),
+            )],
+            &[&authority],
+        )
+    };
+
+    svm.warp_to_timestamp(START);
+    let failure = set_key(&mut svm).unwrap_err();
+    assert_eq!(
+        failure.code(),
+        Some(AuctionError::RangeProofKeyAfterStart.into())
+    );
+    assert!(svm.account(&range_proof_key_pda(&auction).0).is_none());
+
+    svm.warp_to_timestamp(START - 1);
+    set_key(&mut svm).unwrap();
+    assert!(set_key(&mut svm).is_err());
+}
+
+#[test]
+fn delegated_bid_keeps_its_state_under_the_delegation_program() {
+    let Some((mut svm, authority)) = setup() else {
+        return;
+    };
+    let auction = create(
+        &mut svm,
+        &authority,
+        START,
+        options(CommitmentHash::Sha256, false),
+    );
+    let slot = svm.get::<Auction>(&auction).unwrap().creation_slot;
+    let bidder = Keypair::new();
+    svm.airdrop(&bidder.pubkey(), LAMPORTS);
+
+    svm.process(
+        &[
+            instruction::initialize_bid_account(&auction, slot, &bidder.pubkey(), &bidder.pubkey()),
+            instruction::delegate_bid(&bidder.pubkey(), &auction, slot, &bidder.pubkey(), None),
+        ],
+        &[&bidder],
+    )
+    .unwrap();
+
+    let bid = bid_pda(&auction, slot, &bidder.pubkey()).0;
+    assert_eq!(svm.account(&bid).unwrap().owner, DELEGATION_PROGRAM_ID);
+    let state = svm.get::<Bid>(&bid).unwrap();
+    assert_eq!(state.bidder, bidder.pubkey());
+    assert_eq!(state.auction_slot, slot);
+}
+
+#[test]
+fn pedersen_commitments_need_sealed_bids() {
+    let Some((mut svm, authority)) = setup() else {
+        return;
+    };
+    for bid_mode in [BidMode::Private, BidMode::Encrypted] {
+        let encryption_key = (bid_mode == BidMode::Encrypted)
+            .then(|| magic::helpers::encryption_public_key(&[7; 32]).unwrap());
+        let failure = svm
+            .process(
+                &[instruction::create_auction(
+                    &authority.pubkey(),
+                    1,
+                    START,
+                    END,
+                    REVEAL_END,
+                    RESERVE,
+                    AuctionOptions {
+                        bid_mode,
+                        encryption_key,
+                        ..options(CommitmentHash::Pedersen, false)
+                    },
+                )],
+                &[&authority],
+            )
+            .unwrap_err();
+        assert_eq!(failure.code(), Some(AuctionError::BidModeMismatch.into()));
+    }
+    create(
+        &mut svm,
+        &authority,
+        START,
+        options(CommitmentHash::Pedersen, false),
+    );
+}
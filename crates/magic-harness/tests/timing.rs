@@ -0,0 +1,867 @@
+mod common;
+
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, AnchorDeserialize, Discriminator,
+    Space,
+};
+use common::{nonce, seed_auction, seed_bid, seed_deposit, Schedule, LAMPORTS};
+use ephemeral_rollups_sdk::consts::DELEGATION_PROGRAM_ID;
+use magic::{
+    error::AuctionError,
+    helpers,
+    state::{Auction, Bid, Deposit},
+    AuctionOptions, AuctionState, AuctionStatus, BidCiphertext, BidMode, BidProof, BidReveal,
+    BidStatus, CommitmentHash, CommitmentScheme,
+};
+use magic_client::{
+    commitment::{prove_opening, seal_bid},
+    instruction, outcome,
+    pda::{auction_pda, bid_pda, deposit_pda, permission_pda},
+};
+use magic_harness::{Account, ExecutionError, Failure, Harness, GENESIS_TIMESTAMP};
+
+const START: i64 = GENESIS_TIMESTAMP + 10;
+const END: i64 = GENESIS_TIMESTAMP + 100;
+const REVEAL_END: i64 = GENESIS_TIMESTAMP + 200;
+const RESERVE: u64 = 50;
+/// What every bidder escrows, so reveals above it are uncovered.
+const DEPOSIT: u64 = 100;
+
+struct Setup {
+    harness: Harness,
+    authority: Pubkey,
+    auction: Pubkey,
+}
+
+fn setup() -> Setup {
+    let mut harness = Harness::new();
+    let authority = Pubkey::new_unique();
+    harness.airdrop(&authority, LAMPORTS);
+    let auction = seed_auction(
+        &mut harness,
+        &authority,
+        &Schedule {
+            start_ts: START,
+            end_ts: END,
+            reveal_end_ts: REVEAL_END,
+            reserve_price: RESERVE,
+        },
+    );
+    Setup {
+        harness,
+        authority,
+        auction,
+    }
+}
+
+fn code(error: AuctionError) -> Option<u32> {
+    Some(error.into())
+}
+
+impl Setup {
+    fn auction(&self) -> Auction {
+        self.harness.get(&self.auction).unwrap()
+    }
+
+    /// The auction's `creation_slot`, which seeds its bid PDAs.
+    fn slot(&self) -> u64 {
+        self.auction().creation_slot
+    }
+
+    fn bid_key(&self, bidder: &Pubkey) -> Pubkey {
+        bid_pda(&self.auction, self.slot(), bidder).0
+    }
+
+    /// The `Bid` and `Deposit` accounts of `bidders`, for `outcome::audit_accounts`.
+    fn accounts(&self, bidders: &[Pubkey]) -> (Vec<Bid>, Vec<Deposit>) {
+        bidders
+            .iter()
+            .map(|bidder| {
+                let bid = self.harness.get(&self.bid_key(bidder)).unwrap();
+                let deposit = deposit_pda(&self.auction, bidder).0;
+                (bid, self.harness.get(&deposit).unwrap())
+            })
+            .unzip()
+    }
+
+    fn bidder(&mut self) -> Pubkey {
+        let bidder = Pubkey::new_unique();
+        self.harness.airdrop(&bidder, LAMPORTS);
+        seed_bid(&mut self.harness, &self.auction, &bidder);
+        seed_deposit(&mut self.harness, &self.auction, &bidder, DEPOSIT);
+        bidder
+    }
+
+    /// Commits `amount` for a new bidder through the delegated (CPI-free) path.
+    fn commit(&mut self, amount: u64) -> Result<Pubkey, Failure> {
+        let bidder = self.bidder();
+        let bid_hash = seal_bid(
+            &self.auction(),
+            &self.auction,
+            &bidder,
+            amount,
+            &nonce(&bidder),
+        )
+        .unwrap();
+        self.harness.process(
+            &[instruction::submit_sealed_bid_delegated(
+                &self.auction,
+                self.slot(),
+                &bidder,
+                bid_hash,
+                None,
+                None,
+            )],
+            &[bidder],
+        )?;
+        Ok(bidder)
+    }
+
+    /// Runs a view instruction and decodes its return data.
+    fn view<T: AnchorDeserialize>(&mut self, view: Instruction) -> T {
+        self.harness.process(&[view], &[]).unwrap();
+        let (program, data) = self.harness.return_data().unwrap();
+        assert_eq!(program, magic::ID);
+        T::try_from_slice(&data).unwrap()
+    }
+
+    fn reveal(&mut self, bidder: &Pubkey, amount: u64) -> Result<(), Failure> {
+        self.harness.process(
+            &[instruction::reveal_bid(
+                &self.auction,
+                self.slot(),
+                bidder,
+                amount,
+                nonce(bidder),
+                false,
+            )],
+            &[*bidder],
+        )
+    }
+}
+
+#[test]
+fn bidding_opens_at_start_ts_and_closes_at_end_ts() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START - 1);
+    let failure = setup.commit(100).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::AuctionNotStarted));
+
+    setup.harness.warp_to_timestamp(END - 1);
+    setup.commit(100).unwrap();
+
+    setup.harness.warp_to_timestamp(END);
+    let failure = setup.commit(100).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::BiddingClosed));
+    assert_eq!(setup.auction().total_bids, 1);
+}
+
+#[test]
+fn reveals_only_land_inside_the_reveal_window() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let bidder = setup.commit(100).unwrap();
+
+    let failure = setup.reveal(&bidder, 100).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::RevealNotStarted));
+
+    setup.harness.warp_to_timestamp(REVEAL_END);
+    let failure = setup.reveal(&bidder, 100).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::RevealClosed));
+
+    setup.harness.warp_to_timestamp(END);
+    setup.reveal(&bidder, 100).unwrap();
+    let bid: Bid = setup.harness.get(&setup.bid_key(&bidder)).unwrap();
+    assert!(bid.revealed);
+    assert_eq!(bid.amount, 100);
+}
+
+#[test]
+fn sealed_auctions_finalize_on_the_highest_reveal() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let low = setup.commit(70).unwrap();
+    let high = setup.commit(90).unwrap();
+    let silent = setup.commit(500).unwrap();
+
+    setup.harness.warp_to_timestamp(END);
+    setup.reveal(&high, 90).unwrap();
+    setup.reveal(&low, 70).unwrap();
+    let failure = setup.reveal(&silent, 400).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::InvalidReveal));
+
+    let finalize = instruction::finalize_auction(&setup.auction, &setup.authority);
+    let failure = setup
+        .harness
+        .process(std::slice::from_ref(&finalize), &[setup.authority])
+        .unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::RevealStillOpen));
+
+    setup.harness.warp_to_timestamp(REVEAL_END);
+    setup
+        .harness
+        .process(&[finalize], &[setup.authority])
+        .unwrap();
+    let auction = setup.auction();
+    assert!(auction.status == AuctionStatus::Finalized);
+    assert_eq!(auction.highest_bidder, Some(high));
+    assert_eq!(auction.highest_bid, 90);
+    assert_eq!((auction.total_bids, auction.total_revealed), (3, 2));
+
+    let (bids, deposits) = setup.accounts(&[low, high, silent]);
+    let audit = outcome::audit_accounts(&setup.auction, &auction, &bids, &deposits);
+    assert!(audit.is_clean(), "{:?}", audit.discrepancies);
+    assert_eq!(audit.expected.clearing_price, 90);
+}
+
+#[test]
+fn bids_above_the_deposit_reveal_but_cannot_win() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let covered = setup.commit(80).unwrap();
+    let uncovered = setup.commit(DEPOSIT + 1).unwrap();
+
+    setup.harness.warp_to_timestamp(END);
+    setup.reveal(&uncovered, DEPOSIT + 1).unwrap();
+    setup.reveal(&covered, 80).unwrap();
+    let bid: Bid = setup.harness.get(&setup.bid_key(&uncovered)).unwrap();
+    assert!(bid.revealed && bid.uncovered);
+
+    setup.harness.warp_to_timestamp(REVEAL_END);
+    setup
+        .harness
+        .process(
+            &[instruction::finalize_auction(
+                &setup.auction,
+                &setup.authority,
+            )],
+            &[setup.authority],
+        )
+        .unwrap();
+    let auction = setup.auction();
+    assert_eq!(auction.highest_bidder, Some(covered));
+    assert_eq!(auction.highest_bid, 80);
+    assert_eq!(auction.total_revealed, 2);
+
+    let (bids, deposits) = setup.accounts(&[covered, uncovered]);
+    let audit = outcome::audit_accounts(&setup.auction, &auction, &bids, &deposits);
+    assert!(audit.is_clean(), "{:?}", audit.discrepancies);
+}
+
+#[test]
+fn settlement_records_a_missing_deposit_as_shortfall() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let winner = setup.commit(80).unwrap();
+    setup.harness.warp_to_timestamp(END);
+    setup.reveal(&winner, 80).unwrap();
+    setup.harness.warp_to_timestamp(REVEAL_END);
+    setup
+        .harness
+        .process(
+            &[instruction::finalize_auction(
+                &setup.auction,
+                &setup.authority,
+            )],
+            &[setup.authority],
+        )
+        .unwrap();
+
+    setup
+        .harness
+        .set_account(deposit_pda(&setup.auction, &winner).0, Account::default());
+    let balance = setup.harness.account(&setup.authority).unwrap().lamports;
+    setup
+        .harness
+        .process(
+            &[instruction::settle_auction(
+                &setup.auction,
+                &setup.authority,
+                Some(winner),
+                &setup.authority,
+                setup.auction().creation_slot,
+                &[],
+            )],
+            &[setup.authority],
+        )
+        .unwrap();
+    assert!(setup.auction().status == AuctionStatus::Settled);
+    assert_eq!(
+        setup.harness.account(&setup.authority).unwrap().lamports,
+        balance
+    );
+}
+
+#[test]
+fn views_report_the_status_the_handlers_enforce() {
+    let mut setup = setup();
+    let state = instruction::get_auction_state(&setup.auction);
+    let state_at = |setup: &mut Setup, now| {
+        setup.harness.warp_to_timestamp(now);
+        setup.view::<AuctionState>(state.clone())
+    };
+
+    let scheduled = state_at(&mut setup, START - 5);
+    assert_eq!(scheduled.status, AuctionStatus::Scheduled);
+    assert_eq!(scheduled.seconds_remaining, Some(5));
+    assert_eq!(state_at(&mut setup, START).status, AuctionStatus::Bidding);
+
+    let bidder = setup.commit(100).unwrap();
+    let silent = setup.commit(60).unwrap();
+    let outsider = Pubkey::new_unique();
+    let bidding = state_at(&mut setup, END - 1);
+    assert_eq!(bidding.seconds_remaining, Some(1));
+    assert_eq!((bidding.total_bids, bidding.total_revealed), (2, 0));
+
+    // The stored status lags until a handler writes the auction; the view does not.
+    let revealing = state_at(&mut setup, END);
+    assert_eq!(setup.auction().status, AuctionStatus::Bidding);
+    assert_eq!(revealing.status, AuctionStatus::Revealing);
+    assert_eq!(revealing.seconds_remaining, Some(REVEAL_END - END));
+    assert!(!revealing.finalizable);
+    setup.reveal(&bidder, 100).unwrap();
+    let status = |setup: &mut Setup, bidder| {
+        let view = instruction::get_bid_status(&setup.auction, setup.slot(), bidder);
+        let status: BidStatus = setup.view(view);
+        (status.committed, status.revealed)
+    };
+    assert_eq!(status(&mut setup, &bidder), (true, true));
+    // On L1 a delegated bid is still read from its last committed data.
+    setup.harness.delegate(&setup.bid_key(&bidder));
+    assert_eq!(status(&mut setup, &bidder), (true, true));
+    assert_eq!(status(&mut setup, &silent), (true, false));
+    assert_eq!(status(&mut setup, &outsider), (false, false));
+
+    let closed = state_at(&mut setup, REVEAL_END);
+    assert_eq!(closed.status, AuctionStatus::Revealing);
+    assert!(closed.finalizable);
+    assert_eq!(closed.seconds_remaining, None);
+    setup
+        .harness
+        .process(
+            &[instruction::finalize_auction(
+                &setup.auction,
+                &setup.authority,
+            )],
+            &[setup.authority],
+        )
+        .unwrap();
+    let finalized = state_at(&mut setup, REVEAL_END);
+    assert_eq!(finalized.status, AuctionStatus::Finalized);
+    assert!(!finalized.finalizable);
+}
+
+#[test]
+fn handlers_write_the_status_the_clock_implies() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let bidder = setup.commit(100).unwrap();
+    assert_eq!(setup.auction().status, AuctionStatus::Bidding);
+
+    // A rejected handler rolls its clock-driven transitions back with everything else.
+    let mut late = self::setup();
+    late.harness.warp_to_timestamp(END);
+    let failure = late.commit(100).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::BiddingClosed));
+    assert_eq!(late.auction().status, AuctionStatus::Scheduled);
+
+    setup.harness.warp_to_timestamp(END);
+    setup.reveal(&bidder, 100).unwrap();
+    assert_eq!(setup.auction().status, AuctionStatus::Revealing);
+
+    setup.harness.warp_to_timestamp(REVEAL_END);
+    setup
+        .harness
+        .process(
+            &[instruction::finalize_auction(
+                &setup.auction,
+                &setup.authority,
+            )],
+            &[setup.authority],
+        )
+        .unwrap();
+    assert_eq!(setup.auction().status, AuctionStatus::Finalized);
+}
+
+#[test]
+fn a_pinned_reveal_agent_does_not_lock_out_the_bidder() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let (agent, stranger) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut pinned = || {
+        let bidder = setup.commit(80).unwrap();
+        setup
+            .harness
+            .process(
+                &[instruction::set_reveal_agent(
+                    &setup.auction,
+                    setup.slot(),
+                    &bidder,
+                    Some(agent),
+                )],
+                &[bidder],
+            )
+            .unwrap();
+        bidder
+    };
+    let (single, batched) = (pinned(), pinned());
+    setup.harness.warp_to_timestamp(END);
+
+    let reveal_for = |setup: &mut Setup, revealer: &Pubkey| {
+        setup.harness.process(
+            &[instruction::reveal_bid_for(
+                &setup.auction,
+                setup.slot(),
+                &single,
+                revealer,
+                80,
+                nonce(&single),
+                false,
+            )],
+            &[*revealer],
+        )
+    };
+    let failure = reveal_for(&mut setup, &stranger).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::UnauthorizedRevealer));
+    reveal_for(&mut setup, &single).unwrap();
+
+    setup
+        .harness
+        .process(
+            &[instruction::reveal_bids_batch(
+                &setup.auction,
+                setup.slot(),
+                &batched,
+                &[batched],
+                vec![BidReveal {
+                    amount: 80,
+                    nonce: nonce(&batched),
+                }],
+            )],
+            &[batched],
+        )
+        .unwrap();
+    let revealed = |bidder: &Pubkey| {
+        setup
+            .harness
+            .get::<Bid>(&setup.bid_key(bidder))
+            .unwrap()
+            .revealed
+    };
+    assert!(revealed(&single) && revealed(&batched));
+    assert_eq!(setup.auction().total_revealed, 2);
+}
+
+#[test]
+fn only_auctions_without_bids_can_be_cancelled() {
+    let cancel = |setup: &mut Setup| {
+        let cancel = instruction::cancel_auction(&setup.auction, &setup.authority);
+        setup.harness.process(&[cancel], &[setup.authority])
+    };
+
+    let mut unbid = setup();
+    unbid.harness.warp_to_timestamp(START + 1);
+    cancel(&mut unbid).unwrap();
+    assert_eq!(unbid.auction().status, AuctionStatus::Cancelled);
+    let failure = unbid.commit(100).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::AuctionCancelled));
+    unbid.harness.warp_to_timestamp(REVEAL_END);
+    let finalize = instruction::finalize_auction(&unbid.auction, &unbid.authority);
+    let failure = unbid
+        .harness
+        .process(&[finalize], &[unbid.authority])
+        .unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::AuctionCancelled));
+
+    let mut bid = setup();
+    bid.harness.warp_to_timestamp(START);
+    bid.commit(100).unwrap();
+    let failure = cancel(&mut bid).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::AuctionHasBids));
+
+    let mut closed = setup();
+    closed.harness.warp_to_timestamp(END);
+    let failure = cancel(&mut closed).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::InvalidStatusTransition));
+    assert_eq!(closed.auction().status, AuctionStatus::Scheduled);
+}
+
+#[test]
+fn encrypted_auctions_finalize_without_the_key_after_reveal_end_ts() {
+    let mut setup = setup();
+    let secret_key = [7; 32];
+    let mut auction = setup.auction();
+    auction.bid_mode = BidMode::Encrypted;
+    auction.encryption_key = Some(helpers::encryption_public_key(&secret_key).unwrap());
+    setup.harness.set_state(setup.auction, &auction);
+
+    setup.harness.warp_to_timestamp(START);
+    let bidder = setup.bidder();
+    let ciphertext = BidCiphertext {
+        ephemeral_key: helpers::encryption_public_key(&[9; 32]).unwrap(),
+        masked_amount: [0; 8],
+    };
+    setup
+        .harness
+        .process(
+            &[instruction::submit_encrypted_bid(
+                &setup.auction,
+                setup.slot(),
+                &bidder,
+                ciphertext,
+            )],
+            &[bidder],
+        )
+        .unwrap();
+
+    let finalize = instruction::finalize_auction(&setup.auction, &setup.authority);
+    setup.harness.warp_to_timestamp(REVEAL_END - 1);
+    let failure = setup
+        .harness
+        .process(std::slice::from_ref(&finalize), &[setup.authority])
+        .unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::PrivateBidsUnresolved));
+
+    setup.harness.warp_to_timestamp(REVEAL_END);
+    let decrypt =
+        instruction::decrypt_and_reveal(&setup.auction, setup.slot(), secret_key, &[bidder]);
+    let failure = setup.harness.process(&[decrypt], &[]).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::RevealClosed));
+
+    setup
+        .harness
+        .process(&[finalize], &[setup.authority])
+        .unwrap();
+    let auction = setup.auction();
+    assert!(auction.status == AuctionStatus::Finalized);
+    assert_eq!(auction.highest_bidder, None);
+    assert_eq!((auction.total_bids, auction.total_revealed), (1, 0));
+    setup
+        .harness
+        .process(
+            &[instruction::withdraw_deposit(&setup.auction, &bidder)],
+            &[bidder],
+        )
+        .unwrap();
+}
+
+#[test]
+fn pedersen_bids_need_an_opening_proof_to_join_the_demand_sum() {
+    let mut setup = setup();
+    let mut auction = setup.auction();
+    auction.commitment_hash = CommitmentHash::Pedersen;
+    setup.harness.set_state(setup.auction, &auction);
+    setup.harness.warp_to_timestamp(START);
+
+    let bidder = setup.bidder();
+    let blinding = nonce(&bidder).map(|byte| byte & 0x0f);
+    let commitment = helpers::pedersen_commitment(100, &blinding).unwrap();
+    let slot = setup.slot();
+    let submit = |proof| {
+        instruction::submit_sealed_bid_delegated(
+            &setup.auction,
+            slot,
+            &bidder,
+            commitment,
+            None,
+            proof,
+        )
+    };
+
+    let failure = setup
+        .harness
+        .process(&[submit(None)], &[bidder])
+        .unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::OpeningProofRequired));
+
+    let other = Pubkey::new_unique();
+    let stolen = prove_opening(&setup.auction, &other, 100, &blinding).unwrap();
+    let failure = setup
+        .harness
+        .process(&[submit(Some(BidProof::Opening(stolen)))], &[bidder])
+        .unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::InvalidOpeningProof));
+
+    let proof = prove_opening(&setup.auction, &bidder, 100, &blinding).unwrap();
+    setup
+        .harness
+        .process(&[submit(Some(BidProof::Opening(proof)))], &[bidder])
+        .unwrap();
+    assert_eq!(setup.auction().demand_commitment, commitment);
+
+    setup.harness.warp_to_timestamp(END);
+    let open = instruction::open_aggregate_demand(&setup.auction, 100, blinding);
+    setup
+        .harness
+        .process(std::slice::from_ref(&open), &[])
+        .unwrap();
+    assert_eq!(setup.auction().aggregate_demand, Some(100));
+    let failure = setup.harness.process(&[open], &[]).unwrap_err();
+    assert_eq!(
+        failure.code(),
+        code(AuctionError::AggregateDemandAlreadyOpened)
+    );
+}
+
+#[test]
+fn a_recreated_auction_gets_fresh_bid_accounts() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let bidder = setup.commit(100).unwrap();
+    let (stale_slot, stale_bid) = (setup.slot(), setup.bid_key(&bidder));
+
+    // Closed and re-created at the same address in a later slot.
+    setup.harness.warp_to_slot(stale_slot + 10);
+    let auction = seed_auction(
+        &mut setup.harness,
+        &setup.authority,
+        &Schedule {
+            start_ts: START,
+            end_ts: END,
+            reveal_end_ts: REVEAL_END,
+            reserve_price: RESERVE,
+        },
+    );
+    assert_eq!(auction, setup.auction);
+    assert_ne!(setup.bid_key(&bidder), stale_bid);
+
+    let bid_hash = seal_bid(
+        &setup.auction(),
+        &setup.auction,
+        &bidder,
+        100,
+        &nonce(&bidder),
+    )
+    .unwrap();
+    let submit = |slot| {
+        instruction::submit_sealed_bid_delegated(&auction, slot, &bidder, bid_hash, None, None)
+    };
+    let failure = setup
+        .harness
+        .process(&[submit(stale_slot)], &[bidder])
+        .unwrap_err();
+    assert_eq!(
+        failure.code(),
+        Some(anchor_lang::error::ErrorCode::ConstraintSeeds.into())
+    );
+
+    // The earlier bid does not occupy the new instance's bid PDA.
+    seed_bid(&mut setup.harness, &setup.auction, &bidder);
+    setup
+        .harness
+        .process(&[submit(setup.slot())], &[bidder])
+        .unwrap();
+    assert_eq!(setup.auction().total_bids, 1);
+}
+
+#[test]
+fn settlement_leaves_permissions_that_are_not_back_on_l1() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let winner = setup.commit(80).unwrap();
+    let delegated = setup.commit(60).unwrap();
+    let undelegate = |setup: &Setup, bidders: &[Pubkey]| {
+        instruction::undelegate_bids(&setup.auction, &setup.authority, setup.slot(), bidders)
+    };
+
+    let failure = setup
+        .harness
+        .process(&[undelegate(&setup, &[winner])], &[setup.authority])
+        .unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::AuctionNotFinalized));
+
+    setup.harness.warp_to_timestamp(END);
+    setup.reveal(&winner, 80).unwrap();
+    setup.reveal(&delegated, 60).unwrap();
+    setup.harness.warp_to_timestamp(REVEAL_END);
+    setup
+        .harness
+        .process(
+            &[instruction::finalize_auction(
+                &setup.auction,
+                &setup.authority,
+            )],
+            &[setup.authority],
+        )
+        .unwrap();
+
+    // Bids are checked before anything is committed.
+    let failure = setup
+        .harness
+        .process(&[undelegate(&setup, &[winner])], &[setup.authority])
+        .unwrap_err();
+    assert_eq!(failure.error, ExecutionError::UnsupportedCpi);
+
+    // The winner's bid is back on L1 without a permission; the other is still delegated.
+    let bid = setup.bid_key(&delegated);
+    let mut account = setup.harness.account(&bid).unwrap().clone();
+    account.owner = DELEGATION_PROGRAM_ID;
+    setup.harness.set_account(bid, account);
+    setup
+        .harness
+        .process(
+            &[instruction::settle_auction(
+                &setup.auction,
+                &setup.authority,
+                Some(winner),
+                &setup.authority,
+                setup.slot(),
+                &[winner, delegated],
+            )],
+            &[setup.authority],
+        )
+        .unwrap();
+    assert!(setup.auction().status == AuctionStatus::Settled);
+}
+
+#[test]
+fn legacy_bids_migrate_to_the_versioned_layout() {
+    let mut setup = setup();
+    let bidder = Pubkey::new_unique();
+    let (bid, bump) = Pubkey::find_program_address(
+        &[b"bid", setup.auction.as_ref(), bidder.as_ref()],
+        &magic::ID,
+    );
+    // `Bid` as the first release wrote it: committed, not revealed, ending at `bump`.
+    let data = [
+        Bid::DISCRIMINATOR,
+        setup.auction.as_ref(),
+        bidder.as_ref(),
+        &[3; 32],
+        &[1, 0],
+        &0_u64.to_le_bytes(),
+        &[0; 32],
+        &[bump],
+    ]
+    .concat();
+    // Funded for the new size up front, so no transfer CPI is needed.
+    setup.harness.set_account(
+        bid,
+        Account {
+            lamports: LAMPORTS,
+            data,
+            owner: magic::ID,
+            executable: false,
+        },
+    );
+
+    let migrate = instruction::migrate_bid(&bid, &setup.authority);
+    setup
+        .harness
+        .process(std::slice::from_ref(&migrate), &[setup.authority])
+        .unwrap();
+    let migrated = setup.harness.get::<Bid>(&bid).unwrap();
+    assert_eq!(migrated.version, Bid::VERSION);
+    assert_eq!((migrated.auction, migrated.bidder), (setup.auction, bidder));
+    assert_eq!(migrated.bid_hash, [3; 32]);
+    assert!(migrated.committed && !migrated.uncovered);
+    assert_eq!(migrated.auction_slot, 0);
+    assert_eq!(
+        setup.harness.account(&bid).unwrap().data.len(),
+        8 + Bid::INIT_SPACE
+    );
+
+    let failure = setup
+        .harness
+        .process(&[migrate], &[setup.authority])
+        .unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::AccountAlreadyMigrated));
+}
+
+#[test]
+fn failed_transactions_leave_accounts_untouched() {
+    let mut setup = setup();
+    setup.harness.warp_to_timestamp(START);
+    let bidder = setup.commit(100).unwrap();
+    let before = setup.auction();
+
+    setup.harness.warp_to_timestamp(END);
+    let reveal = instruction::reveal_bid(
+        &setup.auction,
+        setup.slot(),
+        &bidder,
+        100,
+        nonce(&bidder),
+        false,
+    );
+    let finalize = instruction::finalize_auction(&setup.auction, &setup.authority);
+    let failure = setup
+        .harness
+        .process(&[reveal, finalize], &[bidder, setup.authority])
+        .unwrap_err();
+    assert_eq!(failure.instruction, 1);
+    assert_eq!(failure.code(), code(AuctionError::RevealStillOpen));
+
+    let after = setup.auction();
+    assert_eq!(after.total_revealed, before.total_revealed);
+    assert_eq!(after.highest_bid, before.highest_bid);
+}
+
+#[test]
+fn instructions_that_cpi_are_reported_as_unsupported() {
+    let mut harness = Harness::new();
+    let authority = Pubkey::new_unique();
+    harness.airdrop(&authority, LAMPORTS);
+    let options = AuctionOptions {
+        bid_mode: BidMode::Sealed,
+        commitment_scheme: CommitmentScheme::V2,
+        commitment_hash: CommitmentHash::Sha256,
+        range_proof: false,
+        encryption_key: None,
+        derived_nonce: false,
+    };
+
+    let failure = harness
+        .process(
+            &[instruction::create_auction(
+                &authority, 1, START, END, REVEAL_END, RESERVE, options,
+            )],
+            &[authority],
+        )
+        .unwrap_err();
+    assert_eq!(failure.error, ExecutionError::UnsupportedCpi);
+    assert!(harness.account(&auction_pda(&authority, 1).0).is_none());
+}
+
+#[test]
+fn prepare_private_bid_checks_each_stage_before_its_cpi() {
+    let mut setup = setup();
+    let prepare = |setup: &mut Setup, bidder: &Pubkey| {
+        setup.harness.process(
+            &[instruction::prepare_private_bid(
+                &setup.auction,
+                setup.slot(),
+                bidder,
+                bidder,
+                None,
+            )],
+            &[*bidder],
+        )
+    };
+
+    let existing = setup.bidder();
+    let failure = prepare(&mut setup, &existing).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::BidInitFailed));
+
+    // Lamports sent to the bid PDA ahead of time do not block stage 1.
+    let bidder = Pubkey::new_unique();
+    setup.harness.airdrop(&bidder, LAMPORTS);
+    let bid = setup.bid_key(&bidder);
+    setup.harness.airdrop(&bid, 1);
+    let permission = permission_pda(&bid).0;
+    setup.harness.set_account(
+        permission,
+        Account {
+            lamports: 1,
+            data: vec![1],
+            owner: Pubkey::new_unique(),
+            executable: false,
+        },
+    );
+    let failure = prepare(&mut setup, &bidder).unwrap_err();
+    assert_eq!(failure.code(), code(AuctionError::PermissionCreateFailed));
+
+    setup.harness.set_account(permission, Account::default());
+    let failure = prepare(&mut setup, &bidder).unwrap_err();
+    assert_eq!(failure.error, ExecutionError::UnsupportedCpi);
+}
@@ -0,0 +1,149 @@
+//! Decoding of the auction lifecycle events `magic` emits with `emit!`, which show up in
+//! transaction logs as `Program data: <base64>` lines.
+
+use anchor_lang::{prelude::Pubkey, AnchorDeserialize, Discriminator, Event};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use magic::event::{
+    AuctionCreated, AuctionFinalized, AuctionSettled, AuctionStatusChanged, BidCommitted,
+    BidRevealed, BidUncovered,
+};
+
+const INVOKE: &str = " invoke [";
+const DATA: &str = "Program data: ";
+
+pub enum AuctionEvent {
+    Created(AuctionCreated),
+    BidCommitted(BidCommitted),
+    BidRevealed(BidRevealed),
+    BidUncovered(BidUncovered),
+    Finalized(AuctionFinalized),
+    Settled(AuctionSettled),
+    StatusChanged(AuctionStatusChanged),
+}
+
+impl AuctionEvent {
+    /// Decodes discriminator-prefixed event bytes; other events yield `None`.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        fn body<E: Discriminator>(data: &[u8]) -> Option<&[u8]> {
+            data.strip_prefix(E::DISCRIMINATOR)
+        }
+        fn event<E: AnchorDeserialize>(mut body: &[u8]) -> Option<E> {
+            E::deserialize(&mut body).ok()
+        }
+
+        if let Some(body) = body::<AuctionCreated>(data) {
+            event(body).map(Self::Created)
+        } else if let Some(body) = body::<BidCommitted>(data) {
+            event(body).map(Self::BidCommitted)
+        } else if let Some(body) = body::<BidRevealed>(data) {
+            event(body).map(Self::BidRevealed)
+        } else if let Some(body) = body::<BidUncovered>(data) {
+            event(body).map(Self::BidUncovered)
+        } else if let Some(body) = body::<AuctionFinalized>(data) {
+            event(body).map(Self::Finalized)
+        } else if let Some(body) = body::<AuctionSettled>(data) {
+            event(body).map(Self::Settled)
+        } else if let Some(body) = body::<AuctionStatusChanged>(data) {
+            event(body).map(Self::StatusChanged)
+        } else {
+            None
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Created(event) => event.data(),
+            Self::BidCommitted(event) => event.data(),
+            Self::BidRevealed(event) => event.data(),
+            Self::BidUncovered(event) => event.data(),
+            Self::Finalized(event) => event.data(),
+            Self::Settled(event) => event.data(),
+            Self::StatusChanged(event) => event.data(),
+        }
+    }
+
+    pub fn auction(&self) -> Pubkey {
+        match self {
+            Self::Created(event) => event.auction,
+            Self::BidCommitted(event) => event.auction,
+            Self::BidRevealed(event) => event.auction,
+            Self::BidUncovered(event) => event.auction,
+            Self::Finalized(event) => event.auction,
+            Self::Settled(event) => event.auction,
+            Self::StatusChanged(event) => event.auction,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Created(_) => "AuctionCreated",
+            Self::BidCommitted(_) => "BidCommitted",
+            Self::BidRevealed(_) => "BidRevealed",
+            Self::BidUncovered(_) => "BidUncovered",
+            Self::Finalized(_) => "AuctionFinalized",
+            Self::Settled(_) => "AuctionSettled",
+            Self::StatusChanged(_) => "AuctionStatusChanged",
+        }
+    }
+}
+
+/// Events logged by `magic` itself, in order. Invocations are tracked so that data
+/// logged by other programs (or CPIs out of `magic`) is never mistaken for ours.
+pub fn parse_logs(logs: &[String]) -> Vec<AuctionEvent> {
+    let program = magic::ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        if let Some(data) = line.strip_prefix(DATA) {
+            if stack.last() == Some(&program.as_str()) {
+                events.extend(
+                    BASE64
+                        .decode(data)
+                        .ok()
+                        .and_then(|data| AuctionEvent::decode(&data)),
+                );
+            }
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            if let Some((id, _)) = rest.split_once(INVOKE) {
+                stack.push(id);
+            } else if rest.ends_with(" success") || rest.contains(" failed: ") {
+                stack.pop();
+            }
+        }
+    }
+    events
+}
+
+/// The log line `emit!` produces for `event`.
+#[cfg(test)]
+pub fn data_log(event: &AuctionEvent) -> String {
+    format!("{DATA}{}", BASE64.encode(event.encode()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_events_logged_by_magic_are_decoded() {
+        let auction = Pubkey::new_unique();
+        let settled = AuctionEvent::Settled(AuctionSettled { auction });
+        let other = Pubkey::new_unique();
+        let logs = [
+            format!("Program {} invoke [1]", magic::ID),
+            "Program log: Instruction: FinalizeAndSettle".to_string(),
+            format!("Program {other} invoke [2]"),
+            data_log(&settled),
+            format!("Program {other} success"),
+            data_log(&settled),
+            "Program data: bm90IGFuIGV2ZW50".to_string(),
+            format!("Program {} success", magic::ID),
+            data_log(&settled),
+        ];
+
+        let events = parse_logs(&logs);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind(), "AuctionSettled");
+        assert_eq!(events[0].auction(), auction);
+    }
+}
@@ -0,0 +1,132 @@
+//! `magic-indexer`: materializes auction events into SQLite, either by following an RPC
+//! node or by replaying saved blocks.
+
+mod events;
+mod source;
+mod store;
+
+use std::{path::PathBuf, thread, time::Duration};
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use magic_client::rpc::{RpcClient, RpcError};
+
+use crate::store::{Ingest, Store};
+
+/// Most slots requested from `getBlocks` at once.
+const BLOCK_BATCH: u64 = 500;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// SQLite database to write.
+    #[arg(long, env = "MAGIC_INDEXER_DB", default_value = "magic-indexer.sqlite")]
+    db: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index saved `getBlock` results (a JSON array, or one block per line), in order.
+    Replay { files: Vec<PathBuf> },
+    /// Index confirmed blocks from an RPC node as they land.
+    Follow {
+        #[arg(long, env = "MAGIC_RPC", default_value = "http://127.0.0.1:8899")]
+        url: String,
+        /// First slot to index; defaults to resuming after the last indexed slot, or the
+        /// finalized slot on an empty database.
+        #[arg(long)]
+        from_slot: Option<u64>,
+        #[arg(long, default_value_t = 1000)]
+        poll_ms: u64,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut store = Store::open(&cli.db)?;
+    match cli.command {
+        Command::Replay { files } => {
+            for file in files {
+                for block in source::read_blocks(&file)? {
+                    report(block.slot, store.ingest(&block)?);
+                }
+            }
+            Ok(())
+        }
+        Command::Follow {
+            url,
+            from_slot,
+            poll_ms,
+        } => {
+            let rpc = RpcClient::new(url);
+            let poll = Duration::from_millis(poll_ms);
+            let mut next = match from_slot.or(store.last_slot()?.map(|slot| slot + 1)) {
+                Some(slot) => slot,
+                None => rpc.get_slot("finalized")?,
+            };
+            loop {
+                match follow(&rpc, &mut store, next) {
+                    Ok(Some(slot)) => next = slot,
+                    Ok(None) => thread::sleep(poll),
+                    Err(err) if is_transient(&err) => {
+                        eprintln!("retrying after: {err:#}");
+                        thread::sleep(poll);
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+    }
+}
+
+/// Indexes the confirmed blocks from `next` up to the tip, returning the slot to continue
+/// from, or `None` when caught up. A reorg rewinds to where the forks diverged.
+fn follow(rpc: &RpcClient, store: &mut Store, next: u64) -> Result<Option<u64>> {
+    let tip = rpc.get_slot("confirmed")?;
+    if next > tip {
+        return Ok(None);
+    }
+    let end = tip.min(next + BLOCK_BATCH - 1);
+    for slot in rpc.get_blocks(next, end)? {
+        let Some(block) = rpc.get_block(slot)? else {
+            continue;
+        };
+        let outcome = store.ingest(&block)?;
+        report(slot, outcome);
+        if let Ingest::Indexed {
+            rolled_back_from: Some(from),
+            ..
+        } = outcome
+        {
+            if from < slot {
+                return Ok(Some(from));
+            }
+        }
+    }
+    store.prune(rpc.get_slot("finalized")?)?;
+    Ok(Some(end + 1))
+}
+
+fn is_transient(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<RpcError>()
+        .is_some_and(RpcError::is_transient)
+}
+
+fn report(slot: u64, outcome: Ingest) {
+    match outcome {
+        Ingest::Duplicate => {}
+        Ingest::Indexed {
+            events,
+            rolled_back_from,
+        } => {
+            if let Some(from) = rolled_back_from {
+                println!("slot {slot}: rolled back slots from {from} on an abandoned fork");
+            }
+            if events > 0 {
+                println!("slot {slot}: {events} event(s)");
+            }
+        }
+    }
+}
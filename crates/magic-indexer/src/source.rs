@@ -0,0 +1,30 @@
+//! Offline block sources: `getBlock` results saved to disk, either as a JSON array or one
+//! object per line (a ledger dump), each with its `slot` added.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use magic_client::rpc::{parse_block, RpcBlock};
+use serde_json::Value;
+
+pub fn read_blocks(path: &Path) -> Result<Vec<RpcBlock>> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let values = if text.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<Value>>(&text)?
+    } else {
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<_>>()?
+    };
+    values
+        .iter()
+        .map(|value| {
+            let slot = value["slot"]
+                .as_u64()
+                .ok_or_else(|| anyhow!("{}: block without a slot", path.display()))?;
+            parse_block(slot, value).with_context(|| format!("{}: slot {slot}", path.display()))
+        })
+        .collect()
+}
@@ -0,0 +1,581 @@
+//! SQLite materialization of auction events.
+//!
+//! Every decoded event is journaled with its slot, and `auctions`/`bids` are derived from
+//! the journal. When a block turns out to be on an abandoned fork, its slot and every
+//! later one are dropped from the journal and the auctions they touched are rebuilt from
+//! the events that remain.
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::Result;
+use magic_client::{
+    account::{bid_mode_name, hash_name, scheme_name, status_name},
+    rpc::RpcBlock,
+};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::events::{parse_logs, AuctionEvent};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+    slot INTEGER PRIMARY KEY,
+    blockhash TEXT NOT NULL,
+    parent_slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS events (
+    slot INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    signature TEXT NOT NULL,
+    auction TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (slot, position)
+);
+CREATE INDEX IF NOT EXISTS events_by_auction ON events (auction, slot, position);
+CREATE TABLE IF NOT EXISTS auctions (
+    address TEXT PRIMARY KEY,
+    authority TEXT NOT NULL,
+    start_ts INTEGER NOT NULL,
+    end_ts INTEGER NOT NULL,
+    reveal_end_ts INTEGER NOT NULL,
+    reserve_price INTEGER NOT NULL,
+    bid_mode TEXT NOT NULL,
+    commitment_scheme TEXT NOT NULL,
+    commitment_hash TEXT NOT NULL,
+    creation_slot INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    winner TEXT,
+    highest_bid INTEGER NOT NULL DEFAULT 0,
+    total_bids INTEGER NOT NULL DEFAULT 0,
+    total_revealed INTEGER NOT NULL DEFAULT 0,
+    updated_slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS bids (
+    auction TEXT NOT NULL,
+    bidder TEXT NOT NULL,
+    amount INTEGER,
+    committed_slot INTEGER,
+    revealed_slot INTEGER,
+    uncovered INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (auction, bidder)
+);
+";
+
+/// What `Store::ingest` did with a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ingest {
+    /// The block was already indexed.
+    Duplicate,
+    Indexed {
+        events: usize,
+        /// Set when indexing the block first rolled back every slot from here on.
+        rolled_back_from: Option<u64>,
+    },
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Highest indexed slot, where a follower resumes.
+    pub fn last_slot(&self) -> Result<Option<u64>> {
+        let slot: Option<i64> = self
+            .conn
+            .query_row("SELECT MAX(slot) FROM blocks", [], |row| row.get(0))?;
+        Ok(slot.map(|slot| slot as u64))
+    }
+
+    /// Indexes `block`, first rolling back any indexed slot it proves was on another fork:
+    /// slots past its parent, or its parent itself if the hashes disagree.
+    pub fn ingest(&mut self, block: &RpcBlock) -> Result<Ingest> {
+        let tx = self.conn.transaction()?;
+        if blockhash(&tx, block.slot)?.as_deref() == Some(block.blockhash.as_str()) {
+            return Ok(Ingest::Duplicate);
+        }
+
+        let parent = blockhash(&tx, block.parent_slot)?;
+        let rolled_back_from = if parent.is_some_and(|hash| hash != block.previous_blockhash) {
+            Some(block.parent_slot)
+        } else {
+            let orphaned: Option<i64> = tx.query_row(
+                "SELECT MIN(slot) FROM blocks WHERE slot > ?1",
+                [int(block.parent_slot)],
+                |row| row.get(0),
+            )?;
+            orphaned.map(|slot| slot as u64)
+        };
+        if let Some(slot) = rolled_back_from {
+            rollback(&tx, slot)?;
+        }
+
+        tx.execute(
+            "INSERT INTO blocks (slot, blockhash, parent_slot) VALUES (?1, ?2, ?3)",
+            params![int(block.slot), block.blockhash, int(block.parent_slot)],
+        )?;
+        let mut position = 0;
+        for transaction in block.transactions.iter().filter(|tx| !tx.failed) {
+            for event in parse_logs(&transaction.logs) {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO events (slot, position, signature, auction, kind, data)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        int(block.slot),
+                        position,
+                        transaction.signature,
+                        event.auction().to_string(),
+                        event.kind(),
+                        event.encode(),
+                    ],
+                )?;
+                if inserted > 0 {
+                    apply(&tx, block.slot, &event)?;
+                }
+                position += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(Ingest::Indexed {
+            events: position as usize,
+            rolled_back_from,
+        })
+    }
+
+    /// Forgets block hashes below `slot` (typically the finalized slot), which can no
+    /// longer be reorganized. The event journal is kept for rebuilds.
+    pub fn prune(&self, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM blocks WHERE slot < ?1 AND slot < (SELECT MAX(slot) FROM blocks)",
+            [int(slot)],
+        )?;
+        Ok(())
+    }
+}
+
+fn blockhash(tx: &Transaction, slot: u64) -> Result<Option<String>> {
+    Ok(tx
+        .query_row(
+            "SELECT blockhash FROM blocks WHERE slot = ?1",
+            [int(slot)],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Drops `slot` and everything after it, then replays the surviving events of every
+/// auction the dropped events touched.
+fn rollback(tx: &Transaction, slot: u64) -> Result<()> {
+    let auctions: Vec<String> = tx
+        .prepare("SELECT DISTINCT auction FROM events WHERE slot >= ?1")?
+        .query_map([int(slot)], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    tx.execute("DELETE FROM events WHERE slot >= ?1", [int(slot)])?;
+    tx.execute("DELETE FROM blocks WHERE slot >= ?1", [int(slot)])?;
+
+    for auction in auctions {
+        tx.execute("DELETE FROM auctions WHERE address = ?1", [&auction])?;
+        tx.execute("DELETE FROM bids WHERE auction = ?1", [&auction])?;
+        let events: Vec<(i64, Vec<u8>)> = tx
+            .prepare("SELECT slot, data FROM events WHERE auction = ?1 ORDER BY slot, position")?
+            .query_map([&auction], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (slot, data) in events {
+            if let Some(event) = AuctionEvent::decode(&data) {
+                apply(tx, slot as u64, &event)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn apply(tx: &Transaction, slot: u64, event: &AuctionEvent) -> Result<()> {
+    let auction = event.auction().to_string();
+    let slot = int(slot);
+    match event {
+        AuctionEvent::Created(event) => {
+            // A re-created auction starts over at the same address.
+            tx.execute("DELETE FROM bids WHERE auction = ?1", [&auction])?;
+            tx.execute(
+                "INSERT OR REPLACE INTO auctions (
+                    address, authority, start_ts, end_ts, reveal_end_ts, reserve_price,
+                    bid_mode, commitment_scheme, commitment_hash, creation_slot, status,
+                    updated_slot
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'scheduled', ?11)",
+                params![
+                    auction,
+                    event.authority.to_string(),
+                    event.start_ts,
+                    event.end_ts,
+                    event.reveal_end_ts,
+                    int(event.reserve_price),
+                    bid_mode_name(&event.bid_mode),
+                    scheme_name(&event.commitment_scheme),
+                    hash_name(&event.commitment_hash),
+                    int(event.creation_slot),
+                    slot,
+                ],
+            )?;
+        }
+        AuctionEvent::BidCommitted(event) => {
+            // A re-committed bid replaces the earlier commitment.
+            tx.execute(
+                "INSERT INTO bids (auction, bidder, committed_slot) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (auction, bidder) DO UPDATE SET
+                    amount = NULL, committed_slot = excluded.committed_slot, revealed_slot = NULL,
+                    uncovered = 0",
+                params![auction, event.bidder.to_string(), slot],
+            )?;
+            tx.execute(
+                "UPDATE auctions SET
+                    total_bids = (SELECT COUNT(*) FROM bids WHERE auction = ?1),
+                    updated_slot = ?2
+                 WHERE address = ?1",
+                params![auction, slot],
+            )?;
+        }
+        AuctionEvent::BidRevealed(event) => {
+            reveal(tx, &auction, &event.bidder, event.amount, false, slot)?;
+        }
+        AuctionEvent::BidUncovered(event) => {
+            reveal(tx, &auction, &event.bidder, event.amount, true, slot)?;
+        }
+        AuctionEvent::Finalized(event) => {
+            tx.execute(
+                "UPDATE auctions SET
+                    status = 'finalized', winner = ?2, highest_bid = ?3, total_bids = ?4,
+                    total_revealed = ?5, updated_slot = ?6
+                 WHERE address = ?1",
+                params![
+                    auction,
+                    event.winner.as_ref().map(Pubkey::to_string),
+                    int(event.highest_bid),
+                    event.total_bids,
+                    event.total_revealed,
+                    slot,
+                ],
+            )?;
+        }
+        AuctionEvent::Settled(_) => {
+            tx.execute(
+                "UPDATE auctions SET status = 'settled', updated_slot = ?2 WHERE address = ?1",
+                params![auction, slot],
+            )?;
+        }
+        AuctionEvent::StatusChanged(event) => {
+            tx.execute(
+                "UPDATE auctions SET status = ?2, updated_slot = ?3 WHERE address = ?1",
+                params![auction, status_name(&event.to), slot],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Records an opened bid. Uncovered bids count as revealed but never raise the
+/// highest bid, since they cannot win.
+fn reveal(
+    tx: &Transaction,
+    auction: &str,
+    bidder: &Pubkey,
+    amount: u64,
+    uncovered: bool,
+    slot: i64,
+) -> Result<()> {
+    tx.execute(
+        "INSERT INTO bids (auction, bidder, amount, revealed_slot, uncovered)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (auction, bidder) DO UPDATE SET
+            amount = excluded.amount, revealed_slot = excluded.revealed_slot,
+            uncovered = excluded.uncovered",
+        params![auction, bidder.to_string(), int(amount), slot, uncovered],
+    )?;
+    tx.execute(
+        "UPDATE auctions SET
+            highest_bid = MAX(highest_bid, ?2),
+            total_revealed = (
+                SELECT COUNT(*) FROM bids WHERE auction = ?1 AND revealed_slot IS NOT NULL
+            ),
+            updated_slot = ?3
+         WHERE address = ?1",
+        params![auction, if uncovered { 0 } else { int(amount) }, slot],
+    )?;
+    Ok(())
+}
+
+/// SQLite integers are signed; u64s are stored bit-for-bit.
+fn int(value: u64) -> i64 {
+    value as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use magic::{
+        event::{
+            AuctionCreated, AuctionFinalized, AuctionSettled, AuctionStatusChanged, BidCommitted,
+            BidRevealed, BidUncovered,
+        },
+        AuctionStatus, BidMode, CommitmentHash, CommitmentScheme,
+    };
+    use magic_client::rpc::RpcTransactionLogs;
+
+    use super::*;
+    use crate::{events::data_log, source::read_blocks};
+
+    fn block(slot: u64, parent_slot: u64, fork: &str, events: &[AuctionEvent]) -> RpcBlock {
+        let mut logs = vec![format!("Program {} invoke [1]", magic::ID)];
+        logs.extend(events.iter().map(data_log));
+        logs.push(format!("Program {} success", magic::ID));
+        RpcBlock {
+            slot,
+            blockhash: format!("{fork}{slot}"),
+            previous_blockhash: format!("{fork}{parent_slot}"),
+            parent_slot,
+            transactions: vec![RpcTransactionLogs {
+                signature: format!("sig-{fork}{slot}"),
+                failed: false,
+                logs,
+            }],
+        }
+    }
+
+    fn created(auction: Pubkey) -> AuctionEvent {
+        AuctionEvent::Created(AuctionCreated {
+            auction,
+            authority: Pubkey::new_unique(),
+            start_ts: 100,
+            end_ts: 200,
+            reveal_end_ts: 300,
+            reserve_price: 10,
+            bid_mode: BidMode::Sealed,
+            commitment_scheme: CommitmentScheme::V2,
+            commitment_hash: CommitmentHash::Sha256,
+            range_proof: false,
+            encryption_key: None,
+            derived_nonce: false,
+            creation_slot: 1,
+        })
+    }
+
+    fn status_changed(auction: Pubkey, from: AuctionStatus, to: AuctionStatus) -> AuctionEvent {
+        AuctionEvent::StatusChanged(AuctionStatusChanged { auction, from, to })
+    }
+
+    fn committed(auction: Pubkey, bidder: Pubkey) -> AuctionEvent {
+        AuctionEvent::BidCommitted(BidCommitted { auction, bidder })
+    }
+
+    fn revealed(auction: Pubkey, bidder: Pubkey, amount: u64) -> AuctionEvent {
+        AuctionEvent::BidRevealed(BidRevealed {
+            auction,
+            bidder,
+            amount,
+        })
+    }
+
+    fn auction_row(store: &Store, auction: &Pubkey) -> (String, Option<String>, i64, i64, i64) {
+        store
+            .conn
+            .query_row(
+                "SELECT status, winner, highest_bid, total_bids, total_revealed
+                 FROM auctions WHERE address = ?1",
+                [auction.to_string()],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .unwrap()
+    }
+
+    fn bids(store: &Store, auction: &Pubkey) -> Vec<(String, Option<i64>)> {
+        store
+            .conn
+            .prepare("SELECT bidder, amount FROM bids WHERE auction = ?1 ORDER BY committed_slot")
+            .unwrap()
+            .query_map([auction.to_string()], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn lifecycle_events_materialize_auctions_and_bids() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (auction, alice, bob) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let blocks = [
+            block(1, 0, "a", &[created(auction)]),
+            block(
+                2,
+                1,
+                "a",
+                &[committed(auction, alice), committed(auction, bob)],
+            ),
+            block(3, 2, "a", &[revealed(auction, alice, 50)]),
+            block(
+                5,
+                3,
+                "a",
+                &[
+                    AuctionEvent::Finalized(AuctionFinalized {
+                        auction,
+                        winner: Some(alice),
+                        highest_bid: 50,
+                        reserve_price: 10,
+                        total_bids: 2,
+                        total_revealed: 1,
+                    }),
+                    AuctionEvent::Settled(AuctionSettled { auction }),
+                ],
+            ),
+        ];
+        for block in &blocks {
+            assert!(matches!(
+                store.ingest(block).unwrap(),
+                Ingest::Indexed {
+                    rolled_back_from: None,
+                    ..
+                }
+            ));
+        }
+        assert_eq!(store.ingest(&blocks[1]).unwrap(), Ingest::Duplicate);
+        assert_eq!(store.last_slot().unwrap(), Some(5));
+
+        assert_eq!(
+            auction_row(&store, &auction),
+            ("settled".to_string(), Some(alice.to_string()), 50, 2, 1)
+        );
+        assert_eq!(
+            bids(&store, &auction),
+            vec![(alice.to_string(), Some(50)), (bob.to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn uncovered_bids_count_as_revealed_without_leading() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (auction, alice, bob) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let uncovered = AuctionEvent::BidUncovered(BidUncovered {
+            auction,
+            bidder: bob,
+            amount: 500,
+            deposit: 100,
+        });
+        store
+            .ingest(&block(
+                1,
+                0,
+                "a",
+                &[
+                    created(auction),
+                    committed(auction, alice),
+                    committed(auction, bob),
+                    revealed(auction, alice, 50),
+                    uncovered,
+                ],
+            ))
+            .unwrap();
+        let (_, _, highest_bid, total_bids, total_revealed) = auction_row(&store, &auction);
+        assert_eq!((highest_bid, total_bids, total_revealed), (50, 2, 2));
+    }
+
+    #[test]
+    fn abandoned_forks_are_rolled_back() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (auction, alice, bob) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        store
+            .ingest(&block(
+                1,
+                0,
+                "a",
+                &[
+                    created(auction),
+                    status_changed(auction, AuctionStatus::Scheduled, AuctionStatus::Bidding),
+                ],
+            ))
+            .unwrap();
+        store
+            .ingest(&block(2, 1, "a", &[committed(auction, alice)]))
+            .unwrap();
+        store
+            .ingest(&block(3, 2, "a", &[revealed(auction, alice, 70)]))
+            .unwrap();
+
+        // Slot 4 builds on slot 1, so slots 2 and 3 were on another fork.
+        assert_eq!(
+            store
+                .ingest(&block(4, 1, "a", &[committed(auction, bob)]))
+                .unwrap(),
+            Ingest::Indexed {
+                events: 1,
+                rolled_back_from: Some(2),
+            }
+        );
+        assert_eq!(
+            auction_row(&store, &auction),
+            ("bidding".to_string(), None, 0, 1, 0)
+        );
+        assert_eq!(bids(&store, &auction), vec![(bob.to_string(), None)]);
+
+        // A block whose parent hash disagrees with slot 4 orphans slot 4 itself.
+        assert_eq!(
+            store.ingest(&block(5, 4, "b", &[])).unwrap(),
+            Ingest::Indexed {
+                events: 0,
+                rolled_back_from: Some(4),
+            }
+        );
+        assert!(bids(&store, &auction).is_empty());
+        assert_eq!(store.last_slot().unwrap(), Some(5));
+    }
+
+    #[test]
+    fn replays_the_fixture_ledger() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ledger.jsonl");
+        let mut store = Store::open_in_memory().unwrap();
+        for block in read_blocks(&path).unwrap() {
+            store.ingest(&block).unwrap();
+        }
+        let auction: String = store
+            .conn
+            .query_row("SELECT address FROM auctions", [], |row| row.get(0))
+            .unwrap();
+        let auction = auction.parse().unwrap();
+        let (status, winner, highest_bid, total_bids, total_revealed) =
+            auction_row(&store, &auction);
+        assert_eq!(status, "finalized");
+        assert_eq!((highest_bid, total_bids, total_revealed), (900, 2, 2));
+        assert_eq!(winner, Some(bids(&store, &auction)[1].0.clone()));
+    }
+}
@@ -0,0 +1,139 @@
+//! The keeper's view of the chain, behind a trait so the crank logic can run against an
+//! in-process cluster in tests.
+
+use std::collections::HashSet;
+
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, Discriminator};
+use magic_client::{
+    account::{decode_auction, decode_bid, Auction, Bid},
+    rpc::{Result, Router, RpcClient, RpcError},
+};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+/// Where an auction currently lives: delegated auctions are only writable on the ER.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Location {
+    L1,
+    Er,
+}
+
+#[derive(Clone)]
+pub struct Watched {
+    pub key: Pubkey,
+    pub auction: Auction,
+    pub location: Location,
+}
+
+/// A bid and where it lives: private-mode bids stay delegated to the ER until the
+/// auction is settled or `undelegate_bids` returns them.
+#[derive(Clone)]
+pub struct WatchedBid {
+    pub bid: Bid,
+    pub location: Location,
+}
+
+pub trait Cluster {
+    fn auctions(&self) -> Result<Vec<Watched>>;
+    fn bids(&self) -> Result<Vec<WatchedBid>>;
+    fn unix_timestamp(&self, location: Location) -> Result<i64>;
+    /// Sends `instructions` signed (and paid for) by `signer`.
+    fn send(
+        &self,
+        location: Location,
+        instructions: &[Instruction],
+        signer: &Keypair,
+    ) -> Result<String>;
+}
+
+pub struct RpcCluster {
+    pub router: Router,
+}
+
+impl RpcCluster {
+    fn rpc(&self, location: Location) -> Result<&RpcClient> {
+        match location {
+            Location::L1 => Ok(&self.router.l1),
+            Location::Er => self
+                .router
+                .er
+                .as_ref()
+                .ok_or_else(|| RpcError::Response("no ER RPC configured".to_string())),
+        }
+    }
+
+    /// The accounts with `discriminator` that `decode` accepts, from L1 and the ER. The
+    /// ER may also hold read-only clones of L1 accounts, which are skipped.
+    fn listed<T>(
+        &self,
+        discriminator: &[u8],
+        decode: impl Fn(&[u8]) -> anchor_lang::Result<T>,
+    ) -> Result<Vec<(Pubkey, T, Location)>> {
+        let mut listed = Vec::new();
+        let mut on_l1 = HashSet::new();
+        for (location, rpc) in [
+            (Location::L1, Some(&self.router.l1)),
+            (Location::Er, self.router.er.as_ref()),
+        ] {
+            let Some(rpc) = rpc else { continue };
+            for (key, account) in rpc.get_program_accounts(discriminator)? {
+                if location == Location::Er && on_l1.contains(&key) {
+                    continue;
+                }
+                let Ok(value) = decode(&account.data) else {
+                    continue;
+                };
+                if location == Location::L1 {
+                    on_l1.insert(key);
+                }
+                listed.push((key, value, location));
+            }
+        }
+        Ok(listed)
+    }
+}
+
+impl Cluster for RpcCluster {
+    /// L1 returns the undelegated auctions; delegated ones are owned by the delegation
+    /// program there, so they are listed from the ER.
+    fn auctions(&self) -> Result<Vec<Watched>> {
+        Ok(self
+            .listed(Auction::DISCRIMINATOR, decode_auction)?
+            .into_iter()
+            .map(|(key, auction, location)| Watched {
+                key,
+                auction,
+                location,
+            })
+            .collect())
+    }
+
+    fn bids(&self) -> Result<Vec<WatchedBid>> {
+        Ok(self
+            .listed(Bid::DISCRIMINATOR, decode_bid)?
+            .into_iter()
+            .map(|(_, bid, location)| WatchedBid { bid, location })
+            .collect())
+    }
+
+    fn unix_timestamp(&self, location: Location) -> Result<i64> {
+        self.rpc(location)?.get_unix_timestamp()
+    }
+
+    fn send(
+        &self,
+        location: Location,
+        instructions: &[Instruction],
+        signer: &Keypair,
+    ) -> Result<String> {
+        let rpc = self.rpc(location)?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signer.pubkey()),
+            &[signer],
+            rpc.get_latest_blockhash()?,
+        );
+        rpc.send_and_confirm(&transaction)
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::keeper::{Authority, RetryPolicy};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub rpc_url: String,
+    pub er_rpc_url: Option<String>,
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_secs: u64,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    pub authorities: Vec<AuthorityConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorityConfig {
+    pub keypair: PathBuf,
+    #[serde(default = "default_true")]
+    pub settle: bool,
+    #[serde(default)]
+    pub with_permission: bool,
+}
+
+fn default_poll_interval() -> u64 {
+    10
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Config {
+    pub fn load(path: &PathBuf) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("invalid config {}", path.display()))
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.retry.max_attempts.max(1),
+            initial_backoff: Duration::from_millis(self.retry.initial_backoff_ms),
+            max_backoff: Duration::from_millis(self.retry.max_backoff_ms),
+        }
+    }
+
+    pub fn authorities(&self) -> Result<Vec<Authority>> {
+        self.authorities
+            .iter()
+            .map(|entry| {
+                let keypair = solana_keypair::read_keypair_file(&entry.keypair).map_err(|err| {
+                    anyhow!("cannot read keypair {}: {err}", entry.keypair.display())
+                })?;
+                Ok(Authority {
+                    keypair,
+                    settle: entry.settle,
+                    with_permission: entry.with_permission,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_parses() {
+        let config: Config = toml::from_str(include_str!("../keeper.example.toml")).unwrap();
+        assert_eq!(config.er_rpc_url.as_deref(), Some("http://127.0.0.1:7799"));
+        assert_eq!(config.retry_policy().max_attempts, 5);
+        assert_eq!(config.authorities.len(), 1);
+        assert!(config.authorities[0].settle);
+        assert!(!config.authorities[0].with_permission);
+
+        let minimal: Config =
+            toml::from_str("rpc_url = \"http://localhost:8899\"\nauthorities = []").unwrap();
+        assert_eq!(minimal.poll_interval(), Duration::from_secs(10));
+        assert_eq!(
+            minimal.retry_policy().initial_backoff,
+            Duration::from_millis(500)
+        );
+    }
+}
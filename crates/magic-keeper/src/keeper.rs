@@ -0,0 +1,581 @@
+use std::{collections::HashMap, thread, time::Duration};
+
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction};
+use magic::handlers::ensure_finalizable;
+use magic_client::{account::Auction, instruction, rpc::Result, AuctionStatus, BidMode};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::cluster::{Cluster, Location, Watched, WatchedBid};
+
+/// Bids passed to one instruction; the rest follow on later ticks.
+const BIDS_PER_TRANSACTION: usize = 8;
+
+/// An authority whose auctions the keeper cranks.
+pub struct Authority {
+    pub keypair: Keypair,
+    /// Settle finalized auctions: `finalize_and_settle` on the ER, `settle_auction` on
+    /// L1, then `undelegate_bids` for the bids still on the ER.
+    pub settle: bool,
+    pub with_permission: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Finalize,
+    Settle,
+    /// Both in one ER transaction, for a delegated auction that just closed.
+    FinalizeAndSettle,
+    /// Count the plaintext bids of a private-mode auction on the ER.
+    ResolvePrivate,
+    /// Return the bids a closed auction left delegated to the ER.
+    UndelegateBids,
+}
+
+/// Exponential backoff for transient RPC errors.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn run<T>(&self, mut op: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Err(err) if err.is_transient() && attempt < self.max_attempts => {
+                    eprintln!("attempt {attempt} failed, retrying in {backoff:?}: {err}");
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+pub struct Outcome {
+    pub auction: Pubkey,
+    pub action: Action,
+    pub result: Result<String>,
+}
+
+/// What the keeper should do with `auction` at `now`, given the `bids` placed in this
+/// instance of it, mirroring the program's own checks so it never sends a transaction
+/// that is bound to fail.
+pub fn plan(
+    auction: &Auction,
+    location: Location,
+    bids: &[WatchedBid],
+    now: i64,
+    authority: &Authority,
+) -> Option<Action> {
+    let settle = authority.settle && location == Location::Er;
+    match auction.status {
+        AuctionStatus::Finalized if authority.settle => Some(Action::Settle),
+        ref status if status.is_closed() => (authority.settle
+            && location == Location::L1
+            && bids.iter().any(|bid| bid.location == Location::Er))
+        .then_some(Action::UndelegateBids),
+        _ if auction.bid_mode == BidMode::Private
+            && location == Location::Er
+            && now >= auction.end_ts
+            && bids.iter().any(unresolved) =>
+        {
+            Some(Action::ResolvePrivate)
+        }
+        _ if ensure_finalizable(auction, now).is_ok() => Some(if settle {
+            Action::FinalizeAndSettle
+        } else {
+            Action::Finalize
+        }),
+        _ => None,
+    }
+}
+
+/// A committed private bid `resolve_private_auction` has not counted yet.
+fn unresolved(bid: &WatchedBid) -> bool {
+    bid.location == Location::Er && bid.bid.committed && !bid.bid.revealed
+}
+
+pub struct Keeper<C> {
+    pub cluster: C,
+    pub authorities: Vec<Authority>,
+    pub retry: RetryPolicy,
+}
+
+impl<C: Cluster> Keeper<C> {
+    /// One pass over every watched auction. Failed actions are reported, not fatal; they
+    /// are planned again on the next tick.
+    pub fn tick(&self) -> Result<Vec<Outcome>> {
+        let authorities: HashMap<Pubkey, &Authority> = self
+            .authorities
+            .iter()
+            .map(|authority| (authority.keypair.pubkey(), authority))
+            .collect();
+        let mut bids: HashMap<Pubkey, Vec<WatchedBid>> = HashMap::new();
+        for bid in self.retry.run(|| self.cluster.bids())? {
+            bids.entry(bid.bid.auction).or_default().push(bid);
+        }
+        let mut clocks = HashMap::new();
+        let mut outcomes = Vec::new();
+        for Watched {
+            key,
+            auction,
+            location,
+        } in self.retry.run(|| self.cluster.auctions())?
+        {
+            let Some(authority) = authorities.get(&auction.authority) else {
+                continue;
+            };
+            let now = match clocks.get(&location) {
+                Some(now) => *now,
+                None => {
+                    let now = self.retry.run(|| self.cluster.unix_timestamp(location))?;
+                    clocks.insert(location, now);
+                    now
+                }
+            };
+            // Bids left over from an earlier instance of a reused auction are not its own.
+            let bids: Vec<WatchedBid> = bids
+                .get(&key)
+                .into_iter()
+                .flatten()
+                .filter(|bid| bid.bid.auction_slot == auction.creation_slot)
+                .cloned()
+                .collect();
+            let Some(action) = plan(&auction, location, &bids, now, authority) else {
+                continue;
+            };
+            let (location, instructions) =
+                instructions(&key, &auction, location, &bids, action, authority);
+            let result = self.retry.run(|| {
+                self.cluster
+                    .send(location, &instructions, &authority.keypair)
+            });
+            outcomes.push(Outcome {
+                auction: key,
+                action,
+                result,
+            });
+        }
+        Ok(outcomes)
+    }
+}
+
+/// The instructions for `action` and the chain to send them to, which is the ER for
+/// the bids of an auction already back on L1.
+fn instructions(
+    key: &Pubkey,
+    auction: &Auction,
+    location: Location,
+    bids: &[WatchedBid],
+    action: Action,
+    authority: &Authority,
+) -> (Location, Vec<Instruction>) {
+    let signer = authority.keypair.pubkey();
+    let slot = auction.creation_slot;
+    let bidders = |keep: fn(&WatchedBid) -> bool| -> Vec<Pubkey> {
+        bids.iter()
+            .filter(|bid| keep(bid))
+            .take(BIDS_PER_TRANSACTION)
+            .map(|bid| bid.bid.bidder)
+            .collect()
+    };
+    let finalize = || instruction::finalize_auction(key, &signer);
+    let settle = || match location {
+        Location::Er => instruction::finalize_and_settle(
+            key,
+            &signer,
+            &signer,
+            authority.with_permission,
+            slot,
+            &bidders(|bid| bid.location == Location::Er),
+        ),
+        Location::L1 => instruction::settle_auction(
+            key,
+            &auction.authority,
+            auction.highest_bidder,
+            &signer,
+            slot,
+            &bidders(|bid| bid.location == Location::L1),
+        ),
+    };
+    match action {
+        Action::Finalize => (location, vec![finalize()]),
+        Action::Settle => (location, vec![settle()]),
+        Action::FinalizeAndSettle => (location, vec![finalize(), settle()]),
+        Action::ResolvePrivate => (
+            Location::Er,
+            vec![instruction::resolve_private_auction(
+                key,
+                slot,
+                &bidders(unresolved),
+            )],
+        ),
+        Action::UndelegateBids => (
+            Location::Er,
+            vec![instruction::undelegate_bids(
+                key,
+                &signer,
+                slot,
+                &bidders(|bid| bid.location == Location::Er),
+            )],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use anchor_lang::Discriminator;
+    use magic_client::{account::Bid, rpc::RpcError, CommitmentHash, CommitmentScheme};
+
+    use super::*;
+
+    const REVEAL_END: i64 = 1_000;
+
+    /// In-process cluster: serves a fixed auction set and fails the first `failures` sends.
+    struct FakeCluster {
+        auctions: Vec<Watched>,
+        bids: Vec<WatchedBid>,
+        now: i64,
+        failures: Cell<u32>,
+        error: fn() -> RpcError,
+        sent: RefCell<Vec<(Location, Vec<Instruction>)>>,
+    }
+
+    impl Cluster for FakeCluster {
+        fn auctions(&self) -> Result<Vec<Watched>> {
+            Ok(self.auctions.clone())
+        }
+
+        fn bids(&self) -> Result<Vec<WatchedBid>> {
+            Ok(self.bids.clone())
+        }
+
+        fn unix_timestamp(&self, _location: Location) -> Result<i64> {
+            Ok(self.now)
+        }
+
+        fn send(
+            &self,
+            location: Location,
+            instructions: &[Instruction],
+            _signer: &Keypair,
+        ) -> Result<String> {
+            if self.failures.get() > 0 {
+                self.failures.set(self.failures.get() - 1);
+                return Err((self.error)());
+            }
+            self.sent
+                .borrow_mut()
+                .push((location, instructions.to_vec()));
+            Ok("signature".to_string())
+        }
+    }
+
+    fn auction(authority: Pubkey, status: AuctionStatus) -> Auction {
+        Auction {
+            auction_id: 1,
+            authority,
+            start_ts: 0,
+            end_ts: REVEAL_END / 2,
+            reveal_end_ts: REVEAL_END,
+            reserve_price: 0,
+            highest_bid: 0,
+            highest_bidder: None,
+            total_bids: 0,
+            total_revealed: 0,
+            status,
+            bump: 255,
+            version: Auction::VERSION,
+            bid_mode: BidMode::Sealed,
+            commitment_scheme: CommitmentScheme::V2,
+            commitment_hash: CommitmentHash::Sha256,
+            range_proof: false,
+            encryption_key: None,
+            derived_nonce: false,
+            creation_slot: 0,
+            demand_commitment: [0; 32],
+            aggregate_demand: None,
+            auto_finalize_task: None,
+        }
+    }
+
+    fn bid(auction: Pubkey, auction_slot: u64, location: Location) -> WatchedBid {
+        WatchedBid {
+            bid: Bid {
+                auction,
+                bidder: Pubkey::new_unique(),
+                bid_hash: [0; 32],
+                committed: true,
+                revealed: false,
+                amount: 10,
+                nonce: [0; 32],
+                bump: 255,
+                version: Bid::VERSION,
+                uncovered: false,
+                ciphertext: None,
+                reveal_agent: None,
+                auction_slot,
+            },
+            location,
+        }
+    }
+
+    fn authority(settle: bool) -> Authority {
+        Authority {
+            keypair: Keypair::new(),
+            settle,
+            with_permission: false,
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        }
+    }
+
+    fn transient() -> RpcError {
+        RpcError::Io(std::io::ErrorKind::TimedOut.into())
+    }
+
+    #[test]
+    fn plans_follow_the_program_checks() {
+        let settler = authority(true);
+        let key = settler.keypair.pubkey();
+        let bidding = auction(key, AuctionStatus::Bidding);
+        let finalized = auction(key, AuctionStatus::Finalized);
+
+        assert_eq!(
+            plan(&bidding, Location::L1, &[], REVEAL_END - 1, &settler),
+            None
+        );
+        assert_eq!(
+            plan(&bidding, Location::L1, &[], REVEAL_END, &settler),
+            Some(Action::Finalize)
+        );
+        assert_eq!(
+            plan(&bidding, Location::Er, &[], REVEAL_END, &settler),
+            Some(Action::FinalizeAndSettle)
+        );
+        assert_eq!(
+            plan(&finalized, Location::Er, &[], REVEAL_END, &settler),
+            Some(Action::Settle)
+        );
+        assert_eq!(
+            plan(&finalized, Location::L1, &[], REVEAL_END, &settler),
+            Some(Action::Settle)
+        );
+        assert_eq!(
+            plan(&finalized, Location::Er, &[], REVEAL_END, &authority(false)),
+            None
+        );
+
+        let settled = auction(key, AuctionStatus::Settled);
+        assert_eq!(
+            plan(&settled, Location::Er, &[], REVEAL_END, &settler),
+            None
+        );
+        let cancelled = auction(key, AuctionStatus::Cancelled);
+        assert_eq!(
+            plan(&cancelled, Location::Er, &[], REVEAL_END, &settler),
+            None
+        );
+    }
+
+    #[test]
+    fn private_bids_are_resolved_and_settled_bids_undelegated() {
+        let ours = authority(true);
+        let (private, finalized, settled) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut private_auction = auction(ours.keypair.pubkey(), AuctionStatus::Bidding);
+        private_auction.bid_mode = BidMode::Private;
+        let bids = vec![
+            bid(private, 0, Location::Er),
+            // Placed in an earlier instance of the auction, so not resolved against it.
+            bid(private, 7, Location::Er),
+            bid(finalized, 0, Location::L1),
+            bid(settled, 0, Location::Er),
+        ];
+        let keeper = Keeper {
+            cluster: FakeCluster {
+                auctions: vec![
+                    Watched {
+                        key: private,
+                        auction: private_auction,
+                        location: Location::Er,
+                    },
+                    Watched {
+                        key: finalized,
+                        auction: auction(ours.keypair.pubkey(), AuctionStatus::Finalized),
+                        location: Location::L1,
+                    },
+                    Watched {
+                        key: settled,
+                        auction: auction(ours.keypair.pubkey(), AuctionStatus::Settled),
+                        location: Location::L1,
+                    },
+                ],
+                bids: bids.clone(),
+                now: REVEAL_END / 2,
+                failures: Cell::new(0),
+                error: transient,
+                sent: RefCell::default(),
+            },
+            authorities: vec![ours],
+            retry: policy(),
+        };
+
+        let outcomes = keeper.tick().unwrap();
+        let actions: Vec<Action> = outcomes.iter().map(|outcome| outcome.action).collect();
+        assert_eq!(
+            actions,
+            [
+                Action::ResolvePrivate,
+                Action::Settle,
+                Action::UndelegateBids
+            ]
+        );
+
+        let sent = keeper.cluster.sent.borrow();
+        let expected = [
+            (
+                Location::Er,
+                instruction::resolve_private_auction(&private, 0, &[bids[0].bid.bidder]),
+            ),
+            (
+                Location::L1,
+                instruction::settle_auction(
+                    &finalized,
+                    &keeper.authorities[0].keypair.pubkey(),
+                    None,
+                    &keeper.authorities[0].keypair.pubkey(),
+                    0,
+                    &[bids[2].bid.bidder],
+                ),
+            ),
+            (
+                Location::Er,
+                instruction::undelegate_bids(
+                    &settled,
+                    &keeper.authorities[0].keypair.pubkey(),
+                    0,
+                    &[bids[3].bid.bidder],
+                ),
+            ),
+        ];
+        for ((location, instructions), (expected_location, expected)) in sent.iter().zip(expected) {
+            assert_eq!(*location, expected_location);
+            assert_eq!(instructions, &[expected]);
+        }
+    }
+
+    #[test]
+    fn ticks_crank_listed_authorities_and_retry_transient_errors() {
+        let ours = authority(true);
+        let (delegated, undelegated, foreign) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let watched = |key, authority, location| Watched {
+            key,
+            auction: auction(authority, AuctionStatus::Bidding),
+            location,
+        };
+        let keeper = Keeper {
+            cluster: FakeCluster {
+                bids: Vec::new(),
+                auctions: vec![
+                    watched(delegated, ours.keypair.pubkey(), Location::Er),
+                    watched(undelegated, ours.keypair.pubkey(), Location::L1),
+                    watched(foreign, Pubkey::new_unique(), Location::L1),
+                ],
+                now: REVEAL_END,
+                failures: Cell::new(2),
+                error: transient,
+                sent: RefCell::default(),
+            },
+            authorities: vec![ours],
+            retry: policy(),
+        };
+
+        let outcomes = keeper.tick().unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(|outcome| outcome.result.is_ok()));
+
+        let sent = keeper.cluster.sent.borrow();
+        let discriminators = |instructions: &[Instruction]| -> Vec<Vec<u8>> {
+            instructions
+                .iter()
+                .map(|ix| ix.data[..8].to_vec())
+                .collect()
+        };
+        assert_eq!(sent[0].0, Location::Er);
+        assert_eq!(
+            discriminators(&sent[0].1),
+            [
+                magic::instruction::FinalizeAuction::DISCRIMINATOR.to_vec(),
+                magic::instruction::FinalizeAndSettle::DISCRIMINATOR.to_vec(),
+            ]
+        );
+        assert_eq!(sent[1].0, Location::L1);
+        assert_eq!(
+            discriminators(&sent[1].1),
+            [magic::instruction::FinalizeAuction::DISCRIMINATOR.to_vec()]
+        );
+        assert!(sent[1].1[0]
+            .accounts
+            .iter()
+            .all(|meta| meta.pubkey != foreign));
+    }
+
+    #[test]
+    fn permanent_errors_and_exhausted_retries_are_reported() {
+        let rejected = || RpcError::TransactionFailed {
+            signature: "signature".to_string(),
+            err: "AuctionAlreadyFinalized".to_string(),
+        };
+        // A rejection is not retried; a transient error is retried `max_attempts` times.
+        for (failures, error, remaining) in
+            [(1, rejected as fn() -> RpcError, 0), (5, transient, 2)]
+        {
+            let ours = authority(false);
+            let key = Pubkey::new_unique();
+            let keeper = Keeper {
+                cluster: FakeCluster {
+                    bids: Vec::new(),
+                    auctions: vec![Watched {
+                        key,
+                        auction: auction(ours.keypair.pubkey(), AuctionStatus::Bidding),
+                        location: Location::L1,
+                    }],
+                    now: REVEAL_END,
+                    failures: Cell::new(failures),
+                    error,
+                    sent: RefCell::default(),
+                },
+                authorities: vec![ours],
+                retry: policy(),
+            };
+
+            let outcomes = keeper.tick().unwrap();
+            assert_eq!(outcomes[0].auction, key);
+            assert!(outcomes[0].result.is_err());
+            assert_eq!(keeper.cluster.failures.get(), remaining);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! `magic-keeper`: resolves private bids, finalizes auctions once their windows close and
+//! settles them on L1 or from the ER, for the authorities listed in its TOML config.
+
+mod cluster;
+mod config;
+mod keeper;
+
+use std::{path::PathBuf, thread};
+
+use anyhow::Result;
+use clap::Parser;
+use magic_client::rpc::{Router, RpcClient};
+
+use crate::{cluster::RpcCluster, config::Config, keeper::Keeper};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[arg(long, default_value = "keeper.toml")]
+    config: PathBuf,
+    /// Run a single pass and exit.
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(&cli.config)?;
+    let keeper = Keeper {
+        cluster: RpcCluster {
+            router: Router::new(
+                RpcClient::new(config.rpc_url.clone()),
+                config.er_rpc_url.clone().map(RpcClient::new),
+            ),
+        },
+        authorities: config.authorities()?,
+        retry: config.retry_policy(),
+    };
+
+    loop {
+        match keeper.tick() {
+            Ok(outcomes) => {
+                for outcome in outcomes {
+                    match outcome.result {
+                        Ok(signature) => {
+                            println!("{:?} {} ({signature})", outcome.action, outcome.auction)
+                        }
+                        Err(err) => {
+                            eprintln!("{:?} {} failed: {err}", outcome.action, outcome.auction)
+                        }
+                    }
+                }
+            }
+            Err(err) if err.is_transient() && !cli.once => eprintln!("tick failed: {err}"),
+            Err(err) => return Err(err.into()),
+        }
+        if cli.once {
+            return Ok(());
+        }
+        thread::sleep(config.poll_interval());
+    }
+}
@@ -1,2 +1,34 @@
 pub const AUCTION_SEED: &[u8] = b"auction";
 pub const BID_SEED: &[u8] = b"bid";
+pub const DEPOSIT_SEED: &[u8] = b"deposit";
+
+/// Domain tag prefixed to versioned bid commitments.
+pub const BID_COMMITMENT_DOMAIN: &[u8] = b"magic-auctions/bid-commitment";
+
+/// Number of times the auto-finalize crank task fires; later runs cover clock drift.
+pub const AUTO_FINALIZE_ITERATIONS: u64 = 3;
+/// Lower bound for the crank task interval when the auction is already closed.
+pub const AUTO_FINALIZE_MIN_INTERVAL_MS: u64 = 1_000;
+
+/// Compute budget requested for the L1 settlement action scheduled on commit.
+pub const SETTLE_ACTION_COMPUTE_UNITS: u32 = 60_000;
+
+/// Seed of the per-auction Groth16 verifying key used for sealed-bid range proofs.
+pub const RANGE_PROOF_KEY_SEED: &[u8] = b"range_proof_key";
+/// Public inputs of the range proof circuit: `[bid_hash, reserve_price, deposit_amount]`.
+pub const RANGE_PROOF_PUBLIC_INPUTS: usize = 3;
+
+/// Domain tag of the keystream that masks encrypted bid amounts.
+pub const ENCRYPTED_BID_DOMAIN: &[u8] = b"magic-auctions/encrypted-bid";
+
+/// Domain tag of the off-chain message a bidder signs for relayed sealed bids.
+pub const SIGNED_BID_DOMAIN: &[u8] = b"magic-auctions/signed-bid";
+
+/// Domain tag of the message signed to derive a bid nonce, and of the nonce hash itself.
+pub const NONCE_DERIVATION_DOMAIN: &[u8] = b"magic-auctions/bid-nonce";
+
+/// Domain the Pedersen blinding generator `H` is hashed from.
+pub const PEDERSEN_H_DOMAIN: &[u8] = b"magic-auctions/pedersen-h";
+
+/// Domain tag of the Fiat-Shamir challenge of a Pedersen opening proof.
+pub const OPENING_PROOF_DOMAIN: &[u8] = b"magic-auctions/opening-proof";
@@ -40,4 +40,72 @@ pub enum AuctionError {
     BidAlreadyCommitted,
     #[msg("Bid account does not match expected auction/bidder.")]
     BidAccountMismatch,
+    #[msg("Instruction is not supported by the auction's bid mode.")]
+    BidModeMismatch,
+    #[msg("Bidding phase is still open.")]
+    BiddingStillOpen,
+    #[msg("Private bids have not all been resolved yet.")]
+    PrivateBidsUnresolved,
+    #[msg("No auto-finalize task is scheduled for this auction.")]
+    AutoFinalizeNotScheduled,
+    #[msg("Auction was already settled.")]
+    AuctionAlreadySettled,
+    #[msg("Auction must be settled first.")]
+    AuctionNotSettled,
+    #[msg("Deposit account does not match the bidder's deposit PDA.")]
+    DepositMismatch,
+    #[msg("The bid account already exists.")]
+    BidInitFailed,
+    #[msg("The permission account already exists.")]
+    PermissionCreateFailed,
+    #[msg("The permission is already delegated.")]
+    PermissionDelegateFailed,
+    #[msg("The account is already delegated or not owned by this program.")]
+    AccountDelegateFailed,
+    #[msg("Commitment hash could not be computed for the given inputs.")]
+    CommitmentHashFailed,
+    #[msg("Range proofs require the Poseidon commitment hash.")]
+    RangeProofNeedsPoseidon,
+    #[msg("Auction requires a range proof, its verifying key and the bidder's deposit.")]
+    RangeProofRequired,
+    #[msg("Range proof failed verification.")]
+    InvalidRangeProof,
+    #[msg("Encrypted auctions need a valid Ristretto encryption key.")]
+    InvalidEncryptionKey,
+    #[msg("Bid ciphertext is malformed.")]
+    InvalidCiphertext,
+    #[msg("Released key does not match the auction encryption key.")]
+    InvalidDecryptionKey,
+    #[msg("Signer is not the bid's reveal agent.")]
+    UnauthorizedRevealer,
+    #[msg("Number of reveals does not match the bid accounts passed.")]
+    BatchLengthMismatch,
+    #[msg("Bid is not authorized by a matching Ed25519 signature instruction.")]
+    InvalidBidSignature,
+    #[msg("Auction requires the bidder's nonce signature with each reveal.")]
+    NonceSignatureRequired,
+    #[msg("Auction options are incompatible with each other.")]
+    IncompatibleAuctionOptions,
+    #[msg("Bid commitment is not a valid Pedersen commitment.")]
+    InvalidPedersenCommitment,
+    #[msg("Opening does not match the aggregate demand commitment.")]
+    InvalidDemandOpening,
+    #[msg("Bid belongs to an earlier auction created at this address.")]
+    StaleBid,
+    #[msg("Auction cannot move to that status from its current one.")]
+    InvalidStatusTransition,
+    #[msg("Auction was cancelled.")]
+    AuctionCancelled,
+    #[msg("Auction already has bids.")]
+    AuctionHasBids,
+    #[msg("Pedersen bids require a proof of knowledge of their opening.")]
+    OpeningProofRequired,
+    #[msg("Opening proof failed verification.")]
+    InvalidOpeningProof,
+    #[msg("The range proof key must be set before bidding starts.")]
+    RangeProofKeyAfterStart,
+    #[msg("Aggregate demand has already been opened.")]
+    AggregateDemandAlreadyOpened,
+    #[msg("Account is not in a legacy layout.")]
+    AccountAlreadyMigrated,
 }
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::{AuctionStatus, BidMode, CommitmentHash, CommitmentScheme};
+
 #[event]
 pub struct AuctionCreated {
     pub auction: Pubkey,
@@ -8,6 +10,13 @@ pub struct AuctionCreated {
     pub end_ts: i64,
     pub reveal_end_ts: i64,
     pub reserve_price: u64,
+    pub bid_mode: BidMode,
+    pub commitment_scheme: CommitmentScheme,
+    pub commitment_hash: CommitmentHash,
+    pub range_proof: bool,
+    pub encryption_key: Option<[u8; 32]>,
+    pub derived_nonce: bool,
+    pub creation_slot: u64,
 }
 
 #[event]
@@ -23,6 +32,28 @@ pub struct BidRevealed {
     pub amount: u64,
 }
 
+/// A bid opened for more than its bidder escrowed. It counts as revealed but cannot win.
+#[event]
+pub struct BidUncovered {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub deposit: u64,
+}
+
+#[event]
+pub struct BidRevealRejected {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub error_code: u32,
+}
+
+#[event]
+pub struct AggregateDemandOpened {
+    pub auction: Pubkey,
+    pub total_demand: u64,
+}
+
 #[event]
 pub struct AuctionFinalized {
     pub auction: Pubkey,
@@ -37,3 +68,32 @@ pub struct AuctionFinalized {
 pub struct AuctionSettled {
     pub auction: Pubkey,
 }
+
+#[event]
+pub struct AuctionPaidOut {
+    pub auction: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub amount: u64,
+    pub shortfall: u64,
+}
+
+#[event]
+pub struct AutoFinalizeScheduled {
+    pub auction: Pubkey,
+    pub task_id: u64,
+    pub execute_after_ts: i64,
+}
+
+#[event]
+pub struct AutoFinalizeCancelled {
+    pub auction: Pubkey,
+    pub task_id: u64,
+}
+
+/// Emitted on every status change, including the clock-driven ones a handler applies.
+#[event]
+pub struct AuctionStatusChanged {
+    pub auction: Pubkey,
+    pub from: AuctionStatus,
+    pub to: AuctionStatus,
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AuctionError,
+    event::AggregateDemandOpened,
+    handlers::ensure_bidding_over,
+    helpers::{add_pedersen_commitments, pedersen_commitment, verify_opening_proof},
+    state::Auction,
+    BidMode, BidProof, CommitmentHash,
+};
+
+/// Opens the summed Pedersen commitment of a Pedersen auction, publishing total
+/// demand without revealing any single bid on-chain. Whoever holds the summed blinding
+/// can call it once bidding closes, and only once. Computing that sum takes every bidder's blinding,
+/// and a blinding opens its bid, so whoever pools them (an auditor, or an MPC run by
+/// the bidders) sees the individual bids.
+pub fn open_aggregate_demand(
+    ctx: Context<OpenAggregateDemand>,
+    total: u64,
+    blinding: [u8; 32],
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+
+    require!(
+        auction.commitment_hash == CommitmentHash::Pedersen,
+        AuctionError::BidModeMismatch
+    );
+    ensure_bidding_over(auction, now)?;
+    require!(
+        auction.aggregate_demand.is_none(),
+        AuctionError::AggregateDemandAlreadyOpened
+    );
+    require!(
+        pedersen_commitment(total, &blinding)? == auction.demand_commitment,
+        AuctionError::InvalidDemandOpening
+    );
+
+    auction.aggregate_demand = Some(total);
+    emit!(AggregateDemandOpened {
+        auction: auction.key(),
+        total_demand: total,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct OpenAggregateDemand<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+}
+
+/// Folds a new sealed Pedersen bid commitment into the auction's demand sum, once its
+/// opening proof checks out. The proof does not bound the amount: a bidder can still
+/// commit to a negative one, but such a bid can never be revealed.
+pub(crate) fn accumulate_demand(
+    auction: &mut Account<Auction>,
+    bidder: &Pubkey,
+    bid_hash: &[u8; 32],
+    proof: Option<&BidProof>,
+) -> Result<()> {
+    if auction.bid_mode != BidMode::Sealed || auction.commitment_hash != CommitmentHash::Pedersen {
+        return Ok(());
+    }
+    let Some(BidProof::Opening(proof)) = proof else {
+        return err!(AuctionError::OpeningProofRequired);
+    };
+    verify_opening_proof(bid_hash, proof, &auction.key(), bidder)?;
+    auction.demand_commitment = add_pedersen_commitments(&auction.demand_commitment, bid_hash)?;
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::Instruction,
+        program::{invoke, invoke_signed},
+    },
+    InstructionData,
+};
+use ephemeral_rollups_sdk::access_control::{
+    instructions::CommitAndUndelegatePermissionCpiBuilder, structs::Permission,
+};
+use ephemeral_rollups_sdk::anchor::{commit, MagicProgram};
+use ephemeral_rollups_sdk::consts::{MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID, PERMISSION_PROGRAM_ID};
+use ephemeral_rollups_sdk::ephem::{
+    CommitAndUndelegate, CommitType, MagicAction, MagicInstructionBuilder, UndelegateType,
+};
+use magicblock_magic_program_api::{args::ScheduleTaskArgs, instruction::MagicBlockInstruction};
+
+use crate::{
+    constant::{AUCTION_SEED, AUTO_FINALIZE_ITERATIONS, AUTO_FINALIZE_MIN_INTERVAL_MS},
+    error::AuctionError,
+    event::{AuctionSettled, AutoFinalizeCancelled, AutoFinalizeScheduled},
+    handlers::{apply_finalization, ensure_finalizable, ensure_open, settlement_handler},
+    state::Auction,
+    AuctionStatus, BidMode,
+};
+
+/// Schedules an ER crank task that runs `auto_finalize` once the auction closes.
+/// Calling it again cancels the recorded task first, so it doubles as a reschedule
+/// after the auction's timestamps change.
+pub fn schedule_auto_finalize(ctx: Context<ScheduleAutoFinalize>, task_id: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &ctx.accounts.auction;
+    ensure_open(&auction.status)?;
+
+    if let Some(previous) = auction.auto_finalize_task {
+        cancel_task(
+            previous,
+            &ctx.accounts.authority,
+            &ctx.accounts.task_context,
+            &ctx.accounts.magic_program,
+        )?;
+    }
+
+    let execute_after_ts = match auction.bid_mode {
+        BidMode::Sealed => auction.reveal_end_ts,
+        BidMode::Private | BidMode::Encrypted => auction.end_ts,
+    };
+    let execution_interval_millis = execute_after_ts
+        .saturating_sub(now)
+        .saturating_mul(1_000)
+        .max(AUTO_FINALIZE_MIN_INTERVAL_MS as i64) as u64;
+
+    let crank_ix = Instruction {
+        program_id: crate::ID,
+        accounts: crate::accounts::AutoFinalize {
+            auction: auction.key(),
+            permission: Permission::find_pda(&auction.key()).0,
+            permission_program: PERMISSION_PROGRAM_ID,
+            magic_program: MAGIC_PROGRAM_ID,
+            magic_context: MAGIC_CONTEXT_ID,
+        }
+        .to_account_metas(None),
+        data: crate::instruction::AutoFinalize {}.data(),
+    };
+
+    let schedule_ix = Instruction::new_with_bincode(
+        MAGIC_PROGRAM_ID,
+        &MagicBlockInstruction::ScheduleTask(ScheduleTaskArgs {
+            task_id,
+            execution_interval_millis,
+            iterations: AUTO_FINALIZE_ITERATIONS,
+            instructions: vec![crank_ix],
+        }),
+        vec![
+            AccountMeta::new(ctx.accounts.authority.key(), true),
+            AccountMeta::new(ctx.accounts.task_context.key(), false),
+            AccountMeta::new(auction.key(), false),
+            AccountMeta::new(MAGIC_CONTEXT_ID, false),
+        ],
+    );
+    invoke(
+        &schedule_ix,
+        &[
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.task_context.to_account_info(),
+            auction.to_account_info(),
+            ctx.accounts.magic_context.to_account_info(),
+        ],
+    )?;
+
+    let auction = &mut ctx.accounts.auction;
+    auction.auto_finalize_task = Some(task_id);
+
+    emit!(AutoFinalizeScheduled {
+        auction: auction.key(),
+        task_id,
+        execute_after_ts,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ScheduleAutoFinalize<'info> {
+    #[account(
+        mut,
+        seeds = [AUCTION_SEED, auction.authority.as_ref(), &auction.auction_id.to_le_bytes()],
+        bump = auction.bump,
+        has_one = authority
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: ER task context account, checked by the magic program.
+    #[account(mut)]
+    pub task_context: UncheckedAccount<'info>,
+    /// CHECK: Referenced by the scheduled crank instruction.
+    #[account(mut, address = MAGIC_CONTEXT_ID)]
+    pub magic_context: UncheckedAccount<'info>,
+    pub magic_program: Program<'info, MagicProgram>,
+}
+
+/// Cancels the recorded auto-finalize task, e.g. when the auction is extended or abandoned.
+pub fn cancel_auto_finalize(ctx: Context<CancelAutoFinalize>) -> Result<()> {
+    let task_id = ctx
+        .accounts
+        .auction
+        .auto_finalize_task
+        .ok_or(AuctionError::AutoFinalizeNotScheduled)?;
+
+    cancel_task(
+        task_id,
+        &ctx.accounts.authority,
+        &ctx.accounts.task_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    let auction = &mut ctx.accounts.auction;
+    auction.auto_finalize_task = None;
+
+    emit!(AutoFinalizeCancelled {
+        auction: auction.key(),
+        task_id,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelAutoFinalize<'info> {
+    #[account(
+        mut,
+        seeds = [AUCTION_SEED, auction.authority.as_ref(), &auction.auction_id.to_le_bytes()],
+        bump = auction.bump,
+        has_one = authority
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: ER task context account, checked by the magic program.
+    #[account(mut)]
+    pub task_context: UncheckedAccount<'info>,
+    pub magic_program: Program<'info, MagicProgram>,
+}
+
+fn cancel_task<'info>(
+    task_id: u64,
+    authority: &Signer<'info>,
+    task_context: &UncheckedAccount<'info>,
+    magic_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let cancel_ix = Instruction::new_with_bincode(
+        *magic_program.key,
+        &MagicBlockInstruction::CancelTask { task_id },
+        vec![
+            AccountMeta::new(authority.key(), true),
+            AccountMeta::new(task_context.key(), false),
+        ],
+    );
+    invoke(
+        &cancel_ix,
+        &[authority.to_account_info(), task_context.to_account_info()],
+    )?;
+    Ok(())
+}
+
+/// Crank target of the scheduled task. Finalizes the auction once it is ready and
+/// commits + undelegates it, and its permission if it has one, to L1; before that it is
+/// a no-op so early task iterations do not fail. The auction PDA pays for the commit and
+/// stands in for the permission authority, so no signer is needed.
+pub fn auto_finalize(ctx: Context<AutoFinalize>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+
+    if auction.status != AuctionStatus::Finalized {
+        if ensure_finalizable(auction, now).is_err() {
+            msg!("Auction is not ready to finalize yet");
+            return Ok(());
+        }
+        apply_finalization(auction, now)?;
+    }
+    auction.auto_finalize_task = None;
+
+    // Exit the account to flush serialized state before commit
+    auction.exit(&crate::ID)?;
+
+    // Same commit + settlement action as `finalize_and_settle`, with the auction PDA
+    // standing in as the signing payer.
+    let auction_info = auction.to_account_info();
+    let (accounts, mut commit_ix) = MagicInstructionBuilder {
+        payer: auction_info.clone(),
+        magic_context: ctx.accounts.magic_context.to_account_info(),
+        magic_program: ctx.accounts.magic_program.to_account_info(),
+        magic_action: MagicAction::CommitAndUndelegate(CommitAndUndelegate {
+            commit_type: CommitType::Standalone(vec![auction_info.clone()]),
+            undelegate_type: UndelegateType::WithHandler(vec![settlement_handler(
+                auction,
+                auction_info.clone(),
+                ctx.accounts.permission.owner == &PERMISSION_PROGRAM_ID,
+            )]),
+        }),
+    }
+    .build();
+    commit_ix.accounts[0].is_signer = true;
+    let auction_seeds: &[&[u8]] = &[
+        AUCTION_SEED,
+        auction.authority.as_ref(),
+        &auction.auction_id.to_le_bytes(),
+        &[auction.bump],
+    ];
+    invoke_signed(&commit_ix, &accounts, &[auction_seeds])?;
+
+    // Private auctions delegated their permission too; return it so it can be closed on L1.
+    let permission = &ctx.accounts.permission;
+    require_keys_eq!(
+        permission.key(),
+        Permission::find_pda(&auction.key()).0,
+        AuctionError::PermissionAccountMismatch
+    );
+    if permission.owner == &PERMISSION_PROGRAM_ID {
+        CommitAndUndelegatePermissionCpiBuilder::new(&ctx.accounts.permission_program)
+            .authority(&auction_info, false)
+            .permissioned_account(&auction_info, true)
+            .permission(permission)
+            .magic_program(&ctx.accounts.magic_program)
+            .magic_context(&ctx.accounts.magic_context)
+            .invoke_signed(&[auction_seeds])?;
+    }
+
+    emit!(AuctionSettled {
+        auction: auction.key(),
+    });
+    Ok(())
+}
+
+#[commit]
+#[derive(Accounts)]
+pub struct AutoFinalize<'info> {
+    #[account(
+        mut,
+        seeds = [AUCTION_SEED, auction.authority.as_ref(), &auction.auction_id.to_le_bytes()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    /// CHECK: Auction permission PDA, checked against the derived address. Only
+    /// undelegated when it exists.
+    #[account(mut)]
+    pub permission: UncheckedAccount<'info>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+}
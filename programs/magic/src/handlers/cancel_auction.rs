@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AuctionError,
+    handlers::{advance, transition},
+    state::Auction,
+    AuctionStatus,
+};
+
+/// Withdraws an auction nobody has bid in yet, before bidding closes. Deposits become
+/// withdrawable; a scheduled auto-finalize task is left to expire as a no-op.
+pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+    advance(auction, now)?;
+    require!(auction.total_bids == 0, AuctionError::AuctionHasBids);
+    transition(auction, AuctionStatus::Cancelled)
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+    #[account(mut, has_one = authority)]
+    pub auction: Account<'info, Auction>,
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use ephemeral_rollups_sdk::access_control::{
+    instructions::ClosePermissionCpiBuilder, structs::Permission,
+};
+use ephemeral_rollups_sdk::consts::PERMISSION_PROGRAM_ID;
+
+use crate::{
+    constant::{AUCTION_SEED, BID_SEED},
+    error::AuctionError,
+    state::{Auction, Bid},
+};
+
+/// Closes the auction permission after settlement and returns its rent to the authority.
+/// Must run where the permission lives, i.e. on L1 once `finalize_and_settle` has
+/// undelegated it.
+pub fn close_auction_permission(ctx: Context<CloseAuctionPermission>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    require!(
+        auction.status.is_closed(),
+        AuctionError::AuctionNotFinalized
+    );
+
+    let (expected_permission, _) = Permission::find_pda(&auction.key());
+    require_keys_eq!(
+        ctx.accounts.permission.key(),
+        expected_permission,
+        AuctionError::PermissionAccountMismatch
+    );
+
+    let auction_info = auction.to_account_info();
+    ClosePermissionCpiBuilder::new(&ctx.accounts.permission_program)
+        .payer(&ctx.accounts.authority)
+        .authority(&ctx.accounts.authority, true)
+        .permissioned_account(&auction_info, true)
+        .permission(&ctx.accounts.permission)
+        .invoke_signed(&[&[
+            AUCTION_SEED,
+            auction.authority.as_ref(),
+            &auction.auction_id.to_le_bytes(),
+            &[auction.bump],
+        ]])?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseAuctionPermission<'info> {
+    #[account(
+        seeds = [AUCTION_SEED, auction.authority.as_ref(), &auction.auction_id.to_le_bytes()],
+        bump = auction.bump,
+        has_one = authority
+    )]
+    pub auction: Account<'info, Auction>,
+    /// CHECK: Permission PDA derived from auction, checked against the derived address.
+    #[account(mut)]
+    pub permission: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+}
+
+/// Closes a bid permission after the auction is finalized and returns its rent to the bidder.
+pub fn close_bid_permission(ctx: Context<CloseBidPermission>) -> Result<()> {
+    require!(
+        ctx.accounts.auction.status.is_closed(),
+        AuctionError::AuctionNotFinalized
+    );
+
+    let bid = &ctx.accounts.bid;
+    let (expected_permission, _) = Permission::find_pda(&bid.key());
+    require_keys_eq!(
+        ctx.accounts.permission.key(),
+        expected_permission,
+        AuctionError::PermissionAccountMismatch
+    );
+
+    let bid_info = bid.to_account_info();
+    ClosePermissionCpiBuilder::new(&ctx.accounts.permission_program)
+        .payer(&ctx.accounts.bidder)
+        .authority(&ctx.accounts.bidder, true)
+        .permissioned_account(&bid_info, true)
+        .permission(&ctx.accounts.permission)
+        .invoke_signed(&[&[
+            BID_SEED,
+            bid.auction.as_ref(),
+            &bid.auction_slot.to_le_bytes(),
+            bid.bidder.as_ref(),
+            &[bid.bump],
+        ]])?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseBidPermission<'info> {
+    pub auction: Account<'info, Auction>,
+    #[account(
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = bidder,
+        has_one = auction
+    )]
+    pub bid: Account<'info, Bid>,
+    /// CHECK: Permission PDA derived from bid, checked against the derived address.
+    #[account(mut)]
+    pub permission: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+}
+
+/// Closes `permission` if it is back on L1, with `permissioned_account` signing as its
+/// payer, and forwards the rent it receives to `recipient`. A permission still delegated
+/// is skipped.
+pub(crate) fn close_returned_permission<'info>(
+    permissioned_account: &AccountInfo<'info>,
+    permission: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    permission_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    require_keys_eq!(
+        permission.key(),
+        Permission::find_pda(permissioned_account.key).0,
+        AuctionError::PermissionAccountMismatch
+    );
+    if permission.owner != &PERMISSION_PROGRAM_ID {
+        return Ok(());
+    }
+
+    let before = permissioned_account.lamports();
+    ClosePermissionCpiBuilder::new(permission_program)
+        .payer(permissioned_account)
+        .authority(permissioned_account, false)
+        .permissioned_account(permissioned_account, true)
+        .permission(permission)
+        .invoke_signed(&[signer_seeds])?;
+    let rent = permissioned_account.lamports().saturating_sub(before);
+    permissioned_account.sub_lamports(rent)?;
+    recipient.add_lamports(rent)?;
+    Ok(())
+}
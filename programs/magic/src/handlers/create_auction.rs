@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
+use solana_curve25519::ristretto::{validate_ristretto, PodRistrettoPoint};
 
 use crate::{
-    constant::AUCTION_SEED, error::AuctionError, event::AuctionCreated, state::Auction,
-    AuctionStatus,
+    constant::AUCTION_SEED, error::AuctionError, event::AuctionCreated, handlers::advance,
+    state::Auction, AuctionOptions, AuctionStatus, BidMode, CommitmentHash,
 };
 
 pub fn create_auction(
@@ -12,12 +13,47 @@ pub fn create_auction(
     end_ts: i64,
     reveal_end_ts: i64,
     reserve_price: u64,
+    options: AuctionOptions,
 ) -> Result<()> {
-    let now = Clock::get()?.unix_timestamp;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let creation_slot = clock.slot;
 
     require!(start_ts >= now, AuctionError::StartInPast);
     require!(end_ts > start_ts, AuctionError::InvalidTimeRange);
     require!(reveal_end_ts > end_ts, AuctionError::InvalidTimeRange);
+    // The circuit recomputes the commitment, which is only practical with Poseidon.
+    if options.range_proof {
+        require!(
+            options.bid_mode == BidMode::Sealed,
+            AuctionError::BidModeMismatch
+        );
+        require!(
+            options.commitment_hash == CommitmentHash::Poseidon,
+            AuctionError::RangeProofNeedsPoseidon
+        );
+    }
+    // Only sealed bids are summed into the demand commitment.
+    if options.commitment_hash == CommitmentHash::Pedersen {
+        require!(
+            options.bid_mode == BidMode::Sealed,
+            AuctionError::BidModeMismatch
+        );
+    }
+    // Derived nonces are SHA-256 outputs, which are rarely canonical scalars.
+    require!(
+        !(options.derived_nonce && options.commitment_hash == CommitmentHash::Pedersen),
+        AuctionError::IncompatibleAuctionOptions
+    );
+    match (&options.bid_mode, &options.encryption_key) {
+        (BidMode::Encrypted, Some(key)) => require!(
+            validate_ristretto(&PodRistrettoPoint(*key)),
+            AuctionError::InvalidEncryptionKey
+        ),
+        (BidMode::Encrypted, None) => return err!(AuctionError::InvalidEncryptionKey),
+        (_, Some(_)) => return err!(AuctionError::BidModeMismatch),
+        (_, None) => {}
+    }
 
     let auction = &mut ctx.accounts.auction;
     auction.auction_id = auction_id;
@@ -30,8 +66,19 @@ pub fn create_auction(
     auction.highest_bidder = None;
     auction.total_bids = 0;
     auction.total_revealed = 0;
-    auction.status = AuctionStatus::Bidding;
+    auction.status = AuctionStatus::Scheduled;
     auction.bump = ctx.bumps.auction;
+    auction.version = Auction::VERSION;
+    auction.bid_mode = options.bid_mode.clone();
+    auction.commitment_scheme = options.commitment_scheme.clone();
+    auction.commitment_hash = options.commitment_hash.clone();
+    auction.range_proof = options.range_proof;
+    auction.encryption_key = options.encryption_key;
+    auction.derived_nonce = options.derived_nonce;
+    auction.creation_slot = creation_slot;
+    auction.demand_commitment = [0_u8; 32];
+    auction.aggregate_demand = None;
+    auction.auto_finalize_task = None;
 
     emit!(AuctionCreated {
         auction: auction.key(),
@@ -40,8 +87,16 @@ pub fn create_auction(
         end_ts,
         reveal_end_ts,
         reserve_price,
+        bid_mode: options.bid_mode,
+        commitment_scheme: options.commitment_scheme,
+        commitment_hash: options.commitment_hash,
+        range_proof: options.range_proof,
+        encryption_key: options.encryption_key,
+        derived_nonce: options.derived_nonce,
+        creation_slot,
     });
-    Ok(())
+    // An auction starting right away opens for bids in the same instruction.
+    advance(auction, now)
 }
 
 #[derive(Accounts)]
@@ -14,29 +14,21 @@ use crate::{
 /// The program can sign because it owns the auction PDA.
 pub fn create_auction_permission(ctx: Context<CreateAuctionPermission>) -> Result<()> {
     let auction = &ctx.accounts.auction;
-    let authority = auction.authority;
 
-    let members = vec![Member {
-        pubkey: authority,
-        flags: AUTHORITY_FLAG,
-    }];
-
-    CreatePermissionCpiBuilder::new(&ctx.accounts.permission_program)
-        .permission(&ctx.accounts.permission)
-        .permissioned_account(&ctx.accounts.auction.to_account_info())
-        .payer(&ctx.accounts.payer)
-        .system_program(&ctx.accounts.system_program)
-        .args(MembersArgs {
-            members: Some(members),
-        })
-        .invoke_signed(&[&[
+    invoke_create_permission(
+        &ctx.accounts.auction.to_account_info(),
+        auction.authority,
+        &ctx.accounts.permission,
+        &ctx.accounts.payer,
+        &ctx.accounts.permission_program,
+        &ctx.accounts.system_program,
+        &[
             AUCTION_SEED,
             auction.authority.as_ref(),
             &auction.auction_id.to_le_bytes(),
             &[auction.bump],
-        ]])?;
-
-    Ok(())
+        ],
+    )
 }
 
 #[derive(Accounts)]
@@ -62,33 +54,27 @@ pub struct CreateAuctionPermission<'info> {
 pub fn create_bid_permission(ctx: Context<CreateBidPermission>) -> Result<()> {
     let bid = &ctx.accounts.bid;
 
-    let members = vec![Member {
-        pubkey: bid.bidder,
-        flags: AUTHORITY_FLAG,
-    }];
-
-    CreatePermissionCpiBuilder::new(&ctx.accounts.permission_program)
-        .permission(&ctx.accounts.permission)
-        .permissioned_account(&ctx.accounts.bid.to_account_info())
-        .payer(&ctx.accounts.payer)
-        .system_program(&ctx.accounts.system_program)
-        .args(MembersArgs {
-            members: Some(members),
-        })
-        .invoke_signed(&[&[
+    invoke_create_permission(
+        &ctx.accounts.bid.to_account_info(),
+        bid.bidder,
+        &ctx.accounts.permission,
+        &ctx.accounts.payer,
+        &ctx.accounts.permission_program,
+        &ctx.accounts.system_program,
+        &[
             BID_SEED,
             bid.auction.as_ref(),
+            &bid.auction_slot.to_le_bytes(),
             bid.bidder.as_ref(),
             &[bid.bump],
-        ]])?;
-
-    Ok(())
+        ],
+    )
 }
 
 #[derive(Accounts)]
 pub struct CreateBidPermission<'info> {
     #[account(
-        seeds = [BID_SEED, bid.auction.as_ref(), bid.bidder.as_ref()],
+        seeds = [BID_SEED, bid.auction.as_ref(), &bid.auction_slot.to_le_bytes(), bid.bidder.as_ref()],
         bump = bid.bump,
     )]
     pub bid: Account<'info, Bid>,
@@ -102,3 +88,31 @@ pub struct CreateBidPermission<'info> {
     pub permission_program: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
+
+/// Creates a permission for one of our PDAs with `owner` as its only (authority) member.
+pub(crate) fn invoke_create_permission<'info>(
+    permissioned_account: &AccountInfo<'info>,
+    owner: Pubkey,
+    permission: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    permission_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let members = vec![Member {
+        pubkey: owner,
+        flags: AUTHORITY_FLAG,
+    }];
+
+    CreatePermissionCpiBuilder::new(permission_program)
+        .permission(permission)
+        .permissioned_account(permissioned_account)
+        .payer(payer)
+        .system_program(system_program)
+        .args(MembersArgs {
+            members: Some(members),
+        })
+        .invoke_signed(&[signer_seeds])?;
+
+    Ok(())
+}
@@ -4,11 +4,21 @@ use ephemeral_rollups_sdk::cpi::DelegateConfig;
 
 use crate::constant::BID_SEED;
 
-pub fn delegate_bid(ctx: Context<DelegateBid>, auction: Pubkey, bidder: Pubkey) -> Result<()> {
+pub fn delegate_bid(
+    ctx: Context<DelegateBid>,
+    auction: Pubkey,
+    auction_slot: u64,
+    bidder: Pubkey,
+) -> Result<()> {
     let validator = ctx.accounts.validator.as_ref().map(|v| v.key());
     ctx.accounts.delegate_bid(
         &ctx.accounts.payer,
-        &[BID_SEED, auction.as_ref(), bidder.as_ref()],
+        &[
+            BID_SEED,
+            auction.as_ref(),
+            &auction_slot.to_le_bytes(),
+            bidder.as_ref(),
+        ],
         DelegateConfig {
             validator,
             commit_frequency_ms: 0,
@@ -19,7 +29,7 @@ pub fn delegate_bid(ctx: Context<DelegateBid>, auction: Pubkey, bidder: Pubkey)
 
 #[delegate]
 #[derive(Accounts)]
-#[instruction(auction: Pubkey, bidder: Pubkey)]
+#[instruction(auction: Pubkey, auction_slot: u64, bidder: Pubkey)]
 pub struct DelegateBid<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -29,7 +39,7 @@ pub struct DelegateBid<'info> {
     #[account(
         mut,
         del,
-        seeds = [BID_SEED, auction.as_ref(), bidder.as_ref()],
+        seeds = [BID_SEED, auction.as_ref(), &auction_slot.to_le_bytes(), bidder.as_ref()],
         bump,
     )]
     pub bid: AccountInfo<'info>,
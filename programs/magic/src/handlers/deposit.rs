@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+
+use crate::{
+    constant::DEPOSIT_SEED,
+    error::AuctionError,
+    handlers::status_at,
+    state::{Auction, Deposit},
+    AuctionStatus,
+};
+
+/// Locks `amount` lamports on L1 as the bidder's settlement escrow.
+/// Deposits are public, so bidders should over-collateralize to keep their bid hidden.
+pub fn deposit_escrow(ctx: Context<DepositEscrow>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    match status_at(&ctx.accounts.auction, now) {
+        AuctionStatus::Scheduled | AuctionStatus::Bidding => {}
+        AuctionStatus::Cancelled => return err!(AuctionError::AuctionCancelled),
+        _ => return err!(AuctionError::BiddingClosed),
+    }
+
+    transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.bidder.to_account_info(),
+                to: ctx.accounts.deposit.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let deposit = &mut ctx.accounts.deposit;
+    deposit.auction = ctx.accounts.auction.key();
+    deposit.bidder = ctx.accounts.bidder.key();
+    deposit.amount = amount;
+    deposit.bump = ctx.bumps.deposit;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DepositEscrow<'info> {
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + Deposit::INIT_SPACE,
+        seeds = [DEPOSIT_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Returns the deposit (and its rent) to the bidder. Losers can withdraw once the
+/// auction is finalized; the winner only after settlement has taken the payment.
+/// Everyone can withdraw from a cancelled auction.
+pub fn withdraw_deposit(ctx: Context<WithdrawDeposit>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    match auction.status {
+        AuctionStatus::Settled | AuctionStatus::Cancelled => {}
+        AuctionStatus::Finalized => require!(
+            auction.highest_bidder != Some(ctx.accounts.bidder.key()),
+            AuctionError::AuctionNotSettled
+        ),
+        _ => return err!(AuctionError::AuctionNotFinalized),
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawDeposit<'info> {
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        close = bidder,
+        seeds = [DEPOSIT_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        bump = deposit.bump,
+        has_one = bidder,
+        has_one = auction
+    )]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+}
+
+/// The deposit stored in `info`, or `None` if the bidder never deposited (the PDA is
+/// then an empty system account).
+pub(crate) fn read_deposit(info: &AccountInfo) -> Result<Option<Deposit>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    Deposit::try_deserialize(&mut &info.try_borrow_data()?[..]).map(Some)
+}
+
+/// `read_deposit` for an account passed without constraints, after checking that it is
+/// `bidder`'s deposit PDA for `auction`.
+pub(crate) fn find_deposit(
+    info: &AccountInfo,
+    auction: &Pubkey,
+    bidder: &Pubkey,
+) -> Result<Option<Deposit>> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[DEPOSIT_SEED, auction.as_ref(), bidder.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(info.key(), expected, AuctionError::DepositMismatch);
+    read_deposit(info)
+}
@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+use solana_curve25519::ristretto::{validate_ristretto, PodRistrettoPoint};
+
+use crate::{
+    constant::BID_SEED,
+    error::AuctionError,
+    event::BidCommitted,
+    handlers::{
+        count_revealed_bid, ensure_bidding, ensure_bidding_over, ensure_open, find_deposit,
+    },
+    helpers::{decrypt_bid_amount, encryption_public_key},
+    state::{Auction, Bid},
+    BidCiphertext, BidMode,
+};
+
+/// Stores a bid encrypted to the auction key on an initialized `Bid`. Works on L1 or,
+/// once the bid is delegated, inside the ER; the ciphertext is confidential either way.
+pub fn submit_encrypted_bid(
+    ctx: Context<SubmitEncryptedBid>,
+    ciphertext: BidCiphertext,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+
+    ensure_bidding(auction, now)?;
+    require!(
+        auction.bid_mode == BidMode::Encrypted,
+        AuctionError::BidModeMismatch
+    );
+    require!(
+        validate_ristretto(&PodRistrettoPoint(ciphertext.ephemeral_key)),
+        AuctionError::InvalidCiphertext
+    );
+
+    let bid = &mut ctx.accounts.bid;
+    require!(!bid.committed, AuctionError::BidAlreadyCommitted);
+    bid.committed = true;
+    bid.ciphertext = Some(ciphertext);
+    bid.auction_slot = auction.creation_slot;
+
+    auction.total_bids = auction
+        .total_bids
+        .checked_add(1)
+        .ok_or(AuctionError::MathOverflow)?;
+
+    emit!(BidCommitted {
+        auction: auction.key(),
+        bidder: bid.bidder,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SubmitEncryptedBid<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = bidder,
+        has_one = auction
+    )]
+    pub bid: Account<'info, Bid>,
+    pub bidder: Signer<'info>,
+}
+
+/// Decrypts the bids passed in `remaining_accounts`, each followed by its bidder's
+/// deposit PDA, with the auction secret released after `end_ts` (by the authority or
+/// the key committee that holds it). Permissionless and batchable like
+/// `resolve_private_auction`, so no bidder has to come back to reveal. Closes at
+/// `reveal_end_ts`, after which the auction finalizes on the bids decrypted so far.
+pub fn decrypt_and_reveal<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DecryptAndReveal<'info>>,
+    secret_key: [u8; 32],
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+
+    require!(
+        auction.bid_mode == BidMode::Encrypted,
+        AuctionError::BidModeMismatch
+    );
+    ensure_bidding_over(auction, now)?;
+    ensure_open(&auction.status)?;
+    require!(now < auction.reveal_end_ts, AuctionError::RevealClosed);
+    require!(
+        auction.encryption_key == Some(encryption_public_key(&secret_key)?),
+        AuctionError::InvalidDecryptionKey
+    );
+
+    let auction_key = auction.key();
+    require!(
+        ctx.remaining_accounts.len() % 2 == 0,
+        AuctionError::BatchLengthMismatch
+    );
+    for accounts in ctx.remaining_accounts.chunks_exact(2) {
+        let (bid_info, deposit_info) = (&accounts[0], &accounts[1]);
+        require!(bid_info.is_writable, AuctionError::BidAccountMismatch);
+        let mut bid: Account<'info, Bid> = Account::try_from(bid_info)?;
+        require!(bid.auction == auction_key, AuctionError::BidAccountMismatch);
+        require!(bid.committed, AuctionError::BidNotCommitted);
+        require!(!bid.revealed, AuctionError::AlreadyRevealed);
+        require!(
+            bid.auction_slot == auction.creation_slot,
+            AuctionError::StaleBid
+        );
+        let ciphertext = bid
+            .ciphertext
+            .as_ref()
+            .ok_or(AuctionError::InvalidCiphertext)?;
+
+        bid.amount = decrypt_bid_amount(&secret_key, ciphertext, &bid.bidder, &auction_key)?;
+        let deposit = find_deposit(deposit_info, &auction_key, &bid.bidder)?;
+        count_revealed_bid(auction, &mut bid, deposit.as_ref())?;
+
+        // Persist immediately so a duplicated account is rejected as already revealed.
+        bid.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DecryptAndReveal<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+}
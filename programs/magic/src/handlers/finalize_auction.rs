@@ -1,17 +1,51 @@
 use anchor_lang::prelude::*;
 
-use crate::{error::AuctionError, event::AuctionFinalized, state::Auction, AuctionStatus};
+use crate::{
+    error::AuctionError,
+    event::AuctionFinalized,
+    handlers::{advance, ensure_open, status_at, transition},
+    state::Auction,
+    AuctionStatus, BidMode,
+};
 
 pub fn finalize_auction(ctx: Context<FinalizeAuction>) -> Result<()> {
     let now = Clock::get()?.unix_timestamp;
     let auction = &mut ctx.accounts.auction;
-    require!(now >= auction.reveal_end_ts, AuctionError::RevealStillOpen);
-    require!(
-        auction.status != AuctionStatus::Finalized,
-        AuctionError::AuctionAlreadyFinalized
-    );
+    ensure_finalizable(auction, now)?;
+    apply_finalization(auction, now)
+}
+
+/// Checks that the auction's bidding/reveal windows are over and it is not finalized yet.
+pub fn ensure_finalizable(auction: &Auction, now: i64) -> Result<()> {
+    match auction.bid_mode {
+        BidMode::Sealed => {
+            require!(now >= auction.reveal_end_ts, AuctionError::RevealStillOpen);
+        }
+        // Private bids need no reveal window, only a complete resolution.
+        BidMode::Private => {
+            require!(now >= auction.end_ts, AuctionError::BiddingStillOpen);
+            require!(
+                auction.total_revealed == auction.total_bids,
+                AuctionError::PrivateBidsUnresolved
+            );
+        }
+        // Encrypted bids wait for the key until `reveal_end_ts` at the latest. After that
+        // the bids decrypted so far decide, so a withheld key cannot lock the deposits.
+        BidMode::Encrypted => {
+            require!(now >= auction.end_ts, AuctionError::BiddingStillOpen);
+            require!(
+                auction.total_revealed == auction.total_bids || now >= auction.reveal_end_ts,
+                AuctionError::PrivateBidsUnresolved
+            );
+        }
+    }
+    ensure_open(&status_at(auction, now))
+}
 
-    auction.status = AuctionStatus::Finalized;
+/// Moves the auction to `Finalized`, enforces the reserve price and emits `AuctionFinalized`.
+pub(crate) fn apply_finalization(auction: &mut Account<Auction>, now: i64) -> Result<()> {
+    advance(auction, now)?;
+    transition(auction, AuctionStatus::Finalized)?;
     if auction.highest_bid < auction.reserve_price {
         auction.highest_bidder = None;
     }
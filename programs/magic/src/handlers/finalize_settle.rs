@@ -1,28 +1,95 @@
 use anchor_lang::prelude::*;
+use ephemeral_rollups_sdk::access_control::{
+    instructions::CommitAndUndelegatePermissionCpiBuilder, structs::Permission,
+};
 use ephemeral_rollups_sdk::anchor::commit;
-use ephemeral_rollups_sdk::ephem::commit_and_undelegate_accounts;
+use ephemeral_rollups_sdk::consts::PERMISSION_PROGRAM_ID;
+use ephemeral_rollups_sdk::ephem::{
+    CommitAndUndelegate, CommitType, MagicAction, MagicInstructionBuilder, UndelegateType,
+};
 
 use crate::{
-    constant::AUCTION_SEED, error::AuctionError, event::AuctionSettled, state::Auction,
+    constant::{AUCTION_SEED, BID_SEED},
+    error::AuctionError,
+    event::AuctionSettled,
+    handlers::settlement_handler,
+    state::{Auction, Bid},
     AuctionStatus,
 };
 
-pub fn finalize_and_settle(ctx: Context<FinalizeAndSettle>) -> Result<()> {
+/// Commits and undelegates the auction, and the `(bid, bid permission)` pairs passed in
+/// `remaining_accounts`, with `settle_auction` scheduled on L1 once they are all back.
+pub fn finalize_and_settle<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FinalizeAndSettle<'info>>,
+) -> Result<()> {
     let auction = &ctx.accounts.auction;
     require!(
         auction.status == AuctionStatus::Finalized,
         AuctionError::AuctionNotFinalized
     );
+    let bids = auction_bids(&auction.key(), ctx.remaining_accounts)?;
 
     // Exit the account to flush serialized state before commit
     ctx.accounts.auction.exit(&crate::ID)?;
 
+    // Settlement runs on L1 as a post-undelegation action so payment lands atomically
+    // with the committed outcome.
+    let settle_handler = settlement_handler(
+        auction,
+        ctx.accounts.payer.to_account_info(),
+        ctx.accounts.permission.is_some(),
+    );
+
     // Commit and undelegate the auction account back to Solana L1
-    commit_and_undelegate_accounts(
-        &ctx.accounts.payer,
-        vec![&ctx.accounts.auction.to_account_info()],
-        &ctx.accounts.magic_context,
+    let committed = std::iter::once(ctx.accounts.auction.to_account_info())
+        .chain(bids.iter().map(|(info, _, _)| (*info).clone()))
+        .collect();
+    MagicInstructionBuilder {
+        payer: ctx.accounts.payer.to_account_info(),
+        magic_context: ctx.accounts.magic_context.to_account_info(),
+        magic_program: ctx.accounts.magic_program.to_account_info(),
+        magic_action: MagicAction::CommitAndUndelegate(CommitAndUndelegate {
+            commit_type: CommitType::Standalone(committed),
+            undelegate_type: UndelegateType::WithHandler(vec![settle_handler]),
+        }),
+    }
+    .build_and_invoke()?;
+
+    // Return the auction permission to L1 as well so it can be closed there.
+    if let (Some(permission), Some(permission_program)) = (
+        ctx.accounts.permission.as_ref(),
+        ctx.accounts.permission_program.as_ref(),
+    ) {
+        let auction = &ctx.accounts.auction;
+        let (expected_permission, _) = Permission::find_pda(&auction.key());
+        require_keys_eq!(
+            permission.key(),
+            expected_permission,
+            AuctionError::PermissionAccountMismatch
+        );
+
+        let auction_info = auction.to_account_info();
+        CommitAndUndelegatePermissionCpiBuilder::new(permission_program)
+            .authority(&ctx.accounts.authority, true)
+            .permissioned_account(&auction_info, true)
+            .permission(permission)
+            .magic_program(&ctx.accounts.magic_program)
+            .magic_context(&ctx.accounts.magic_context)
+            .invoke_signed(&[&[
+                AUCTION_SEED,
+                auction.authority.as_ref(),
+                &auction.auction_id.to_le_bytes(),
+                &[auction.bump],
+            ]])?;
+    }
+    undelegate_bid_permissions(
+        &bids,
+        ctx.accounts
+            .permission_program
+            .as_ref()
+            .map(|program| program.as_ref()),
         &ctx.accounts.magic_program,
+        &ctx.accounts.magic_context,
     )?;
 
     emit!(AuctionSettled {
@@ -44,4 +111,117 @@ pub struct FinalizeAndSettle<'info> {
     pub authority: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
+    /// CHECK: Optional auction permission PDA, checked against the derived address.
+    #[account(mut)]
+    pub permission: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: Option<UncheckedAccount<'info>>,
+}
+
+/// Commits and undelegates the `(bid, bid permission)` pairs in `remaining_accounts` once
+/// the auction is closed, for bids `finalize_and_settle` did not take along: auctions
+/// finalized by `auto_finalize`, or more bids than fit in one transaction.
+/// Permissionless, like the crank that finalized the auction.
+pub fn undelegate_bids<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UndelegateBids<'info>>,
+) -> Result<()> {
+    require!(
+        ctx.accounts.auction.status.is_closed(),
+        AuctionError::AuctionNotFinalized
+    );
+    let bids = auction_bids(&ctx.accounts.auction.key(), ctx.remaining_accounts)?;
+    require!(!bids.is_empty(), AuctionError::BatchLengthMismatch);
+
+    MagicInstructionBuilder {
+        payer: ctx.accounts.payer.to_account_info(),
+        magic_context: ctx.accounts.magic_context.to_account_info(),
+        magic_program: ctx.accounts.magic_program.to_account_info(),
+        magic_action: MagicAction::CommitAndUndelegate(CommitAndUndelegate {
+            commit_type: CommitType::Standalone(
+                bids.iter().map(|(info, _, _)| (*info).clone()).collect(),
+            ),
+            undelegate_type: UndelegateType::Standalone,
+        }),
+    }
+    .build_and_invoke()?;
+
+    undelegate_bid_permissions(
+        &bids,
+        Some(&ctx.accounts.permission_program),
+        &ctx.accounts.magic_program,
+        &ctx.accounts.magic_context,
+    )
+}
+
+#[commit]
+#[derive(Accounts)]
+pub struct UndelegateBids<'info> {
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+}
+
+/// A bid account, its decoded state and its permission PDA.
+type BidAccounts<'a, 'info> = (&'a AccountInfo<'info>, Bid, &'a AccountInfo<'info>);
+
+/// Decodes the `(bid, bid permission)` pairs in `remaining_accounts` and checks that each
+/// bid belongs to `auction` and each permission to its bid.
+fn auction_bids<'a, 'info>(
+    auction: &Pubkey,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<Vec<BidAccounts<'a, 'info>>> {
+    require!(
+        remaining_accounts.len() % 2 == 0,
+        AuctionError::BatchLengthMismatch
+    );
+    remaining_accounts
+        .chunks_exact(2)
+        .map(|accounts| {
+            let (bid_info, permission) = (&accounts[0], &accounts[1]);
+            require!(bid_info.is_writable, AuctionError::BidAccountMismatch);
+            let bid = Bid::try_deserialize(&mut &bid_info.try_borrow_data()?[..])?;
+            require!(bid.auction == *auction, AuctionError::BidAccountMismatch);
+            require_keys_eq!(
+                permission.key(),
+                Permission::find_pda(bid_info.key).0,
+                AuctionError::PermissionAccountMismatch
+            );
+            Ok((bid_info, bid, permission))
+        })
+        .collect()
+}
+
+/// Commits and undelegates the permissions of `bids` that have one, with each bid PDA
+/// signing for its own permission.
+fn undelegate_bid_permissions<'info>(
+    bids: &[BidAccounts<'_, 'info>],
+    permission_program: Option<&AccountInfo<'info>>,
+    magic_program: &AccountInfo<'info>,
+    magic_context: &AccountInfo<'info>,
+) -> Result<()> {
+    for (bid_info, bid, permission) in bids {
+        if permission.owner != &PERMISSION_PROGRAM_ID {
+            continue;
+        }
+        let permission_program =
+            permission_program.ok_or(AuctionError::PermissionAccountMismatch)?;
+        CommitAndUndelegatePermissionCpiBuilder::new(permission_program)
+            .authority(bid_info, false)
+            .permissioned_account(bid_info, true)
+            .permission(permission)
+            .magic_program(magic_program)
+            .magic_context(magic_context)
+            .invoke_signed(&[&[
+                BID_SEED,
+                bid.auction.as_ref(),
+                &bid.auction_slot.to_le_bytes(),
+                bid.bidder.as_ref(),
+                &[bid.bump],
+            ]])?;
+    }
+    Ok(())
 }
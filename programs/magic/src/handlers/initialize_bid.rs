@@ -15,6 +15,11 @@ pub fn initialize_bid_account(ctx: Context<InitializeBidAccount>) -> Result<()>
     bid.amount = 0;
     bid.nonce = [0_u8; 32];
     bid.bump = ctx.bumps.bid;
+    bid.version = Bid::VERSION;
+    bid.uncovered = false;
+    bid.ciphertext = None;
+    bid.reveal_agent = None;
+    bid.auction_slot = ctx.accounts.auction.creation_slot;
     Ok(())
 }
 
@@ -25,7 +30,7 @@ pub struct InitializeBidAccount<'info> {
         init,
         payer = payer,
         space = 8 + Bid::INIT_SPACE,
-        seeds = [BID_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
         bump
     )]
     pub bid: Account<'info, Bid>,
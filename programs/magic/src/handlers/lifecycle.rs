@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AuctionError, event::AuctionStatusChanged, state::Auction, AuctionStatus};
+
+/// The status the clock alone moves `status` to at `now`, if any.
+fn clock_step(auction: &Auction, status: &AuctionStatus, now: i64) -> Option<AuctionStatus> {
+    match status {
+        AuctionStatus::Scheduled if now >= auction.start_ts => Some(AuctionStatus::Bidding),
+        AuctionStatus::Bidding if now >= auction.end_ts => Some(AuctionStatus::Revealing),
+        _ => None,
+    }
+}
+
+/// The auction's status at `now`, counting clock-driven transitions that no handler
+/// has written yet.
+pub fn status_at(auction: &Auction, now: i64) -> AuctionStatus {
+    let mut status = auction.status.clone();
+    while let Some(next) = clock_step(auction, &status, now) {
+        status = next;
+    }
+    status
+}
+
+/// Writes the clock-driven transitions due at `now`. Handlers that modify the auction
+/// call this before checking its status.
+pub fn advance(auction: &mut Account<Auction>, now: i64) -> Result<()> {
+    while let Some(next) = clock_step(auction, &auction.status, now) {
+        transition(auction, next)?;
+    }
+    Ok(())
+}
+
+/// The only place `Auction::status` changes after creation. Rejects edges the
+/// lifecycle does not have and emits `AuctionStatusChanged`.
+pub fn transition(auction: &mut Account<Auction>, to: AuctionStatus) -> Result<()> {
+    require!(
+        auction.status.can_become(&to),
+        AuctionError::InvalidStatusTransition
+    );
+    let from = std::mem::replace(&mut auction.status, to.clone());
+    emit!(AuctionStatusChanged {
+        auction: auction.key(),
+        from,
+        to,
+    });
+    Ok(())
+}
+
+/// Advances the auction and checks that it takes bids.
+pub(crate) fn ensure_bidding(auction: &mut Account<Auction>, now: i64) -> Result<()> {
+    advance(auction, now)?;
+    match auction.status {
+        AuctionStatus::Bidding => Ok(()),
+        AuctionStatus::Scheduled => err!(AuctionError::AuctionNotStarted),
+        AuctionStatus::Cancelled => err!(AuctionError::AuctionCancelled),
+        _ => err!(AuctionError::BiddingClosed),
+    }
+}
+
+/// Advances the auction and checks that bidding is over.
+pub(crate) fn ensure_bidding_over(auction: &mut Account<Auction>, now: i64) -> Result<()> {
+    advance(auction, now)?;
+    match auction.status {
+        AuctionStatus::Scheduled | AuctionStatus::Bidding => {
+            err!(AuctionError::BiddingStillOpen)
+        }
+        AuctionStatus::Cancelled => err!(AuctionError::AuctionCancelled),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that `status` still has an outcome to decide.
+pub(crate) fn ensure_open(status: &AuctionStatus) -> Result<()> {
+    match status {
+        AuctionStatus::Finalized | AuctionStatus::Settled => {
+            err!(AuctionError::AuctionAlreadyFinalized)
+        }
+        AuctionStatus::Cancelled => err!(AuctionError::AuctionCancelled),
+        _ => Ok(()),
+    }
+}
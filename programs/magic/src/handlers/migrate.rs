@@ -0,0 +1,192 @@
+use anchor_lang::{prelude::*, system_program, Discriminator};
+
+use crate::{
+    constant::{AUCTION_SEED, BID_SEED},
+    error::AuctionError,
+    state::{Auction, Bid},
+    AuctionStatus, BidMode, CommitmentHash, CommitmentScheme,
+};
+
+/// `Auction` as it was written before the layout `version` byte.
+#[derive(AnchorDeserialize, InitSpace)]
+struct LegacyAuction {
+    auction_id: u64,
+    authority: Pubkey,
+    start_ts: i64,
+    end_ts: i64,
+    reveal_end_ts: i64,
+    reserve_price: u64,
+    highest_bid: u64,
+    highest_bidder: Option<Pubkey>,
+    total_bids: u32,
+    total_revealed: u32,
+    status: AuctionStatus,
+    bump: u8,
+}
+
+/// `Bid` as it was written before the layout `version` byte, at `[BID_SEED, auction, bidder]`.
+#[derive(AnchorDeserialize, InitSpace)]
+struct LegacyBid {
+    auction: Pubkey,
+    bidder: Pubkey,
+    bid_hash: [u8; 32],
+    committed: bool,
+    revealed: bool,
+    amount: u64,
+    nonce: [u8; 32],
+    bump: u8,
+}
+
+/// Grows an auction written before the layout version byte to the current layout. The
+/// appended fields take the settings every auction had then: sealed SHA-256 bids under
+/// the legacy commitment scheme, created at slot 0. Permissionless, as the result is
+/// fixed by the existing state; the payer covers the extra rent.
+pub fn migrate_auction(ctx: Context<MigrateAuction>) -> Result<()> {
+    let info = ctx.accounts.auction.to_account_info();
+    let legacy: LegacyAuction = read_legacy(&info, Auction::DISCRIMINATOR)?;
+    let address = Pubkey::create_program_address(
+        &[
+            AUCTION_SEED,
+            legacy.authority.as_ref(),
+            &legacy.auction_id.to_le_bytes(),
+            &[legacy.bump],
+        ],
+        &crate::ID,
+    );
+    require!(address == Ok(info.key()), ErrorCode::ConstraintSeeds);
+
+    let auction = Auction {
+        auction_id: legacy.auction_id,
+        authority: legacy.authority,
+        start_ts: legacy.start_ts,
+        end_ts: legacy.end_ts,
+        reveal_end_ts: legacy.reveal_end_ts,
+        reserve_price: legacy.reserve_price,
+        highest_bid: legacy.highest_bid,
+        highest_bidder: legacy.highest_bidder,
+        total_bids: legacy.total_bids,
+        total_revealed: legacy.total_revealed,
+        status: legacy.status,
+        bump: legacy.bump,
+        version: Auction::VERSION,
+        bid_mode: BidMode::Sealed,
+        commitment_scheme: CommitmentScheme::Legacy,
+        commitment_hash: CommitmentHash::Sha256,
+        range_proof: false,
+        encryption_key: None,
+        derived_nonce: false,
+        creation_slot: 0,
+        demand_commitment: [0_u8; 32],
+        aggregate_demand: None,
+        auto_finalize_task: None,
+    };
+    grow(
+        &info,
+        8 + Auction::INIT_SPACE,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+    auction.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateAuction<'info> {
+    /// CHECK: Legacy auction, decoded and checked against its PDA in the handler.
+    #[account(mut, owner = crate::ID)]
+    pub auction: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a bid written before the layout version byte to the current layout, like
+/// `migrate_auction`. Its `auction_slot` is 0, matching a migrated auction.
+pub fn migrate_bid(ctx: Context<MigrateBid>) -> Result<()> {
+    let info = ctx.accounts.bid.to_account_info();
+    let legacy: LegacyBid = read_legacy(&info, Bid::DISCRIMINATOR)?;
+    let address = Pubkey::create_program_address(
+        &[
+            BID_SEED,
+            legacy.auction.as_ref(),
+            legacy.bidder.as_ref(),
+            &[legacy.bump],
+        ],
+        &crate::ID,
+    );
+    require!(address == Ok(info.key()), ErrorCode::ConstraintSeeds);
+
+    let bid = Bid {
+        auction: legacy.auction,
+        bidder: legacy.bidder,
+        bid_hash: legacy.bid_hash,
+        committed: legacy.committed,
+        revealed: legacy.revealed,
+        amount: legacy.amount,
+        nonce: legacy.nonce,
+        bump: legacy.bump,
+        version: Bid::VERSION,
+        uncovered: false,
+        ciphertext: None,
+        reveal_agent: None,
+        auction_slot: 0,
+    };
+    grow(
+        &info,
+        8 + Bid::INIT_SPACE,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+    bid.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateBid<'info> {
+    /// CHECK: Legacy bid, decoded and checked against its PDA in the handler.
+    #[account(mut, owner = crate::ID)]
+    pub bid: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Decodes `info` as the legacy layout `T`. Accounts already in a versioned layout are
+/// longer than any legacy one and are rejected.
+fn read_legacy<T: AnchorDeserialize + Space>(
+    info: &AccountInfo,
+    discriminator: &[u8],
+) -> Result<T> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() == 8 + T::INIT_SPACE && data.starts_with(discriminator),
+        AuctionError::AccountAlreadyMigrated
+    );
+    Ok(T::deserialize(&mut &data[8..])?)
+}
+
+/// Tops `info` up to the rent of `len` bytes from `payer` and resizes it.
+fn grow<'info>(
+    info: &AccountInfo<'info>,
+    len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    info.resize(len)?;
+    Ok(())
+}
@@ -1,3 +1,6 @@
+pub mod lifecycle;
+pub use lifecycle::*;
+
 pub mod create_auction;
 pub use create_auction::*;
 
@@ -16,6 +19,9 @@ pub use reveal_bid::*;
 pub mod finalize_auction;
 pub use finalize_auction::*;
 
+pub mod cancel_auction;
+pub use cancel_auction::*;
+
 pub mod create_permission;
 pub use create_permission::*;
 
@@ -27,3 +33,45 @@ pub use delegate_bid::*;
 
 pub mod finalize_settle;
 pub use finalize_settle::*;
+
+pub mod publish_permission;
+pub use publish_permission::*;
+
+pub mod close_permission;
+pub use close_permission::*;
+
+pub mod resolve_private;
+pub use resolve_private::*;
+
+pub mod auto_finalize;
+pub use auto_finalize::*;
+
+pub mod deposit;
+pub use deposit::*;
+
+pub mod settle_auction;
+pub use settle_auction::*;
+
+pub mod prepare_private;
+pub use prepare_private::*;
+
+pub mod range_proof;
+pub use range_proof::*;
+
+pub mod encrypted_bid;
+pub use encrypted_bid::*;
+
+pub mod reveal_batch;
+pub use reveal_batch::*;
+
+pub mod submit_signed_bid;
+pub use submit_signed_bid::*;
+
+pub mod aggregate_demand;
+pub use aggregate_demand::*;
+
+pub mod view;
+pub use view::*;
+
+pub mod migrate;
+pub use migrate::*;
@@ -0,0 +1,303 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use ephemeral_rollups_sdk::access_control::{
+    instructions::DelegatePermissionCpiBuilder, structs::Permission,
+};
+use ephemeral_rollups_sdk::anchor::delegate;
+use ephemeral_rollups_sdk::consts::PERMISSION_PROGRAM_ID;
+use ephemeral_rollups_sdk::cpi::DelegateConfig;
+use ephemeral_rollups_sdk::utils::create_pda;
+
+use crate::{
+    constant::{AUCTION_SEED, BID_SEED},
+    error::AuctionError,
+    handlers::invoke_create_permission,
+    state::{Auction, Bid},
+};
+
+/// Onboards a bidder for private bidding in one instruction: creates the bid PDA,
+/// its permission, delegates the permission and then the bid to the ER validator.
+/// Every stage's preconditions are checked before any CPI, so a stage that cannot run
+/// fails with its own error; a failing CPI aborts with the callee's error instead.
+pub fn prepare_private_bid(ctx: Context<PreparePrivateBid>) -> Result<()> {
+    let auction_key = ctx.accounts.auction.key();
+    let auction_slot = ctx.accounts.auction.creation_slot.to_le_bytes();
+    let bidder_key = ctx.accounts.bidder.key();
+    let bump = ctx.bumps.bid;
+    let bid_seeds: &[&[u8]] = &[
+        BID_SEED,
+        auction_key.as_ref(),
+        &auction_slot,
+        bidder_key.as_ref(),
+        &[bump],
+    ];
+    let bid_info = ctx.accounts.bid.to_account_info();
+
+    ensure_unused(&bid_info, AuctionError::BidInitFailed)?;
+    ensure_permission_unused(
+        &ctx.accounts.permission,
+        &bid_info.key(),
+        &ctx.accounts.permission_delegation_record,
+    )?;
+    ensure_undelegated(
+        &bid_info,
+        &ctx.accounts.delegation_record_bid,
+        AuctionError::AccountDelegateFailed,
+    )?;
+
+    // Stage 1: bid account. `create_pda` allocates and assigns an account someone
+    // pre-funded, which a plain `create_account` would refuse.
+    create_pda(
+        &bid_info,
+        &crate::ID,
+        8 + Bid::INIT_SPACE,
+        &[bid_seeds],
+        &ctx.accounts.system_program,
+        &ctx.accounts.payer,
+        true,
+    )?;
+    let bid = Bid {
+        auction: auction_key,
+        bidder: bidder_key,
+        bid_hash: [0_u8; 32],
+        committed: false,
+        revealed: false,
+        amount: 0,
+        nonce: [0_u8; 32],
+        bump,
+        version: Bid::VERSION,
+        uncovered: false,
+        ciphertext: None,
+        reveal_agent: None,
+        auction_slot: ctx.accounts.auction.creation_slot,
+    };
+    bid.try_serialize(&mut &mut bid_info.try_borrow_mut_data()?[..])?;
+
+    // Stage 2: bid permission
+    invoke_create_permission(
+        &bid_info,
+        bidder_key,
+        &ctx.accounts.permission,
+        &ctx.accounts.payer,
+        &ctx.accounts.permission_program,
+        &ctx.accounts.system_program,
+        bid_seeds,
+    )?;
+
+    // Stage 3: permission delegation
+    DelegatePermissionCpiBuilder::new(&ctx.accounts.permission_program)
+        .payer(&ctx.accounts.payer)
+        .authority(&ctx.accounts.bidder, true)
+        .permissioned_account(&bid_info, true)
+        .permission(&ctx.accounts.permission)
+        .system_program(&ctx.accounts.system_program)
+        .owner_program(&ctx.accounts.permission_program)
+        .delegation_buffer(&ctx.accounts.permission_delegation_buffer)
+        .delegation_record(&ctx.accounts.permission_delegation_record)
+        .delegation_metadata(&ctx.accounts.permission_delegation_metadata)
+        .delegation_program(&ctx.accounts.delegation_program)
+        .validator(ctx.accounts.validator.as_ref())
+        .invoke_signed(&[bid_seeds])?;
+
+    // Stage 4: bid delegation
+    let validator = ctx.accounts.validator.as_ref().map(|v| v.key());
+    ctx.accounts.delegate_bid(
+        &ctx.accounts.payer,
+        &[
+            BID_SEED,
+            auction_key.as_ref(),
+            &auction_slot,
+            bidder_key.as_ref(),
+        ],
+        DelegateConfig {
+            validator,
+            commit_frequency_ms: 0,
+        },
+    )?;
+
+    Ok(())
+}
+
+#[delegate]
+#[derive(Accounts)]
+pub struct PreparePrivateBid<'info> {
+    pub auction: Account<'info, Auction>,
+    /// CHECK: Bid PDA that must not exist yet (it may hold lamports), created and
+    /// delegated by this instruction
+    #[account(
+        mut,
+        del,
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
+        bump,
+    )]
+    pub bid: AccountInfo<'info>,
+    /// The bidder identity tied to this bid PDA.
+    pub bidder: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Permission PDA derived from bid, checked by permission program.
+    #[account(mut)]
+    pub permission: UncheckedAccount<'info>,
+    /// CHECK: Delegation buffer of the permission, checked by permission program.
+    #[account(mut)]
+    pub permission_delegation_buffer: UncheckedAccount<'info>,
+    /// CHECK: Delegation record of the permission, checked by delegation program.
+    #[account(mut)]
+    pub permission_delegation_record: UncheckedAccount<'info>,
+    /// CHECK: Delegation metadata of the permission, checked by delegation program.
+    #[account(mut)]
+    pub permission_delegation_metadata: UncheckedAccount<'info>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by the delegate program
+    pub validator: Option<AccountInfo<'info>>,
+}
+
+/// Authority-side counterpart of `prepare_private_bid` for an existing auction:
+/// creates the auction permission, delegates it and then delegates the auction.
+/// Stage preconditions are checked up front in the same way.
+pub fn prepare_private_auction(ctx: Context<PreparePrivateAuction>, auction_id: u64) -> Result<()> {
+    let authority_key = ctx.accounts.authority.key();
+    let auction_info = ctx.accounts.auction.to_account_info();
+    ensure_undelegated(
+        &auction_info,
+        &ctx.accounts.delegation_record_auction,
+        AuctionError::AccountDelegateFailed,
+    )?;
+    let auction = Auction::try_deserialize(&mut &auction_info.try_borrow_data()?[..])?;
+    require_keys_eq!(
+        auction.authority,
+        authority_key,
+        AuctionError::UnauthorizedAuthority
+    );
+    let auction_id_bytes = auction_id.to_le_bytes();
+    let auction_seeds: &[&[u8]] = &[
+        AUCTION_SEED,
+        authority_key.as_ref(),
+        &auction_id_bytes,
+        &[auction.bump],
+    ];
+    ensure_permission_unused(
+        &ctx.accounts.permission,
+        &auction_info.key(),
+        &ctx.accounts.permission_delegation_record,
+    )?;
+
+    // Stage 1: auction permission
+    invoke_create_permission(
+        &auction_info,
+        authority_key,
+        &ctx.accounts.permission,
+        &ctx.accounts.authority,
+        &ctx.accounts.permission_program,
+        &ctx.accounts.system_program,
+        auction_seeds,
+    )?;
+
+    // Stage 2: permission delegation
+    DelegatePermissionCpiBuilder::new(&ctx.accounts.permission_program)
+        .payer(&ctx.accounts.authority)
+        .authority(&ctx.accounts.authority, true)
+        .permissioned_account(&auction_info, true)
+        .permission(&ctx.accounts.permission)
+        .system_program(&ctx.accounts.system_program)
+        .owner_program(&ctx.accounts.permission_program)
+        .delegation_buffer(&ctx.accounts.permission_delegation_buffer)
+        .delegation_record(&ctx.accounts.permission_delegation_record)
+        .delegation_metadata(&ctx.accounts.permission_delegation_metadata)
+        .delegation_program(&ctx.accounts.delegation_program)
+        .validator(ctx.accounts.validator.as_ref())
+        .invoke_signed(&[auction_seeds])?;
+
+    // Stage 3: auction delegation
+    let validator = ctx.accounts.validator.as_ref().map(|v| v.key());
+    ctx.accounts.delegate_auction(
+        &ctx.accounts.authority,
+        &[AUCTION_SEED, authority_key.as_ref(), &auction_id_bytes],
+        DelegateConfig {
+            validator,
+            commit_frequency_ms: 0,
+        },
+    )?;
+
+    Ok(())
+}
+
+#[delegate]
+#[derive(Accounts)]
+#[instruction(auction_id: u64)]
+pub struct PreparePrivateAuction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: The auction PDA to delegate, deserialized in the handler
+    #[account(
+        mut,
+        del,
+        seeds = [AUCTION_SEED, authority.key().as_ref(), &auction_id.to_le_bytes()],
+        bump,
+    )]
+    pub auction: AccountInfo<'info>,
+    /// CHECK: Permission PDA derived from auction, checked by permission program.
+    #[account(mut)]
+    pub permission: UncheckedAccount<'info>,
+    /// CHECK: Delegation buffer of the permission, checked by permission program.
+    #[account(mut)]
+    pub permission_delegation_buffer: UncheckedAccount<'info>,
+    /// CHECK: Delegation record of the permission, checked by delegation program.
+    #[account(mut)]
+    pub permission_delegation_record: UncheckedAccount<'info>,
+    /// CHECK: Delegation metadata of the permission, checked by delegation program.
+    #[account(mut)]
+    pub permission_delegation_metadata: UncheckedAccount<'info>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by the delegate program
+    pub validator: Option<AccountInfo<'info>>,
+}
+
+/// Fails with `stage` unless `account` is still a plain system account without data,
+/// i.e. the stage that creates it has not run. Lamports alone do not count.
+fn ensure_unused(account: &AccountInfo, stage: AuctionError) -> Result<()> {
+    if account.data_is_empty() && account.owner == &system_program::ID {
+        return Ok(());
+    }
+    msg!("{} failed: {} already exists", stage, account.key);
+    Err(stage.into())
+}
+
+/// Preconditions of the permission stages: `permission` is the permissioned account's
+/// PDA and neither it nor its delegation record exists yet.
+fn ensure_permission_unused(
+    permission: &AccountInfo,
+    permissioned_account: &Pubkey,
+    delegation_record: &AccountInfo,
+) -> Result<()> {
+    require_keys_eq!(
+        permission.key(),
+        Permission::find_pda(permissioned_account).0,
+        AuctionError::PermissionAccountMismatch
+    );
+    ensure_unused(permission, AuctionError::PermissionCreateFailed)?;
+    ensure_unused(delegation_record, AuctionError::PermissionDelegateFailed)
+}
+
+/// Fails with `stage` unless this program still owns `account` (once it exists) and
+/// no delegation record exists for it.
+fn ensure_undelegated(
+    account: &AccountInfo,
+    delegation_record: &AccountInfo,
+    stage: AuctionError,
+) -> Result<()> {
+    if !account.data_is_empty() && account.owner != &crate::ID {
+        msg!(
+            "{} failed: {} is owned by {}",
+            stage,
+            account.key,
+            account.owner
+        );
+        return Err(stage.into());
+    }
+    ensure_unused(delegation_record, stage)
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use ephemeral_rollups_sdk::access_control::{
+    instructions::UpdatePermissionCpiBuilder,
+    structs::{MembersArgs, Permission},
+};
+use ephemeral_rollups_sdk::consts::PERMISSION_PROGRAM_ID;
+
+use crate::{
+    constant::BID_SEED,
+    error::AuctionError,
+    handlers::status_at,
+    state::{Auction, Bid},
+    AuctionStatus,
+};
+
+/// Relaxes a bid permission to public once bidding has closed.
+/// Anyone may crank this so bids that are never revealed still become auditable.
+pub fn publish_bid_permission(ctx: Context<PublishBidPermission>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !matches!(
+            status_at(&ctx.accounts.auction, now),
+            AuctionStatus::Scheduled | AuctionStatus::Bidding
+        ),
+        AuctionError::RevealNotStarted
+    );
+
+    make_bid_permission_public(
+        &ctx.accounts.bid,
+        &ctx.accounts.permission,
+        &ctx.accounts.permission_program,
+    )
+}
+
+/// Replaces the bid permission member list with `None`, which the Permission
+/// Program treats as public. The bid PDA signs as the permissioned account.
+pub(crate) fn make_bid_permission_public<'info>(
+    bid: &Account<'info, Bid>,
+    permission: &AccountInfo<'info>,
+    permission_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let (expected_permission, _) = Permission::find_pda(&bid.key());
+    require_keys_eq!(
+        permission.key(),
+        expected_permission,
+        AuctionError::PermissionAccountMismatch
+    );
+
+    let bid_info = bid.to_account_info();
+    UpdatePermissionCpiBuilder::new(permission_program)
+        .authority(&bid_info, false)
+        .permissioned_account(&bid_info, true)
+        .permission(permission)
+        .args(MembersArgs { members: None })
+        .invoke_signed(&[&[
+            BID_SEED,
+            bid.auction.as_ref(),
+            &bid.auction_slot.to_le_bytes(),
+            bid.bidder.as_ref(),
+            &[bid.bump],
+        ]])?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PublishBidPermission<'info> {
+    pub auction: Account<'info, Auction>,
+    #[account(
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bid.bidder.as_ref()],
+        bump = bid.bump,
+        has_one = auction
+    )]
+    pub bid: Account<'info, Bid>,
+    /// CHECK: Permission PDA derived from bid, checked against the derived address.
+    #[account(mut)]
+    pub permission: UncheckedAccount<'info>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constant::RANGE_PROOF_KEY_SEED,
+    error::AuctionError,
+    helpers::{range_proof_public_inputs, verify_range_proof},
+    state::{Auction, Deposit, RangeProofKey},
+    BidProof, RangeProofVerifyingKey,
+};
+
+/// Registers the Groth16 verifying key sealed bids of a range-proof auction are checked
+/// against. The key is write-once and must be in place before `start_ts`, so every
+/// bidder sees it before bidding and it cannot change under them.
+pub fn set_range_proof_key(
+    ctx: Context<SetRangeProofKey>,
+    vk: RangeProofVerifyingKey,
+) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    require!(auction.range_proof, AuctionError::RangeProofRequired);
+    require!(
+        Clock::get()?.unix_timestamp < auction.start_ts,
+        AuctionError::RangeProofKeyAfterStart
+    );
+
+    let key = &mut ctx.accounts.range_proof_key;
+    key.auction = auction.key();
+    key.vk = vk;
+    key.bump = ctx.bumps.range_proof_key;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRangeProofKey<'info> {
+    #[account(has_one = authority)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RangeProofKey::INIT_SPACE,
+        seeds = [RANGE_PROOF_KEY_SEED, auction.key().as_ref()],
+        bump
+    )]
+    pub range_proof_key: Account<'info, RangeProofKey>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Checks that a sealed bid commits to an amount within `[reserve_price, deposit]`
+/// when the auction requires range proofs; a no-op otherwise.
+pub(crate) fn check_range_proof(
+    auction: &Auction,
+    bid_hash: &[u8; 32],
+    proof: Option<&BidProof>,
+    range_proof_key: Option<&Account<RangeProofKey>>,
+    deposit: Option<&Account<Deposit>>,
+) -> Result<()> {
+    if !auction.range_proof {
+        return Ok(());
+    }
+    let (Some(BidProof::Range(proof)), Some(key), Some(deposit)) =
+        (proof, range_proof_key, deposit)
+    else {
+        return err!(AuctionError::RangeProofRequired);
+    };
+
+    let public_inputs = range_proof_public_inputs(bid_hash, auction.reserve_price, deposit.amount);
+    verify_range_proof(&key.vk, proof, &public_inputs)
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AuctionError,
+    handlers::{count_revealed_bid, ensure_bidding_over, ensure_open, find_deposit},
+    state::{Auction, Bid},
+    BidMode,
+};
+
+/// Resolves the plaintext bids passed in `remaining_accounts`, each followed by its
+/// bidder's deposit PDA, against a private-mode auction. Permissionless so any crank
+/// can run it on the ER once bidding closes; large auctions can be resolved over several
+/// calls and `finalize_auction` waits until every committed bid has been counted.
+pub fn resolve_private_auction<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ResolvePrivateAuction<'info>>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+
+    require!(
+        auction.bid_mode == BidMode::Private,
+        AuctionError::BidModeMismatch
+    );
+    ensure_bidding_over(auction, now)?;
+    ensure_open(&auction.status)?;
+
+    let auction_key = auction.key();
+    require!(
+        ctx.remaining_accounts.len() % 2 == 0,
+        AuctionError::BatchLengthMismatch
+    );
+    for accounts in ctx.remaining_accounts.chunks_exact(2) {
+        let (bid_info, deposit_info) = (&accounts[0], &accounts[1]);
+        require!(bid_info.is_writable, AuctionError::BidAccountMismatch);
+        let mut bid: Account<'info, Bid> = Account::try_from(bid_info)?;
+        require!(bid.auction == auction_key, AuctionError::BidAccountMismatch);
+        require!(bid.committed, AuctionError::BidNotCommitted);
+        require!(!bid.revealed, AuctionError::AlreadyRevealed);
+        require!(
+            bid.auction_slot == auction.creation_slot,
+            AuctionError::StaleBid
+        );
+
+        let deposit = find_deposit(deposit_info, &auction_key, &bid.bidder)?;
+        count_revealed_bid(auction, &mut bid, deposit.as_ref())?;
+
+        // Persist immediately so a duplicated account is rejected as already revealed.
+        bid.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ResolvePrivateAuction<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::load_current_index_checked;
+
+use crate::{
+    error::AuctionError,
+    event::BidRevealRejected,
+    handlers::{
+        check_revealer, count_revealed_bid, ensure_reveal_open, find_deposit, open_bid,
+        verify_nonce_signature,
+    },
+    state::{Auction, Bid},
+    BidReveal,
+};
+
+/// Reveals many sealed bids in one transaction for reveal services. `reveals[i]` opens
+/// the `Bid` at `remaining_accounts[2 * i]`, followed by its bidder's deposit PDA. A
+/// preimage that does not open its bid is reported with `BidRevealRejected` instead of
+/// failing the batch. For `derived_nonce` auctions the batch is preceded by one Ed25519
+/// nonce signature instruction per reveal, in the same order as `reveals`.
+pub fn reveal_bids_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RevealBidsBatch<'info>>,
+    reveals: Vec<BidReveal>,
+) -> Result<()> {
+    let auction = &mut ctx.accounts.auction;
+    ensure_reveal_open(auction)?;
+    require!(
+        ctx.remaining_accounts.len() == reveals.len() * 2,
+        AuctionError::BatchLengthMismatch
+    );
+    // For `derived_nonce`, the index of the Ed25519 instruction signing the first nonce.
+    let first_signature = if auction.derived_nonce {
+        let instructions = ctx
+            .accounts
+            .instructions
+            .as_ref()
+            .ok_or(AuctionError::NonceSignatureRequired)?;
+        let current_index = load_current_index_checked(instructions)? as usize;
+        Some(
+            current_index
+                .checked_sub(reveals.len())
+                .ok_or(AuctionError::NonceSignatureRequired)?,
+        )
+    } else {
+        None
+    };
+
+    let auction_key = auction.key();
+    let revealer = ctx.accounts.revealer.key();
+    for (i, (reveal, accounts)) in reveals
+        .into_iter()
+        .zip(ctx.remaining_accounts.chunks_exact(2))
+        .enumerate()
+    {
+        let (bid_info, deposit_info) = (&accounts[0], &accounts[1]);
+        require!(bid_info.is_writable, AuctionError::BidAccountMismatch);
+        let mut bid: Account<'info, Bid> = Account::try_from(bid_info)?;
+        require!(bid.auction == auction_key, AuctionError::BidAccountMismatch);
+        let deposit = find_deposit(deposit_info, &auction_key, &bid.bidder)?;
+
+        let signed = match (first_signature, ctx.accounts.instructions.as_ref()) {
+            (Some(first), Some(instructions)) => {
+                verify_nonce_signature(auction, &bid, &reveal.nonce, instructions, first + i)
+            }
+            _ => Ok(()),
+        };
+        let result = check_revealer(&bid, &revealer)
+            .and(signed)
+            .and_then(|()| open_bid(auction, &mut bid, reveal.amount, reveal.nonce));
+        if let Err(err) = result {
+            emit!(BidRevealRejected {
+                auction: auction_key,
+                bidder: bid.bidder,
+                error_code: match err {
+                    Error::AnchorError(err) => err.error_code_number,
+                    Error::ProgramError(_) => 0,
+                },
+            });
+            continue;
+        }
+        count_revealed_bid(auction, &mut bid, deposit.as_ref())?;
+
+        // Persist immediately so a duplicated account is rejected as already revealed.
+        bid.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevealBidsBatch<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+    pub revealer: Signer<'info>,
+    /// CHECK: Instructions sysvar, required for auctions with `derived_nonce`.
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
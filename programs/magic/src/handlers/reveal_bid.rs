@@ -1,60 +1,309 @@
 use anchor_lang::prelude::*;
+use ephemeral_rollups_sdk::consts::PERMISSION_PROGRAM_ID;
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
 
 use crate::{
-    constant::BID_SEED,
+    constant::{BID_SEED, DEPOSIT_SEED},
     error::AuctionError,
-    event::BidRevealed,
-    helpers::compute_bid_hash,
-    state::{Auction, Bid},
+    event::{BidRevealed, BidUncovered},
+    handlers::{advance, make_bid_permission_public, read_deposit},
+    helpers::{
+        compute_bid_commitment, derive_bid_nonce, nonce_derivation_message, outbids,
+        verify_ed25519_instruction,
+    },
+    state::{Auction, Bid, Deposit},
+    AuctionStatus, BidMode,
 };
 
 pub fn reveal_bid(ctx: Context<RevealBid>, amount: u64, nonce: [u8; 32]) -> Result<()> {
-    let now = Clock::get()?.unix_timestamp;
-    let auction = &mut ctx.accounts.auction;
+    check_derived_nonce(
+        &ctx.accounts.auction,
+        &ctx.accounts.bid,
+        &nonce,
+        ctx.accounts.instructions.as_ref(),
+    )?;
+    let deposit = read_deposit(&ctx.accounts.deposit)?;
+    apply_reveal(
+        &mut ctx.accounts.auction,
+        &mut ctx.accounts.bid,
+        deposit.as_ref(),
+        amount,
+        nonce,
+    )?;
 
-    require!(now >= auction.end_ts, AuctionError::RevealNotStarted);
-    require!(now < auction.reveal_end_ts, AuctionError::RevealClosed);
+    // Publish the bid permission alongside the reveal when the caller supplies it.
+    if let (Some(permission), Some(permission_program)) = (
+        ctx.accounts.permission.as_ref(),
+        ctx.accounts.permission_program.as_ref(),
+    ) {
+        make_bid_permission_public(&ctx.accounts.bid, permission, permission_program)?;
+    }
+    Ok(())
+}
 
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = bidder,
+        has_one = auction
+    )]
+    pub bid: Account<'info, Bid>,
+    pub bidder: Signer<'info>,
+    /// CHECK: The bidder's deposit PDA, which may not exist; read by `read_deposit`.
+    #[account(seeds = [DEPOSIT_SEED, auction.key().as_ref(), bidder.key().as_ref()], bump)]
+    pub deposit: UncheckedAccount<'info>,
+    /// CHECK: Optional bid permission PDA, checked against the derived address.
+    #[account(mut)]
+    pub permission: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Instructions sysvar, required for auctions with `derived_nonce`.
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+/// Reveals a bid on the bidder's behalf. The commitment binds the bidder pubkey, so
+/// anyone holding the preimage can reveal; a bidder who pinned a reveal agent only
+/// lets that agent (or themself) do it.
+pub fn reveal_bid_for(ctx: Context<RevealBidFor>, amount: u64, nonce: [u8; 32]) -> Result<()> {
+    check_revealer(&ctx.accounts.bid, &ctx.accounts.revealer.key())?;
+    check_derived_nonce(
+        &ctx.accounts.auction,
+        &ctx.accounts.bid,
+        &nonce,
+        ctx.accounts.instructions.as_ref(),
+    )?;
+
+    let deposit = read_deposit(&ctx.accounts.deposit)?;
+    apply_reveal(
+        &mut ctx.accounts.auction,
+        &mut ctx.accounts.bid,
+        deposit.as_ref(),
+        amount,
+        nonce,
+    )?;
+
+    if let (Some(permission), Some(permission_program)) = (
+        ctx.accounts.permission.as_ref(),
+        ctx.accounts.permission_program.as_ref(),
+    ) {
+        make_bid_permission_public(&ctx.accounts.bid, permission, permission_program)?;
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevealBidFor<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bid.bidder.as_ref()],
+        bump = bid.bump,
+        has_one = auction
+    )]
+    pub bid: Account<'info, Bid>,
+    pub revealer: Signer<'info>,
+    /// CHECK: The bidder's deposit PDA, which may not exist; read by `read_deposit`.
+    #[account(seeds = [DEPOSIT_SEED, auction.key().as_ref(), bid.bidder.as_ref()], bump)]
+    pub deposit: UncheckedAccount<'info>,
+    /// CHECK: Optional bid permission PDA, checked against the derived address.
+    #[account(mut)]
+    pub permission: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Instructions sysvar, required for auctions with `derived_nonce`.
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+/// A pinned reveal agent narrows who may reveal for the bidder; the bidder always can.
+pub(crate) fn check_revealer(bid: &Bid, revealer: &Pubkey) -> Result<()> {
+    match bid.reveal_agent {
+        Some(agent) if *revealer != agent && *revealer != bid.bidder => {
+            err!(AuctionError::UnauthorizedRevealer)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Lets the bidder (e.g. from a cold wallet) pin or clear the key allowed to reveal
+/// their bid through `reveal_bid_for`.
+pub fn set_reveal_agent(ctx: Context<SetRevealAgent>, agent: Option<Pubkey>) -> Result<()> {
     let bid = &mut ctx.accounts.bid;
-    require!(bid.committed, AuctionError::BidNotCommitted);
     require!(!bid.revealed, AuctionError::AlreadyRevealed);
+    bid.reveal_agent = agent;
+    Ok(())
+}
 
-    let expected = compute_bid_hash(amount, &nonce, &bid.bidder, &auction.key());
-    require!(bid.bid_hash == expected, AuctionError::InvalidReveal);
+#[derive(Accounts)]
+pub struct SetRevealAgent<'info> {
+    #[account(
+        mut,
+        seeds = [BID_SEED, bid.auction.as_ref(), &bid.auction_slot.to_le_bytes(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = bidder
+    )]
+    pub bid: Account<'info, Bid>,
+    pub bidder: Signer<'info>,
+}
 
-    bid.revealed = true;
-    bid.amount = amount;
-    bid.nonce = nonce;
+/// Checks the preimage against the sealed commitment and records the revealed amount.
+fn apply_reveal(
+    auction: &mut Account<Auction>,
+    bid: &mut Account<Bid>,
+    deposit: Option<&Deposit>,
+    amount: u64,
+    nonce: [u8; 32],
+) -> Result<()> {
+    ensure_reveal_open(auction)?;
+    open_bid(auction, bid, amount, nonce)?;
+    count_revealed_bid(auction, bid, deposit)
+}
 
+/// Counts a bid whose amount is now known, however it was opened, and updates the
+/// leader. A bid above the bidder's deposit stays revealed but is reported with
+/// `BidUncovered` and cannot lead, so the highest covered bid wins and settlement can
+/// always take the winning amount from escrow.
+pub(crate) fn count_revealed_bid(
+    auction: &mut Account<Auction>,
+    bid: &mut Bid,
+    deposit: Option<&Deposit>,
+) -> Result<()> {
+    bid.revealed = true;
     auction.total_revealed = auction
         .total_revealed
         .checked_add(1)
         .ok_or(AuctionError::MathOverflow)?;
 
-    if amount > auction.highest_bid {
-        auction.highest_bid = amount;
-        auction.highest_bidder = Some(bid.bidder);
+    let escrowed = deposit.map_or(0, |deposit| deposit.amount);
+    bid.uncovered = bid.amount > escrowed;
+    if bid.uncovered {
+        emit!(BidUncovered {
+            auction: auction.key(),
+            bidder: bid.bidder,
+            amount: bid.amount,
+            deposit: escrowed,
+        });
+        return Ok(());
     }
 
+    if outbids(
+        bid.amount,
+        &bid.bidder,
+        auction.highest_bid,
+        auction.highest_bidder.as_ref(),
+    ) {
+        auction.highest_bid = bid.amount;
+        auction.highest_bidder = Some(bid.bidder);
+    }
     emit!(BidRevealed {
         auction: auction.key(),
         bidder: bid.bidder,
-        amount,
+        amount: bid.amount,
     });
     Ok(())
 }
 
-#[derive(Accounts)]
-pub struct RevealBid<'info> {
-    #[account(mut)]
-    pub auction: Account<'info, Auction>,
-    #[account(
-        mut,
-        seeds = [BID_SEED, auction.key().as_ref(), bidder.key().as_ref()],
-        bump = bid.bump,
-        has_one = bidder,
-        has_one = auction
-    )]
-    pub bid: Account<'info, Bid>,
-    pub bidder: Signer<'info>,
+/// For `derived_nonce` auctions, checks that the nonce is `derive_bid_nonce` of the
+/// bidder's signature, verified by an Ed25519 instruction right before the reveal.
+fn check_derived_nonce(
+    auction: &Account<Auction>,
+    bid: &Bid,
+    nonce: &[u8; 32],
+    instructions: Option<&UncheckedAccount>,
+) -> Result<()> {
+    if !auction.derived_nonce {
+        return Ok(());
+    }
+    let instructions = instructions.ok_or(AuctionError::NonceSignatureRequired)?;
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, AuctionError::NonceSignatureRequired);
+    verify_nonce_signature(
+        auction,
+        bid,
+        nonce,
+        instructions,
+        current_index as usize - 1,
+    )
+}
+
+/// Checks that `nonce` is `derive_bid_nonce` of the bidder's signature verified by the
+/// Ed25519 instruction at `index` in the transaction.
+pub(crate) fn verify_nonce_signature(
+    auction: &Account<Auction>,
+    bid: &Bid,
+    nonce: &[u8; 32],
+    instructions: &AccountInfo,
+    index: usize,
+) -> Result<()> {
+    let ed25519_ix = load_instruction_at_checked(index, instructions)?;
+    let signature = verify_ed25519_instruction(
+        &ed25519_ix,
+        &bid.bidder,
+        &nonce_derivation_message(&auction.key()),
+    )?;
+    require!(
+        derive_bid_nonce(&signature) == *nonce,
+        AuctionError::InvalidReveal
+    );
+    Ok(())
+}
+
+/// Checks that the auction is a sealed-bid auction inside its reveal window.
+pub(crate) fn ensure_reveal_open(auction: &mut Account<Auction>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+
+    advance(auction, now)?;
+    match auction.status {
+        AuctionStatus::Scheduled | AuctionStatus::Bidding => {
+            return err!(AuctionError::RevealNotStarted)
+        }
+        AuctionStatus::Cancelled => return err!(AuctionError::AuctionCancelled),
+        _ => {}
+    }
+    require!(now < auction.reveal_end_ts, AuctionError::RevealClosed);
+    require!(
+        auction.bid_mode == BidMode::Sealed,
+        AuctionError::BidModeMismatch
+    );
+    Ok(())
+}
+
+/// Verifies `(amount, nonce)` against the bid's commitment and records them. Leaves
+/// counting the bid to `count_revealed_bid`.
+pub(crate) fn open_bid(
+    auction: &Account<Auction>,
+    bid: &mut Bid,
+    amount: u64,
+    nonce: [u8; 32],
+) -> Result<()> {
+    require!(bid.committed, AuctionError::BidNotCommitted);
+    require!(!bid.revealed, AuctionError::AlreadyRevealed);
+    require!(
+        bid.auction_slot == auction.creation_slot,
+        AuctionError::StaleBid
+    );
+
+    let expected = compute_bid_commitment(
+        &auction.commitment_hash,
+        &auction.commitment_scheme,
+        amount,
+        &nonce,
+        &bid.bidder,
+        &auction.key(),
+        auction.creation_slot,
+    )?;
+    require!(bid.bid_hash == expected, AuctionError::InvalidReveal);
+
+    bid.amount = amount;
+    bid.nonce = nonce;
+    Ok(())
 }
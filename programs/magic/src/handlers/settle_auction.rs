@@ -0,0 +1,201 @@
+use anchor_lang::{prelude::*, InstructionData};
+use ephemeral_rollups_sdk::access_control::structs::Permission;
+use ephemeral_rollups_sdk::anchor::action;
+use ephemeral_rollups_sdk::consts::PERMISSION_PROGRAM_ID;
+use ephemeral_rollups_sdk::ephem::CallHandler;
+use ephemeral_rollups_sdk::{ActionArgs, ShortAccountMeta};
+
+use crate::{
+    constant::{AUCTION_SEED, BID_SEED, DEPOSIT_SEED, SETTLE_ACTION_COMPUTE_UNITS},
+    error::AuctionError,
+    event::AuctionPaidOut,
+    handlers::{close_returned_permission, find_deposit, transition},
+    state::{Auction, Bid},
+    AuctionStatus,
+};
+
+/// L1 settlement scheduled by `finalize_and_settle` to run right after the auction is
+/// undelegated. Pays the winning bid out of the winner's deposit to the authority.
+/// Reveals only let covered bids win, but a missing or short deposit still settles:
+/// whatever it lacks is recorded as `shortfall` so the auction cannot get stuck.
+/// The outcome is fully determined by committed state, so it is also safe to call
+/// manually if the action ever fails to land.
+///
+/// It also closes the auction permission, and those of the `(bid, bid permission,
+/// bidder)` triples in `remaining_accounts`, that are already back on L1, returning each
+/// rent to the authority or bidder. Permissions still delegated are skipped and left
+/// to `close_auction_permission` / `close_bid_permission`.
+pub fn settle_auction<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SettleAuction<'info>>,
+) -> Result<()> {
+    close_settled_permissions(&ctx)?;
+
+    let auction = &mut ctx.accounts.auction;
+    match auction.status {
+        AuctionStatus::Finalized => {}
+        AuctionStatus::Settled => return err!(AuctionError::AuctionAlreadySettled),
+        _ => return err!(AuctionError::AuctionNotFinalized),
+    }
+
+    let mut paid = 0_u64;
+    let mut shortfall = 0_u64;
+    if let Some(winner) = auction.highest_bidder {
+        let deposit_info = ctx
+            .accounts
+            .winner_deposit
+            .as_ref()
+            .ok_or(AuctionError::DepositMismatch)?;
+        shortfall = auction.highest_bid;
+        if let Some(mut deposit) = find_deposit(deposit_info, &auction.key(), &winner)? {
+            paid = deposit.amount.min(auction.highest_bid);
+            shortfall -= paid;
+            deposit.amount -= paid;
+            deposit.try_serialize(&mut &mut deposit_info.try_borrow_mut_data()?[..])?;
+            deposit_info.sub_lamports(paid)?;
+            ctx.accounts.authority.add_lamports(paid)?;
+        }
+    }
+    transition(auction, AuctionStatus::Settled)?;
+
+    emit!(AuctionPaidOut {
+        auction: auction.key(),
+        winner: auction.highest_bidder,
+        amount: paid,
+        shortfall,
+    });
+    Ok(())
+}
+
+#[action]
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(
+        mut,
+        seeds = [AUCTION_SEED, auction.authority.as_ref(), &auction.auction_id.to_le_bytes()],
+        bump = auction.bump,
+        has_one = authority
+    )]
+    pub auction: Account<'info, Auction>,
+    /// CHECK: The winner's deposit PDA, checked and read by `find_deposit`. It may not
+    /// exist, in which case the whole bid is recorded as shortfall.
+    #[account(mut)]
+    pub winner_deposit: Option<UncheckedAccount<'info>>,
+    /// CHECK: Receives the winning payment; matched against `auction.authority`.
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+    /// CHECK: Optional auction permission PDA, checked against the derived address.
+    #[account(mut)]
+    pub permission: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match MagicBlock Permission Program.
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: Option<UncheckedAccount<'info>>,
+}
+
+fn close_settled_permissions<'info>(
+    ctx: &Context<'_, '_, 'info, 'info, SettleAuction<'info>>,
+) -> Result<()> {
+    let Some(permission_program) = ctx.accounts.permission_program.as_ref() else {
+        return Ok(());
+    };
+    let auction = &ctx.accounts.auction;
+    if let Some(permission) = ctx.accounts.permission.as_ref() {
+        close_returned_permission(
+            &auction.to_account_info(),
+            permission,
+            &ctx.accounts.authority,
+            permission_program,
+            &[
+                AUCTION_SEED,
+                auction.authority.as_ref(),
+                &auction.auction_id.to_le_bytes(),
+                &[auction.bump],
+            ],
+        )?;
+    }
+
+    require!(
+        ctx.remaining_accounts.len() % 3 == 0,
+        AuctionError::BatchLengthMismatch
+    );
+    for accounts in ctx.remaining_accounts.chunks_exact(3) {
+        let (bid_info, permission, bidder) = (&accounts[0], &accounts[1], &accounts[2]);
+        // Bids still delegated cannot sign for their permission yet.
+        if bid_info.owner != &crate::ID {
+            continue;
+        }
+        let bid = Bid::try_deserialize(&mut &bid_info.try_borrow_data()?[..])?;
+        require!(
+            bid.auction == auction.key() && bid.bidder == bidder.key(),
+            AuctionError::BidAccountMismatch
+        );
+        close_returned_permission(
+            bid_info,
+            permission,
+            bidder,
+            permission_program,
+            &[
+                BID_SEED,
+                bid.auction.as_ref(),
+                &bid.auction_slot.to_le_bytes(),
+                bid.bidder.as_ref(),
+                &[bid.bump],
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Builds the `settle_auction` call that the ER schedules on L1 after undelegating `auction`.
+/// `with_permission` has it close the auction permission if it is back on L1 by then.
+pub(crate) fn settlement_handler<'info>(
+    auction: &Account<'info, Auction>,
+    escrow_authority: AccountInfo<'info>,
+    with_permission: bool,
+) -> CallHandler<'info> {
+    let auction_key = auction.key();
+    let winner_deposit = match auction.highest_bidder {
+        Some(winner) => {
+            Pubkey::find_program_address(
+                &[DEPOSIT_SEED, auction_key.as_ref(), winner.as_ref()],
+                &crate::ID,
+            )
+            .0
+        }
+        // Anchor reads the program id as an absent optional account.
+        None => crate::ID,
+    };
+    let (permission, permission_program) = if with_permission {
+        (Permission::find_pda(&auction_key).0, PERMISSION_PROGRAM_ID)
+    } else {
+        (crate::ID, crate::ID)
+    };
+
+    CallHandler {
+        args: ActionArgs::new(crate::instruction::SettleAuction {}.data()),
+        compute_units: SETTLE_ACTION_COMPUTE_UNITS,
+        escrow_authority,
+        destination_program: crate::ID,
+        accounts: vec![
+            ShortAccountMeta {
+                pubkey: auction_key,
+                is_writable: true,
+            },
+            ShortAccountMeta {
+                pubkey: winner_deposit,
+                is_writable: winner_deposit != crate::ID,
+            },
+            ShortAccountMeta {
+                pubkey: auction.authority,
+                is_writable: true,
+            },
+            ShortAccountMeta {
+                pubkey: permission,
+                is_writable: with_permission,
+            },
+            ShortAccountMeta {
+                pubkey: permission_program,
+                is_writable: false,
+            },
+        ],
+    }
+}
@@ -1,29 +1,104 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    constant::BID_SEED,
+    constant::{BID_SEED, DEPOSIT_SEED, RANGE_PROOF_KEY_SEED},
     error::AuctionError,
     event::BidCommitted,
-    state::{Auction, Bid},
+    handlers::{accumulate_demand, check_range_proof, ensure_bidding},
+    state::{Auction, Bid, Deposit, RangeProofKey},
+    BidMode, BidProof,
 };
 
-pub fn submit_sealed_bid(ctx: Context<SubmitSealedBid>, bid_hash: [u8; 32]) -> Result<()> {
-    let now = Clock::get()?.unix_timestamp;
+pub fn submit_sealed_bid(
+    ctx: Context<SubmitSealedBid>,
+    bid_hash: [u8; 32],
+    proof: Option<BidProof>,
+) -> Result<()> {
     let auction = &mut ctx.accounts.auction;
+    ensure_sealed_bidding_open(auction)?;
+    check_range_proof(
+        auction,
+        &bid_hash,
+        proof.as_ref(),
+        ctx.accounts.range_proof_key.as_ref(),
+        ctx.accounts.deposit.as_ref(),
+    )?;
+
+    record_sealed_bid(
+        auction,
+        &mut ctx.accounts.bid,
+        ctx.accounts.bidder.key(),
+        bid_hash,
+        proof.as_ref(),
+        ctx.bumps.bid,
+    )
+}
 
-    require!(now >= auction.start_ts, AuctionError::AuctionNotStarted);
-    require!(now < auction.end_ts, AuctionError::BiddingClosed);
+#[derive(Accounts)]
+pub struct SubmitSealedBid<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// Required when the auction enforces range proofs.
+    #[account(
+        seeds = [RANGE_PROOF_KEY_SEED, auction.key().as_ref()],
+        bump = range_proof_key.bump,
+    )]
+    pub range_proof_key: Option<Account<'info, RangeProofKey>>,
+    /// The bidder's escrow, whose amount bounds the proven bid from above.
+    #[account(
+        seeds = [DEPOSIT_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        bump = deposit.bump,
+    )]
+    pub deposit: Option<Account<'info, Deposit>>,
+    pub system_program: Program<'info, System>,
+}
 
-    let bid = &mut ctx.accounts.bid;
+/// Checks that the auction takes L1 sealed bids right now.
+pub(crate) fn ensure_sealed_bidding_open(auction: &mut Account<Auction>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+
+    ensure_bidding(auction, now)?;
+    // Plaintext bids are only private inside the ER, never on L1.
+    require!(
+        auction.bid_mode == BidMode::Sealed,
+        AuctionError::BidModeMismatch
+    );
+    Ok(())
+}
+
+/// Fills a freshly created `Bid` with the commitment and counts it.
+pub(crate) fn record_sealed_bid(
+    auction: &mut Account<Auction>,
+    bid: &mut Bid,
+    bidder: Pubkey,
+    bid_hash: [u8; 32],
+    proof: Option<&BidProof>,
+    bump: u8,
+) -> Result<()> {
     bid.auction = auction.key();
-    bid.bidder = ctx.accounts.bidder.key();
+    bid.bidder = bidder;
     bid.bid_hash = bid_hash;
     bid.committed = true;
     bid.revealed = false;
     bid.amount = 0;
+    bid.uncovered = false;
     bid.nonce = [0_u8; 32];
-    bid.bump = ctx.bumps.bid;
+    bid.ciphertext = None;
+    bid.reveal_agent = None;
+    bid.auction_slot = auction.creation_slot;
+    bid.bump = bump;
 
+    accumulate_demand(auction, &bidder, &bid_hash, proof)?;
     auction.total_bids = auction
         .total_bids
         .checked_add(1)
@@ -31,24 +106,7 @@ pub fn submit_sealed_bid(ctx: Context<SubmitSealedBid>, bid_hash: [u8; 32]) -> R
 
     emit!(BidCommitted {
         auction: auction.key(),
-        bidder: bid.bidder,
+        bidder,
     });
     Ok(())
 }
-
-#[derive(Accounts)]
-pub struct SubmitSealedBid<'info> {
-    #[account(mut)]
-    pub auction: Account<'info, Auction>,
-    #[account(
-        init,
-        payer = bidder,
-        space = 8 + Bid::INIT_SPACE,
-        seeds = [BID_SEED, auction.key().as_ref(), bidder.key().as_ref()],
-        bump
-    )]
-    pub bid: Account<'info, Bid>,
-    #[account(mut)]
-    pub bidder: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
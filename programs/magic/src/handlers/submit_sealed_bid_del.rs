@@ -1,21 +1,24 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    constant::BID_SEED,
+    constant::{BID_SEED, DEPOSIT_SEED, RANGE_PROOF_KEY_SEED},
     error::AuctionError,
     event::BidCommitted,
-    state::{Auction, Bid},
+    handlers::{accumulate_demand, check_range_proof, ensure_bidding},
+    state::{Auction, Bid, Deposit, RangeProofKey},
+    BidMode, BidProof,
 };
 
 pub fn submit_sealed_bid_delegated(
     ctx: Context<SubmitSealedBidDelegated>,
     bid_hash: [u8; 32],
+    amount: Option<u64>,
+    proof: Option<BidProof>,
 ) -> Result<()> {
     let now = Clock::get()?.unix_timestamp;
     let auction = &mut ctx.accounts.auction;
 
-    require!(now >= auction.start_ts, AuctionError::AuctionNotStarted);
-    require!(now < auction.end_ts, AuctionError::BiddingClosed);
+    ensure_bidding(auction, now)?;
 
     let bid = &mut ctx.accounts.bid;
     require!(!bid.committed, AuctionError::BidAlreadyCommitted);
@@ -28,12 +31,34 @@ pub fn submit_sealed_bid_delegated(
         AuctionError::BidAccountMismatch
     );
 
+    // Private mode stores the plaintext amount in the permissioned bid; sealed mode
+    // only ever sees the commitment.
+    let amount = match auction.bid_mode {
+        BidMode::Sealed => {
+            require!(amount.is_none(), AuctionError::BidModeMismatch);
+            0
+        }
+        BidMode::Private => amount.ok_or(AuctionError::BidModeMismatch)?,
+        BidMode::Encrypted => return err!(AuctionError::BidModeMismatch),
+    };
+    check_range_proof(
+        auction,
+        &bid_hash,
+        proof.as_ref(),
+        ctx.accounts.range_proof_key.as_ref(),
+        ctx.accounts.deposit.as_ref(),
+    )?;
+
     bid.bid_hash = bid_hash;
     bid.committed = true;
     bid.revealed = false;
-    bid.amount = 0;
+    bid.amount = amount;
+    bid.uncovered = false;
     bid.nonce = [0_u8; 32];
+    bid.ciphertext = None;
+    bid.auction_slot = auction.creation_slot;
 
+    accumulate_demand(auction, &bid.bidder, &bid_hash, proof.as_ref())?;
     auction.total_bids = auction
         .total_bids
         .checked_add(1)
@@ -52,11 +77,23 @@ pub struct SubmitSealedBidDelegated<'info> {
     pub auction: Account<'info, Auction>,
     #[account(
         mut,
-        seeds = [BID_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
         bump = bid.bump,
         has_one = bidder,
         has_one = auction
     )]
     pub bid: Account<'info, Bid>,
     pub bidder: Signer<'info>,
+    /// Required when the auction enforces range proofs.
+    #[account(
+        seeds = [RANGE_PROOF_KEY_SEED, auction.key().as_ref()],
+        bump = range_proof_key.bump,
+    )]
+    pub range_proof_key: Option<Account<'info, RangeProofKey>>,
+    /// The bidder's escrow, whose amount bounds the proven bid from above.
+    #[account(
+        seeds = [DEPOSIT_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        bump = deposit.bump,
+    )]
+    pub deposit: Option<Account<'info, Deposit>>,
 }
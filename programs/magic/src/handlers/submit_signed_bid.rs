@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+
+use crate::{
+    constant::{BID_SEED, DEPOSIT_SEED, RANGE_PROOF_KEY_SEED},
+    error::AuctionError,
+    handlers::{check_range_proof, ensure_sealed_bidding_open, record_sealed_bid},
+    helpers::{signed_bid_message, verify_ed25519_instruction},
+    state::{Auction, Bid, Deposit, RangeProofKey},
+    BidProof,
+};
+
+/// Gasless variant of `submit_sealed_bid`: the bidder signs `signed_bid_message` off-chain
+/// and a relayer (or the authority) submits it, paying the `Bid` rent and fees. The
+/// signature is checked by an Ed25519 program instruction placed right before this one.
+pub fn submit_sealed_bid_signed(
+    ctx: Context<SubmitSealedBidSigned>,
+    bid_hash: [u8; 32],
+    proof: Option<BidProof>,
+) -> Result<()> {
+    let instructions = ctx.accounts.instructions.to_account_info();
+    let current_index = load_current_index_checked(&instructions)?;
+    require!(current_index > 0, AuctionError::InvalidBidSignature);
+    let ed25519_ix = load_instruction_at_checked(current_index as usize - 1, &instructions)?;
+    verify_ed25519_instruction(
+        &ed25519_ix,
+        ctx.accounts.bidder.key,
+        &signed_bid_message(
+            &ctx.accounts.auction.key(),
+            ctx.accounts.auction.creation_slot,
+            &bid_hash,
+        ),
+    )?;
+
+    let auction = &mut ctx.accounts.auction;
+    ensure_sealed_bidding_open(auction)?;
+    check_range_proof(
+        auction,
+        &bid_hash,
+        proof.as_ref(),
+        ctx.accounts.range_proof_key.as_ref(),
+        ctx.accounts.deposit.as_ref(),
+    )?;
+
+    record_sealed_bid(
+        auction,
+        &mut ctx.accounts.bid,
+        ctx.accounts.bidder.key(),
+        bid_hash,
+        proof.as_ref(),
+        ctx.bumps.bid,
+    )
+}
+
+#[derive(Accounts)]
+pub struct SubmitSealedBidSigned<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+    /// CHECK: Authenticated by the preceding Ed25519 signature instruction.
+    pub bidder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    /// Required when the auction enforces range proofs.
+    #[account(
+        seeds = [RANGE_PROOF_KEY_SEED, auction.key().as_ref()],
+        bump = range_proof_key.bump,
+    )]
+    pub range_proof_key: Option<Account<'info, RangeProofKey>>,
+    /// The bidder's escrow, whose amount bounds the proven bid from above.
+    #[account(
+        seeds = [DEPOSIT_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        bump = deposit.bump,
+    )]
+    pub deposit: Option<Account<'info, Deposit>>,
+    /// CHECK: Instructions sysvar, checked by address.
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constant::BID_SEED,
+    handlers::{ensure_finalizable, status_at},
+    helpers::set_return_data,
+    state::{Auction, Bid},
+    AuctionState, AuctionStatus, BidMode, BidStatus,
+};
+
+/// Sets the auction's status and counters as return data, for clients to simulate.
+pub fn get_auction_state(ctx: Context<GetAuctionState>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &ctx.accounts.auction;
+    let status = status_at(auction, now);
+    set_return_data(&AuctionState {
+        finalizable: ensure_finalizable(auction, now).is_ok(),
+        now,
+        seconds_remaining: status_deadline(auction, &status, now).map(|deadline| deadline - now),
+        status,
+        total_bids: auction.total_bids,
+        total_revealed: auction.total_revealed,
+    })
+}
+
+/// Sets whether `bidder` committed and revealed in the current auction instance as
+/// return data.
+pub fn get_bid_status(ctx: Context<GetBidStatus>, _bidder: Pubkey) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &ctx.accounts.auction;
+    let bid = &ctx.accounts.bid;
+
+    // A delegated bid is owned by the delegation program on L1 but keeps its data.
+    let readable = bid.owner == &crate::ID || bid.owner == &ephemeral_rollups_sdk::id();
+    let bid = readable
+        .then(|| Bid::try_deserialize(&mut &bid.try_borrow_data().ok()?[..]).ok())
+        .flatten()
+        .filter(|bid| bid.auction_slot == auction.creation_slot);
+
+    set_return_data(&BidStatus {
+        status: status_at(auction, now),
+        committed: bid.as_ref().is_some_and(|bid| bid.committed),
+        revealed: bid.as_ref().is_some_and(|bid| bid.revealed),
+    })
+}
+
+/// When the clock ends the window `status` stands for; `None` once only an
+/// instruction moves the auction on.
+fn status_deadline(auction: &Auction, status: &AuctionStatus, now: i64) -> Option<i64> {
+    match status {
+        AuctionStatus::Scheduled => Some(auction.start_ts),
+        AuctionStatus::Bidding => Some(auction.end_ts),
+        AuctionStatus::Revealing
+            if auction.bid_mode != BidMode::Private && now < auction.reveal_end_ts =>
+        {
+            Some(auction.reveal_end_ts)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Accounts)]
+pub struct GetAuctionState<'info> {
+    pub auction: Account<'info, Auction>,
+}
+
+#[derive(Accounts)]
+#[instruction(bidder: Pubkey)]
+pub struct GetBidStatus<'info> {
+    pub auction: Account<'info, Auction>,
+    /// CHECK: the bidder's bid PDA, which may not exist; decoded by the handler.
+    #[account(seeds = [BID_SEED, auction.key().as_ref(), &auction.creation_slot.to_le_bytes(), bidder.as_ref()], bump)]
+    pub bid: UncheckedAccount<'info>,
+}
@@ -1,20 +1,855 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
 use sha2::{Digest, Sha256};
+use solana_bn254::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
+use solana_curve25519::{
+    ristretto::{
+        add_ristretto, multiply_ristretto, multiscalar_multiply_ristretto, validate_ristretto,
+        PodRistrettoPoint,
+    },
+    scalar::PodScalar,
+};
+use solana_sdk_ids::ed25519_program;
+
+use crate::{
+    constant::{
+        BID_COMMITMENT_DOMAIN, ENCRYPTED_BID_DOMAIN, NONCE_DERIVATION_DOMAIN, OPENING_PROOF_DOMAIN,
+        RANGE_PROOF_PUBLIC_INPUTS, SIGNED_BID_DOMAIN,
+    },
+    error::AuctionError,
+    BidCiphertext, CommitmentHash, CommitmentScheme, OpeningProof, RangeProof,
+    RangeProofVerifyingKey,
+};
+
+/// Pedersen blinding generator `H`: Ristretto hash-to-point (SHA-512) of
+/// `PEDERSEN_H_DOMAIN`, so nobody knows its discrete log relative to `G`.
+const PEDERSEN_H: [u8; 32] = [
+    0xd0, 0xa8, 0xf6, 0x27, 0x69, 0x55, 0x7d, 0x28, 0x6f, 0x47, 0x58, 0x87, 0x3d, 0x77, 0x83, 0x3f,
+    0x07, 0x88, 0xcf, 0x1d, 0x9a, 0x39, 0x03, 0x54, 0x43, 0x0e, 0xbd, 0x02, 0xa7, 0x52, 0x44, 0x17,
+];
+
+/// Compressed Ristretto basepoint.
+const RISTRETTO_BASEPOINT: [u8; 32] = [
+    0xe2, 0xf2, 0xae, 0x0a, 0x6a, 0xbc, 0x4e, 0x71, 0xa8, 0x84, 0xa9, 0x61, 0xc5, 0x00, 0x51, 0x5f,
+    0x58, 0xe3, 0x0b, 0x6a, 0xa5, 0x82, 0xdd, 0x8d, 0xb6, 0xa6, 0x59, 0x45, 0xe0, 0x8d, 0x2d, 0x76,
+];
+
+/// BN254 base field modulus, used to negate G1 points.
+const BN254_BASE_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+/// BN254 scalar field modulus; public inputs must be canonical field elements.
+const BN254_SCALAR_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
 
 /// Computes the SHA-256 bid hash from amount, nonce, bidder pubkey, and auction pubkey.
+/// This is the legacy, untagged layout kept for `CommitmentScheme::Legacy` auctions.
 pub fn compute_bid_hash(
     amount: u64,
     nonce: &[u8; 32],
     bidder: &Pubkey,
     auction: &Pubkey,
 ) -> [u8; 32] {
+    bytes_commitment(
+        sha256v,
+        &CommitmentScheme::Legacy,
+        amount,
+        nonce,
+        bidder,
+        auction,
+        0,
+    )
+}
+
+/// Computes the domain-separated bid commitment:
+/// `SHA-256(domain || version || program_id || auction || bidder || amount || nonce)`.
+pub fn compute_bid_hash_v1(
+    amount: u64,
+    nonce: &[u8; 32],
+    bidder: &Pubkey,
+    auction: &Pubkey,
+) -> [u8; 32] {
+    bytes_commitment(
+        sha256v,
+        &CommitmentScheme::V1,
+        amount,
+        nonce,
+        bidder,
+        auction,
+        0,
+    )
+}
+
+/// Computes the bid commitment under the given scheme and hash function.
+/// `creation_slot` is only mixed in by `CommitmentScheme::V2`.
+pub fn compute_bid_commitment(
+    hash: &CommitmentHash,
+    scheme: &CommitmentScheme,
+    amount: u64,
+    nonce: &[u8; 32],
+    bidder: &Pubkey,
+    auction: &Pubkey,
+    creation_slot: u64,
+) -> Result<[u8; 32]> {
+    match hash {
+        CommitmentHash::Sha256 => Ok(bytes_commitment(
+            sha256v,
+            scheme,
+            amount,
+            nonce,
+            bidder,
+            auction,
+            creation_slot,
+        )),
+        CommitmentHash::Keccak256 => Ok(bytes_commitment(
+            keccak256v,
+            scheme,
+            amount,
+            nonce,
+            bidder,
+            auction,
+            creation_slot,
+        )),
+        CommitmentHash::Poseidon => {
+            poseidon_commitment(scheme, amount, nonce, bidder, auction, creation_slot)
+        }
+        CommitmentHash::Pedersen => pedersen_commitment(amount, nonce),
+    }
+}
+
+/// Byte-oriented hashes share the preimage layout of the scheme; integers are little-endian.
+fn bytes_commitment(
+    hashv: fn(&[&[u8]]) -> [u8; 32],
+    scheme: &CommitmentScheme,
+    amount: u64,
+    nonce: &[u8; 32],
+    bidder: &Pubkey,
+    auction: &Pubkey,
+    creation_slot: u64,
+) -> [u8; 32] {
+    let amount = amount.to_le_bytes();
+    match scheme {
+        CommitmentScheme::Legacy => hashv(&[&amount, nonce, bidder.as_ref(), auction.as_ref()]),
+        CommitmentScheme::V1 => hashv(&[
+            BID_COMMITMENT_DOMAIN,
+            &[CommitmentScheme::V1.version()],
+            crate::ID.as_ref(),
+            auction.as_ref(),
+            bidder.as_ref(),
+            &amount,
+            nonce,
+        ]),
+        CommitmentScheme::V2 => hashv(&[
+            BID_COMMITMENT_DOMAIN,
+            &[CommitmentScheme::V2.version()],
+            crate::ID.as_ref(),
+            auction.as_ref(),
+            &creation_slot.to_le_bytes(),
+            bidder.as_ref(),
+            &amount,
+            nonce,
+        ]),
+    }
+}
+
+fn sha256v(parts: &[&[u8]]) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(amount.to_le_bytes());
-    hasher.update(nonce.as_ref());
-    hasher.update(bidder.as_ref());
-    hasher.update(auction.as_ref());
-    let digest = hasher.finalize();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn keccak256v(parts: &[&[u8]]) -> [u8; 32] {
+    solana_keccak_hasher::hashv(parts).to_bytes()
+}
+
+/// Poseidon works over BN254 field elements, so every input is a 32-byte big-endian
+/// element (see `poseidon_commitment_inputs`).
+fn poseidon_commitment(
+    scheme: &CommitmentScheme,
+    amount: u64,
+    nonce: &[u8; 32],
+    bidder: &Pubkey,
+    auction: &Pubkey,
+    creation_slot: u64,
+) -> Result<[u8; 32]> {
+    let inputs = poseidon_commitment_inputs(scheme, amount, nonce, bidder, auction, creation_slot);
+    let inputs: Vec<&[u8]> = inputs.iter().map(|input| &input[..]).collect();
+    poseidon_hashv(&inputs)
+}
+
+/// The field elements a Poseidon commitment hashes, in order, for circuits that open it:
+/// the amount as an integer, the nonce with its top 3 bits cleared, and each pubkey
+/// split into two 16-byte halves. V1 adds the domain tag, version and program id as
+/// leading elements, matching the byte layout order; V2 also adds the creation slot.
+pub fn poseidon_commitment_inputs(
+    scheme: &CommitmentScheme,
+    amount: u64,
+    nonce: &[u8; 32],
+    bidder: &Pubkey,
+    auction: &Pubkey,
+    creation_slot: u64,
+) -> Vec<[u8; 32]> {
+    let mut amount_fe = [0_u8; 32];
+    amount_fe[24..].copy_from_slice(&amount.to_be_bytes());
+    let mut nonce_fe = *nonce;
+    nonce_fe[0] &= 0x1f;
+    let [bidder_hi, bidder_lo] = split_key(bidder);
+    let [auction_hi, auction_lo] = split_key(auction);
+
+    match scheme {
+        CommitmentScheme::Legacy => vec![
+            amount_fe, nonce_fe, bidder_hi, bidder_lo, auction_hi, auction_lo,
+        ],
+        CommitmentScheme::V1 | CommitmentScheme::V2 => {
+            let mut domain_fe = [0_u8; 32];
+            domain_fe[32 - BID_COMMITMENT_DOMAIN.len()..].copy_from_slice(BID_COMMITMENT_DOMAIN);
+            let mut version_fe = [0_u8; 32];
+            version_fe[31] = scheme.version();
+            let [program_hi, program_lo] = split_key(&crate::ID);
+            let mut inputs = vec![
+                domain_fe, version_fe, program_hi, program_lo, auction_hi, auction_lo,
+            ];
+            if *scheme == CommitmentScheme::V2 {
+                let mut slot_fe = [0_u8; 32];
+                slot_fe[24..].copy_from_slice(&creation_slot.to_be_bytes());
+                inputs.push(slot_fe);
+            }
+            inputs.extend([bidder_hi, bidder_lo, amount_fe, nonce_fe]);
+            inputs
+        }
+    }
+}
+
+/// Splits a pubkey into two field elements that always fit under the BN254 modulus.
+fn split_key(key: &Pubkey) -> [[u8; 32]; 2] {
+    let bytes = key.to_bytes();
+    let mut hi = [0_u8; 32];
+    let mut lo = [0_u8; 32];
+    hi[16..].copy_from_slice(&bytes[..16]);
+    lo[16..].copy_from_slice(&bytes[16..]);
+    [hi, lo]
+}
+
+/// Circom-compatible Poseidon (BN254, x^5, big-endian) via the `sol_poseidon` syscall.
+#[cfg(target_os = "solana")]
+fn poseidon_hashv(inputs: &[&[u8]]) -> Result<[u8; 32]> {
+    const BN254_X5: u64 = 0;
+    const BIG_ENDIAN: u64 = 0;
+
     let mut output = [0_u8; 32];
-    output.copy_from_slice(&digest);
-    output
+    let result = unsafe {
+        solana_define_syscall::definitions::sol_poseidon(
+            BN254_X5,
+            BIG_ENDIAN,
+            inputs as *const _ as *const u8,
+            inputs.len() as u64,
+            output.as_mut_ptr(),
+        )
+    };
+    require!(result == 0, AuctionError::CommitmentHashFailed);
+    Ok(output)
+}
+
+/// Off-chain builds use the same parameters as the syscall.
+#[cfg(not(target_os = "solana"))]
+fn poseidon_hashv(inputs: &[&[u8]]) -> Result<[u8; 32]> {
+    use light_poseidon::{Poseidon, PoseidonBytesHasher};
+
+    Poseidon::<ark_bn254::Fr>::new_circom(inputs.len())
+        .and_then(|mut hasher| hasher.hash_bytes_be(inputs))
+        .map_err(|err| {
+            msg!("Poseidon hash failed: {}", err);
+            error!(AuctionError::CommitmentHashFailed)
+        })
+}
+
+/// Public inputs of the sealed-bid range proof, as 32-byte big-endian field elements.
+pub fn range_proof_public_inputs(
+    bid_hash: &[u8; 32],
+    reserve_price: u64,
+    deposit_amount: u64,
+) -> [[u8; 32]; RANGE_PROOF_PUBLIC_INPUTS] {
+    let mut reserve = [0_u8; 32];
+    reserve[24..].copy_from_slice(&reserve_price.to_be_bytes());
+    let mut deposit = [0_u8; 32];
+    deposit[24..].copy_from_slice(&deposit_amount.to_be_bytes());
+    [*bid_hash, reserve, deposit]
+}
+
+/// Verifies a Groth16 proof with the alt_bn128 syscalls by checking
+/// `e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1`.
+pub fn verify_range_proof(
+    vk: &RangeProofVerifyingKey,
+    proof: &RangeProof,
+    public_inputs: &[[u8; 32]; RANGE_PROOF_PUBLIC_INPUTS],
+) -> Result<()> {
+    let mut vk_x = vk.ic[0].to_vec();
+    for (input, ic) in public_inputs.iter().zip(&vk.ic[1..]) {
+        require!(
+            input < &BN254_SCALAR_MODULUS,
+            AuctionError::InvalidRangeProof
+        );
+        let term = alt_bn128_multiplication(&[&ic[..], input].concat()).map_err(bn254_error)?;
+        vk_x = alt_bn128_addition(&[&vk_x[..], &term].concat()).map_err(bn254_error)?;
+    }
+
+    let pairing = alt_bn128_pairing(
+        &[
+            &negate_g1(&proof.a)[..],
+            &proof.b,
+            &vk.alpha_g1,
+            &vk.beta_g2,
+            &vk_x,
+            &vk.gamma_g2,
+            &proof.c,
+            &vk.delta_g2,
+        ]
+        .concat(),
+    )
+    .map_err(bn254_error)?;
+
+    let mut one = [0_u8; 32];
+    one[31] = 1;
+    require!(pairing == one, AuctionError::InvalidRangeProof);
+    Ok(())
+}
+
+/// Negates a G1 point by replacing `y` with `p - y`; the identity stays as is.
+fn negate_g1(point: &[u8; 64]) -> [u8; 64] {
+    let mut negated = *point;
+    if point[32..].iter().all(|byte| *byte == 0) {
+        return negated;
+    }
+    let mut borrow = 0_i16;
+    for i in (0..32).rev() {
+        let diff = BN254_BASE_MODULUS[i] as i16 - point[32 + i] as i16 - borrow;
+        borrow = (diff < 0) as i16;
+        negated[32 + i] = diff.rem_euclid(256) as u8;
+    }
+    negated
+}
+
+fn bn254_error<E: std::fmt::Debug>(err: E) -> Error {
+    msg!("alt_bn128 syscall failed: {:?}", err);
+    error!(AuctionError::InvalidRangeProof)
+}
+
+/// Pedersen commitment `amount·G + blinding·H`; `blinding` must be a canonical scalar.
+pub fn pedersen_commitment(amount: u64, blinding: &[u8; 32]) -> Result<[u8; 32]> {
+    let mut amount_scalar = [0_u8; 32];
+    amount_scalar[..8].copy_from_slice(&amount.to_le_bytes());
+    multiscalar_multiply_ristretto(
+        &[PodScalar(amount_scalar), PodScalar(*blinding)],
+        &[
+            PodRistrettoPoint(RISTRETTO_BASEPOINT),
+            PodRistrettoPoint(PEDERSEN_H),
+        ],
+    )
+    .map(|point| point.0)
+    .ok_or_else(|| error!(AuctionError::CommitmentHashFailed))
+}
+
+/// Adds a Pedersen commitment into a running sum; the opening of the sum is the
+/// sum of the amounts and of the blindings.
+pub fn add_pedersen_commitments(sum: &[u8; 32], commitment: &[u8; 32]) -> Result<[u8; 32]> {
+    require!(
+        validate_ristretto(&PodRistrettoPoint(*commitment)),
+        AuctionError::InvalidPedersenCommitment
+    );
+    add_ristretto(&PodRistrettoPoint(*sum), &PodRistrettoPoint(*commitment))
+        .map(|point| point.0)
+        .ok_or_else(|| error!(AuctionError::InvalidPedersenCommitment))
+}
+
+/// Fiat-Shamir challenge of an opening proof for `commitment`, bound to the bid it
+/// comes with: the first 16 bytes of
+/// `SHA-256(domain || auction || bidder || commitment || nonce_commitment)`, so it is
+/// always a canonical scalar.
+pub fn opening_challenge(
+    commitment: &[u8; 32],
+    nonce_commitment: &[u8; 32],
+    auction: &Pubkey,
+    bidder: &Pubkey,
+) -> [u8; 32] {
+    let digest = sha256v(&[
+        OPENING_PROOF_DOMAIN,
+        auction.as_ref(),
+        bidder.as_ref(),
+        commitment,
+        nonce_commitment,
+    ]);
+    let mut challenge = [0_u8; 32];
+    challenge[..16].copy_from_slice(&digest[..16]);
+    challenge
+}
+
+/// Checks that `proof` shows knowledge of an opening of the Pedersen `commitment`.
+pub fn verify_opening_proof(
+    commitment: &[u8; 32],
+    proof: &OpeningProof,
+    auction: &Pubkey,
+    bidder: &Pubkey,
+) -> Result<()> {
+    let challenge = opening_challenge(commitment, &proof.nonce_commitment, auction, bidder);
+    let responses = multiscalar_multiply_ristretto(
+        &[
+            PodScalar(proof.amount_response),
+            PodScalar(proof.blinding_response),
+        ],
+        &[
+            PodRistrettoPoint(RISTRETTO_BASEPOINT),
+            PodRistrettoPoint(PEDERSEN_H),
+        ],
+    );
+    let expected = multiply_ristretto(&PodScalar(challenge), &PodRistrettoPoint(*commitment))
+        .and_then(|scaled| add_ristretto(&PodRistrettoPoint(proof.nonce_commitment), &scaled));
+    require!(
+        responses.is_some() && responses == expected,
+        AuctionError::InvalidOpeningProof
+    );
+    Ok(())
+}
+
+/// Derives the Ristretto public key `s·G` for an auction secret key.
+pub fn encryption_public_key(secret_key: &[u8; 32]) -> Result<[u8; 32]> {
+    multiply_ristretto(
+        &PodScalar(*secret_key),
+        &PodRistrettoPoint(RISTRETTO_BASEPOINT),
+    )
+    .map(|point| point.0)
+    .ok_or_else(|| error!(AuctionError::InvalidDecryptionKey))
+}
+
+/// Keystream masking an encrypted amount:
+/// `SHA-256(domain || shared_point || ephemeral_key || auction || bidder)[..8]`.
+/// Bidders derive `shared_point` as `r·PK`, the auction as `s·R`.
+pub fn bid_keystream(
+    shared_point: &[u8; 32],
+    ephemeral_key: &[u8; 32],
+    bidder: &Pubkey,
+    auction: &Pubkey,
+) -> [u8; 8] {
+    let digest = sha256v(&[
+        ENCRYPTED_BID_DOMAIN,
+        shared_point,
+        ephemeral_key,
+        auction.as_ref(),
+        bidder.as_ref(),
+    ]);
+    let mut keystream = [0_u8; 8];
+    keystream.copy_from_slice(&digest[..8]);
+    keystream
+}
+
+/// Opens an encrypted bid with the released auction secret key.
+pub fn decrypt_bid_amount(
+    secret_key: &[u8; 32],
+    ciphertext: &BidCiphertext,
+    bidder: &Pubkey,
+    auction: &Pubkey,
+) -> Result<u64> {
+    let shared_point = multiply_ristretto(
+        &PodScalar(*secret_key),
+        &PodRistrettoPoint(ciphertext.ephemeral_key),
+    )
+    .ok_or(AuctionError::InvalidCiphertext)?;
+    let keystream = bid_keystream(&shared_point.0, &ciphertext.ephemeral_key, bidder, auction);
+
+    let mut amount = ciphertext.masked_amount;
+    for (byte, mask) in amount.iter_mut().zip(keystream) {
+        *byte ^= mask;
+    }
+    Ok(u64::from_le_bytes(amount))
+}
+
+/// Message a bidder signs off-chain to authorize a relayed sealed bid:
+/// `domain || program_id || auction || creation_slot (LE) || bid_hash`. The `Bid` PDA is
+/// one per bidder and auction instance, and the slot keeps the signature from being
+/// replayed into an auction re-created at the same address.
+pub fn signed_bid_message(auction: &Pubkey, creation_slot: u64, bid_hash: &[u8; 32]) -> Vec<u8> {
+    [
+        SIGNED_BID_DOMAIN,
+        crate::ID.as_ref(),
+        auction.as_ref(),
+        &creation_slot.to_le_bytes(),
+        bid_hash,
+    ]
+    .concat()
+}
+
+/// Checks that `ix` is an Ed25519 program instruction verifying exactly one signature
+/// by `signer` over `message`, with all data inline in that instruction, and returns
+/// the verified signature.
+pub fn verify_ed25519_instruction(
+    ix: &Instruction,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<[u8; 64]> {
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    const SAME_INSTRUCTION: u16 = u16::MAX;
+
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        AuctionError::InvalidBidSignature
+    );
+    let data = &ix.data;
+    require!(
+        data.len() >= HEADER_LEN + OFFSETS_LEN && data[0] == 1,
+        AuctionError::InvalidBidSignature
+    );
+
+    let field = |index: usize| {
+        let at = HEADER_LEN + index * 2;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    let (signature_offset, signature_ix) = (field(0), field(1));
+    let (public_key_offset, public_key_ix) = (field(2), field(3));
+    let (message_offset, message_size, message_ix) = (field(4), field(5), field(6));
+    require!(
+        signature_ix == SAME_INSTRUCTION
+            && public_key_ix == SAME_INSTRUCTION
+            && message_ix == SAME_INSTRUCTION,
+        AuctionError::InvalidBidSignature
+    );
+
+    let slice = |offset: u16, len: usize| data.get(offset as usize..offset as usize + len);
+    require!(
+        slice(public_key_offset, 32) == Some(signer.as_ref()),
+        AuctionError::InvalidBidSignature
+    );
+    require!(
+        slice(message_offset, message_size as usize) == Some(message),
+        AuctionError::InvalidBidSignature
+    );
+    slice(signature_offset, 64)
+        .and_then(|signature| signature.try_into().ok())
+        .ok_or_else(|| error!(AuctionError::InvalidBidSignature))
+}
+
+/// Message a bidder signs to derive their bid nonce: `domain || auction`.
+pub fn nonce_derivation_message(auction: &Pubkey) -> Vec<u8> {
+    [NONCE_DERIVATION_DOMAIN, auction.as_ref()].concat()
+}
+
+/// Derives the bid nonce from the bidder's Ed25519 signature over
+/// `nonce_derivation_message`: `SHA-256(domain || signature)`. Ed25519 signatures are
+/// deterministic, so a wallet can regenerate the nonce at reveal time instead of storing it.
+pub fn derive_bid_nonce(signature: &[u8; 64]) -> [u8; 32] {
+    sha256v(&[NONCE_DERIVATION_DOMAIN, signature])
+}
+
+/// Whether a bid of `amount` by `bidder` takes the lead from the current leader. Ties go
+/// to the lower bidder key, so the winner does not depend on the order in which bids are
+/// revealed or resolved, whatever the bid mode.
+pub fn outbids(
+    amount: u64,
+    bidder: &Pubkey,
+    highest_bid: u64,
+    highest_bidder: Option<&Pubkey>,
+) -> bool {
+    match highest_bidder {
+        Some(current) => amount > highest_bid || (amount == highest_bid && bidder < current),
+        None => amount > highest_bid,
+    }
+}
+
+/// Serializes `value` as the instruction's return data. Anchor's typed returns go through
+/// a path that is a no-op off-chain; this one reaches the syscall stubs natively.
+pub fn set_return_data<T: AnchorSerialize>(value: &T) -> Result<()> {
+    let mut data = Vec::new();
+    value.serialize(&mut data)?;
+
+    #[cfg(target_os = "solana")]
+    unsafe {
+        solana_define_syscall::definitions::sol_set_return_data(data.as_ptr(), data.len() as u64)
+    };
+    #[cfg(not(target_os = "solana"))]
+    solana_sysvar::program_stubs::sol_set_return_data(&data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONCE: [u8; 32] = [0xab; 32];
+    const AMOUNT: u64 = 1_500_000_000;
+    const SLOT: u64 = 123_456_789;
+
+    fn vector(hash: CommitmentHash, scheme: CommitmentScheme) -> String {
+        let bidder = Pubkey::new_from_array([1; 32]);
+        let auction = Pubkey::new_from_array([2; 32]);
+        hex::encode(
+            compute_bid_commitment(&hash, &scheme, AMOUNT, &NONCE, &bidder, &auction, SLOT)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn hash_primitives_match_reference_values() {
+        assert_eq!(
+            hex::encode(sha256v(&[b"abc"])),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex::encode(keccak256v(&[b""])),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        let mut one = [0_u8; 32];
+        one[31] = 1;
+        let mut two = [0_u8; 32];
+        two[31] = 2;
+        assert_eq!(
+            hex::encode(poseidon_hashv(&[&one, &two]).unwrap()),
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+    }
+
+    #[test]
+    fn sha256_vectors() {
+        assert_eq!(
+            vector(CommitmentHash::Sha256, CommitmentScheme::Legacy),
+            "75721023d27ee1ec6cd73435ff80ac8e1819a10858c5dd714633f915a92da547"
+        );
+        assert_eq!(
+            vector(CommitmentHash::Sha256, CommitmentScheme::V1),
+            "43ef644c24e05ceb5a75f1c90e8b541e986c7fb366c507a8c5773421f0d6bfdc"
+        );
+    }
+
+    #[test]
+    fn keccak256_vectors() {
+        assert_eq!(
+            vector(CommitmentHash::Keccak256, CommitmentScheme::Legacy),
+            "16fe45f10650257205510e25e9e3e9c7c70a0ed3eeea3e6a59b27c90666bfdd5"
+        );
+        assert_eq!(
+            vector(CommitmentHash::Keccak256, CommitmentScheme::V1),
+            "206050bfca821e713f9cc6462aabbb00b9d37b0440ce3f525ee113d71f3eeb42"
+        );
+    }
+
+    #[test]
+    fn poseidon_vectors() {
+        assert_eq!(
+            vector(CommitmentHash::Poseidon, CommitmentScheme::Legacy),
+            "2219c2175602bfe55e68cda17449c5ed28da7c57ac6097366ea3805af3f5bdc8"
+        );
+        assert_eq!(
+            vector(CommitmentHash::Poseidon, CommitmentScheme::V1),
+            "21ed7c2f64657223407be3287ddcbfede47280ec33f817706b5650716405b4b4"
+        );
+    }
+
+    #[test]
+    fn v2_vectors() {
+        assert_eq!(
+            vector(CommitmentHash::Sha256, CommitmentScheme::V2),
+            "70c6974a2c81f532f69e1bf24dae612933aacbb7a53c314e99c0f094cedb55b3"
+        );
+        assert_eq!(
+            vector(CommitmentHash::Keccak256, CommitmentScheme::V2),
+            "f4c467090540651cc2dd02b2fa346b4c1bfce2410b755d552b950c9ff0991e67"
+        );
+        assert_eq!(
+            vector(CommitmentHash::Poseidon, CommitmentScheme::V2),
+            "2e30cdd4a5a66b7b0024183d37cf53c25d02415eb6e8a58c9d678ff19ca46335"
+        );
+    }
+
+    #[test]
+    fn signed_bid_message_binds_the_auction_instance() {
+        let auction = Pubkey::new_from_array([2; 32]);
+        let message = signed_bid_message(&auction, SLOT, &NONCE);
+        assert_eq!(
+            message,
+            [
+                SIGNED_BID_DOMAIN,
+                crate::ID.as_ref(),
+                auction.as_ref(),
+                &SLOT.to_le_bytes(),
+                &NONCE,
+            ]
+            .concat()
+        );
+        assert_ne!(message, signed_bid_message(&auction, SLOT + 1, &NONCE));
+    }
+
+    #[test]
+    fn negated_g1_cancels_out() {
+        let mut generator = [0_u8; 64];
+        generator[31] = 1;
+        generator[63] = 2;
+        let sum = alt_bn128_addition(&[generator, negate_g1(&generator)].concat()).unwrap();
+        assert_eq!(sum, vec![0_u8; 64]);
+        assert_eq!(negate_g1(&[0_u8; 64]), [0_u8; 64]);
+    }
+
+    /// Proof and key generated by `crates/magic-client/examples/range_proof_fixture.rs`.
+    fn range_proof_fixture() -> (serde_json::Value, RangeProofVerifyingKey, RangeProof) {
+        fn bytes<const N: usize>(value: &serde_json::Value) -> [u8; N] {
+            hex::decode(value.as_str().unwrap())
+                .unwrap()
+                .try_into()
+                .unwrap()
+        }
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../fixtures/range_proof.json")).unwrap();
+        let (vk, proof) = (&fixture["vk"], &fixture["proof"]);
+        let ic: Vec<[u8; 64]> = vk["ic"].as_array().unwrap().iter().map(bytes).collect();
+        let vk = RangeProofVerifyingKey {
+            alpha_g1: bytes(&vk["alpha_g1"]),
+            beta_g2: bytes(&vk["beta_g2"]),
+            gamma_g2: bytes(&vk["gamma_g2"]),
+            delta_g2: bytes(&vk["delta_g2"]),
+            ic: ic.try_into().unwrap(),
+        };
+        let proof = RangeProof {
+            a: bytes(&proof["a"]),
+            b: bytes(&proof["b"]),
+            c: bytes(&proof["c"]),
+        };
+        (fixture, vk, proof)
+    }
+
+    #[test]
+    fn fixture_range_proof_verifies() {
+        let (fixture, vk, proof) = range_proof_fixture();
+        let number = |key: &str| fixture[key].as_u64().unwrap();
+        let key = |name: &str| {
+            Pubkey::new_from_array(
+                hex::decode(fixture[name].as_str().unwrap())
+                    .unwrap()
+                    .try_into()
+                    .unwrap(),
+            )
+        };
+        let nonce: [u8; 32] = hex::decode(fixture["nonce"].as_str().unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let bid_hash = compute_bid_commitment(
+            &CommitmentHash::Poseidon,
+            &CommitmentScheme::V2,
+            number("amount"),
+            &nonce,
+            &key("bidder"),
+            &key("auction"),
+            number("creation_slot"),
+        )
+        .unwrap();
+        assert_eq!(hex::encode(bid_hash), fixture["bid_hash"].as_str().unwrap());
+
+        let (reserve, deposit) = (number("reserve_price"), number("deposit_amount"));
+        let inputs = range_proof_public_inputs(&bid_hash, reserve, deposit);
+        assert!(verify_range_proof(&vk, &proof, &inputs).is_ok());
+
+        let rejected = |inputs, proof: &RangeProof| {
+            verify_range_proof(&vk, proof, &inputs).unwrap_err()
+                == AuctionError::InvalidRangeProof.into()
+        };
+        assert!(rejected(
+            range_proof_public_inputs(&bid_hash, reserve, number("amount") - 1),
+            &proof
+        ));
+        assert!(rejected(
+            range_proof_public_inputs(&bid_hash, reserve + 1, deposit),
+            &proof
+        ));
+        let mut tampered = proof.clone();
+        tampered.c = proof.a;
+        assert!(rejected(inputs, &tampered));
+    }
+
+    #[test]
+    fn ristretto_basepoint_matches_dalek() {
+        assert_eq!(
+            RISTRETTO_BASEPOINT,
+            curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED.to_bytes()
+        );
+    }
+
+    #[test]
+    fn encrypted_bid_round_trips() {
+        let bidder = Pubkey::new_from_array([1; 32]);
+        let auction = Pubkey::new_from_array([2; 32]);
+        let secret_key = [7_u8; 32];
+        let ephemeral_secret = [9_u8; 32];
+        let auction_key = encryption_public_key(&secret_key).unwrap();
+        let ephemeral_key = encryption_public_key(&ephemeral_secret).unwrap();
+
+        let shared_point = multiply_ristretto(
+            &PodScalar(ephemeral_secret),
+            &PodRistrettoPoint(auction_key),
+        )
+        .unwrap();
+        let keystream = bid_keystream(&shared_point.0, &ephemeral_key, &bidder, &auction);
+        let mut masked_amount = AMOUNT.to_le_bytes();
+        for (byte, mask) in masked_amount.iter_mut().zip(keystream) {
+            *byte ^= mask;
+        }
+        let ciphertext = BidCiphertext {
+            ephemeral_key,
+            masked_amount,
+        };
+
+        assert_eq!(
+            decrypt_bid_amount(&secret_key, &ciphertext, &bidder, &auction).unwrap(),
+            AMOUNT
+        );
+        assert_ne!(
+            decrypt_bid_amount(&[8_u8; 32], &ciphertext, &bidder, &auction).unwrap(),
+            AMOUNT
+        );
+    }
+
+    #[test]
+    fn derived_nonce_vector() {
+        let auction = Pubkey::new_from_array([2; 32]);
+        assert_eq!(
+            nonce_derivation_message(&auction),
+            [b"magic-auctions/bid-nonce".as_ref(), &[2; 32]].concat()
+        );
+        assert_eq!(
+            hex::encode(derive_bid_nonce(&[7; 64])),
+            "552f6e3773546677437cefd66cea1e35ce7b93edb286f256edd7b484f9d8e0ca"
+        );
+    }
+
+    #[test]
+    fn pedersen_generator_is_hashed_from_domain() {
+        let h = curve25519_dalek::RistrettoPoint::hash_from_bytes::<sha2::Sha512>(
+            crate::constant::PEDERSEN_H_DOMAIN,
+        );
+        assert_eq!(
+            hex::encode(PEDERSEN_H),
+            hex::encode(h.compress().to_bytes())
+        );
+    }
+
+    #[test]
+    fn pedersen_commitments_add_up() {
+        use curve25519_dalek::Scalar;
+
+        let r1 = Scalar::from(11_u64);
+        let r2 = Scalar::from(31_u64);
+        let sum = add_pedersen_commitments(
+            &pedersen_commitment(100, &r1.to_bytes()).unwrap(),
+            &pedersen_commitment(250, &r2.to_bytes()).unwrap(),
+        )
+        .unwrap();
+        let sum = add_pedersen_commitments(&[0_u8; 32], &sum).unwrap();
+        assert_eq!(
+            sum,
+            pedersen_commitment(350, &(r1 + r2).to_bytes()).unwrap()
+        );
+        assert!(pedersen_commitment(100, &[0xff; 32]).is_err());
+    }
 }
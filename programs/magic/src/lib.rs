@@ -8,6 +8,7 @@ pub mod handlers;
 pub mod helpers;
 pub mod state;
 
+use constant::RANGE_PROOF_PUBLIC_INPUTS;
 use handlers::*;
 
 declare_id!("DahAM1GyX34r7kBuSAwKGpgLCG7XkyVrwheCo8P53VWC");
@@ -24,6 +25,7 @@ pub mod magic {
         end_ts: i64,
         reveal_end_ts: i64,
         reserve_price: u64,
+        options: AuctionOptions,
     ) -> Result<()> {
         handlers::create_auction(
             ctx,
@@ -32,11 +34,31 @@ pub mod magic {
             end_ts,
             reveal_end_ts,
             reserve_price,
+            options,
         )
     }
 
-    pub fn submit_sealed_bid(ctx: Context<SubmitSealedBid>, bid_hash: [u8; 32]) -> Result<()> {
-        handlers::submit_sealed_bid(ctx, bid_hash)
+    pub fn set_range_proof_key(
+        ctx: Context<SetRangeProofKey>,
+        vk: RangeProofVerifyingKey,
+    ) -> Result<()> {
+        handlers::set_range_proof_key(ctx, vk)
+    }
+
+    pub fn submit_sealed_bid(
+        ctx: Context<SubmitSealedBid>,
+        bid_hash: [u8; 32],
+        proof: Option<BidProof>,
+    ) -> Result<()> {
+        handlers::submit_sealed_bid(ctx, bid_hash, proof)
+    }
+
+    pub fn submit_sealed_bid_signed(
+        ctx: Context<SubmitSealedBidSigned>,
+        bid_hash: [u8; 32],
+        proof: Option<BidProof>,
+    ) -> Result<()> {
+        handlers::submit_sealed_bid_signed(ctx, bid_hash, proof)
     }
 
     pub fn initialize_bid_account(ctx: Context<InitializeBidAccount>) -> Result<()> {
@@ -46,18 +68,70 @@ pub mod magic {
     pub fn submit_sealed_bid_delegated(
         ctx: Context<SubmitSealedBidDelegated>,
         bid_hash: [u8; 32],
+        amount: Option<u64>,
+        proof: Option<BidProof>,
     ) -> Result<()> {
-        handlers::submit_sealed_bid_delegated(ctx, bid_hash)
+        handlers::submit_sealed_bid_delegated(ctx, bid_hash, amount, proof)
     }
 
     pub fn reveal_bid(ctx: Context<RevealBid>, amount: u64, nonce: [u8; 32]) -> Result<()> {
         handlers::reveal_bid(ctx, amount, nonce)
     }
 
+    pub fn reveal_bid_for(ctx: Context<RevealBidFor>, amount: u64, nonce: [u8; 32]) -> Result<()> {
+        handlers::reveal_bid_for(ctx, amount, nonce)
+    }
+
+    pub fn reveal_bids_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevealBidsBatch<'info>>,
+        reveals: Vec<BidReveal>,
+    ) -> Result<()> {
+        handlers::reveal_bids_batch(ctx, reveals)
+    }
+
+    pub fn set_reveal_agent(ctx: Context<SetRevealAgent>, agent: Option<Pubkey>) -> Result<()> {
+        handlers::set_reveal_agent(ctx, agent)
+    }
+
     pub fn finalize_auction(ctx: Context<FinalizeAuction>) -> Result<()> {
         handlers::finalize_auction(ctx)
     }
 
+    /// Cancels an auction that has no bids yet, before bidding closes. Authority only.
+    pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+        handlers::cancel_auction(ctx)
+    }
+
+    /// Resolves plaintext bids of a private-mode auction inside the ER. Bid accounts
+    /// are passed as remaining accounts and may be split across several calls.
+    pub fn resolve_private_auction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolvePrivateAuction<'info>>,
+    ) -> Result<()> {
+        handlers::resolve_private_auction(ctx)
+    }
+
+    pub fn submit_encrypted_bid(
+        ctx: Context<SubmitEncryptedBid>,
+        ciphertext: BidCiphertext,
+    ) -> Result<()> {
+        handlers::submit_encrypted_bid(ctx, ciphertext)
+    }
+
+    pub fn decrypt_and_reveal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DecryptAndReveal<'info>>,
+        secret_key: [u8; 32],
+    ) -> Result<()> {
+        handlers::decrypt_and_reveal(ctx, secret_key)
+    }
+
+    pub fn open_aggregate_demand(
+        ctx: Context<OpenAggregateDemand>,
+        total: u64,
+        blinding: [u8; 32],
+    ) -> Result<()> {
+        handlers::open_aggregate_demand(ctx, total, blinding)
+    }
+
     // --- PER: Permission & Delegation ---
 
     /// Creates a permission for the auction PDA via CPI to the Permission Program.
@@ -70,6 +144,21 @@ pub mod magic {
         handlers::create_bid_permission(ctx)
     }
 
+    /// Relaxes a bid permission to public once bidding has closed.
+    pub fn publish_bid_permission(ctx: Context<PublishBidPermission>) -> Result<()> {
+        handlers::publish_bid_permission(ctx)
+    }
+
+    /// Closes the auction permission after settlement, reclaiming its rent.
+    pub fn close_auction_permission(ctx: Context<CloseAuctionPermission>) -> Result<()> {
+        handlers::close_auction_permission(ctx)
+    }
+
+    /// Closes a bid permission after finalization, reclaiming its rent.
+    pub fn close_bid_permission(ctx: Context<CloseBidPermission>) -> Result<()> {
+        handlers::close_bid_permission(ctx)
+    }
+
     /// Delegates the auction PDA to an ER validator using the SDK macro.
     pub fn delegate_auction(
         ctx: Context<DelegateAuction>,
@@ -79,19 +168,289 @@ pub mod magic {
         handlers::delegate_auction(ctx, authority, auction_id)
     }
 
-    /// Delegates the bid PDA to an ER validator using the SDK macro.
-    pub fn delegate_bid(ctx: Context<DelegateBid>, auction: Pubkey, bidder: Pubkey) -> Result<()> {
-        handlers::delegate_bid(ctx, auction, bidder)
+    /// Delegates the bid PDA to an ER validator using the SDK macro. `auction_slot` is the
+    /// auction's `creation_slot`, part of the bid PDA seeds.
+    pub fn delegate_bid(
+        ctx: Context<DelegateBid>,
+        auction: Pubkey,
+        auction_slot: u64,
+        bidder: Pubkey,
+    ) -> Result<()> {
+        handlers::delegate_bid(ctx, auction, auction_slot, bidder)
+    }
+
+    /// Creates, permissions and delegates a bid PDA for private bidding in one call.
+    pub fn prepare_private_bid(ctx: Context<PreparePrivateBid>) -> Result<()> {
+        handlers::prepare_private_bid(ctx)
+    }
+
+    /// Permissions and delegates an existing auction PDA for private bidding in one call.
+    pub fn prepare_private_auction(
+        ctx: Context<PreparePrivateAuction>,
+        auction_id: u64,
+    ) -> Result<()> {
+        handlers::prepare_private_auction(ctx, auction_id)
     }
 
-    /// Commits and undelegates the auction account back to Solana L1.
-    pub fn finalize_and_settle(ctx: Context<FinalizeAndSettle>) -> Result<()> {
+    /// Commits and undelegates the auction account, and the bids passed along, back to Solana L1.
+    pub fn finalize_and_settle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeAndSettle<'info>>,
+    ) -> Result<()> {
         handlers::finalize_and_settle(ctx)
     }
+
+    /// Commits and undelegates the bids of a closed auction, and their permissions.
+    pub fn undelegate_bids<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UndelegateBids<'info>>,
+    ) -> Result<()> {
+        handlers::undelegate_bids(ctx)
+    }
+
+    // --- L1: Escrow & settlement ---
+
+    /// Locks lamports on L1 as the bidder's settlement escrow.
+    pub fn deposit_escrow(ctx: Context<DepositEscrow>, amount: u64) -> Result<()> {
+        handlers::deposit_escrow(ctx, amount)
+    }
+
+    /// Returns a bidder's deposit once it is no longer needed for settlement.
+    pub fn withdraw_deposit(ctx: Context<WithdrawDeposit>) -> Result<()> {
+        handlers::withdraw_deposit(ctx)
+    }
+
+    /// L1 action run after `finalize_and_settle` undelegates the auction; pays the winner's bid out.
+    pub fn settle_auction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleAuction<'info>>,
+    ) -> Result<()> {
+        handlers::settle_auction(ctx)
+    }
+
+    // --- ER: Scheduled crank tasks ---
+
+    /// Schedules (or reschedules) an ER task that finalizes and settles the auction.
+    pub fn schedule_auto_finalize(ctx: Context<ScheduleAutoFinalize>, task_id: u64) -> Result<()> {
+        handlers::schedule_auto_finalize(ctx, task_id)
+    }
+
+    /// Cancels the scheduled auto-finalize task.
+    pub fn cancel_auto_finalize(ctx: Context<CancelAutoFinalize>) -> Result<()> {
+        handlers::cancel_auto_finalize(ctx)
+    }
+
+    /// Crank target of the auto-finalize task: finalizes, commits and undelegates.
+    pub fn auto_finalize(ctx: Context<AutoFinalize>) -> Result<()> {
+        handlers::auto_finalize(ctx)
+    }
+
+    // --- L1: Account migration ---
+
+    /// Grows an auction from before the layout version byte to the current layout.
+    pub fn migrate_auction(ctx: Context<MigrateAuction>) -> Result<()> {
+        handlers::migrate_auction(ctx)
+    }
+
+    /// Grows a bid from before the layout version byte to the current layout.
+    pub fn migrate_bid(ctx: Context<MigrateBid>) -> Result<()> {
+        handlers::migrate_bid(ctx)
+    }
+
+    // --- Views (simulate and read the return data) ---
+
+    /// Returns `AuctionState`: the status by the current clock, time remaining and counters.
+    pub fn get_auction_state(ctx: Context<GetAuctionState>) -> Result<()> {
+        handlers::get_auction_state(ctx)
+    }
+
+    /// Returns `BidStatus`: the status and whether `bidder` committed and revealed.
+    pub fn get_bid_status(ctx: Context<GetBidStatus>, bidder: Pubkey) -> Result<()> {
+        handlers::get_bid_status(ctx, bidder)
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+/// Lifecycle of an auction. Only `handlers::transition` changes it; the clock-driven
+/// steps are applied by the next handler that writes the auction (`handlers::advance`).
+/// `Bidding` and `Finalized` keep the discriminants of the original two-state enum;
+/// later statuses are appended after them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
 pub enum AuctionStatus {
+    /// From `start_ts` until `end_ts`.
     Bidding,
+    /// Winner fixed and the reserve applied.
     Finalized,
+    /// Created, before `start_ts`.
+    Scheduled,
+    /// After `end_ts`, while bids are revealed or resolved, until finalization.
+    Revealing,
+    /// The winner's payment was taken on L1.
+    Settled,
+    /// Withdrawn by the authority before any bid was placed.
+    Cancelled,
+}
+
+impl AuctionStatus {
+    /// Whether the lifecycle has an edge from `self` to `to`.
+    pub fn can_become(&self, to: &AuctionStatus) -> bool {
+        use AuctionStatus::*;
+        matches!(
+            (self, to),
+            (Scheduled, Bidding)
+                | (Bidding, Revealing)
+                | (Revealing, Finalized)
+                | (Finalized, Settled)
+                | (Scheduled | Bidding, Cancelled)
+        )
+    }
+
+    /// Whether the outcome is fixed: finalized, settled or cancelled.
+    pub fn is_closed(&self) -> bool {
+        matches!(
+            self,
+            AuctionStatus::Finalized | AuctionStatus::Settled | AuctionStatus::Cancelled
+        )
+    }
+}
+
+/// Per-auction settings chosen at `create_auction`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct AuctionOptions {
+    pub bid_mode: BidMode,
+    pub commitment_scheme: CommitmentScheme,
+    pub commitment_hash: CommitmentHash,
+    /// Require a Groth16 range proof with every sealed bid; needs `CommitmentHash::Poseidon`.
+    pub range_proof: bool,
+    /// Ristretto public key bids are encrypted to; required for `BidMode::Encrypted`.
+    pub encryption_key: Option<[u8; 32]>,
+    /// Require reveals to prove the nonce came from `derive_bid_nonce`.
+    pub derived_nonce: bool,
+}
+
+/// How bids are placed and opened. Every mode breaks ties between equal amounts the
+/// same way, in favour of the lower bidder key (`helpers::outbids`).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum BidMode {
+    /// Bidders commit a hash and reveal `(amount, nonce)` after `end_ts`.
+    Sealed,
+    /// Bidders submit plaintext amounts to the permissioned `Bid` inside the ER
+    /// and `resolve_private_auction` computes the winner after `end_ts`.
+    Private,
+    /// Bidders encrypt their amount to the auction's Ristretto key; once the secret
+    /// is released after `end_ts`, `decrypt_and_reveal` opens every bid. Whoever holds
+    /// the secret can read bids early, so it must be a trusted authority or a key
+    /// committee. Bids still encrypted at `reveal_end_ts` are left out of the outcome.
+    Encrypted,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum CommitmentScheme {
+    /// `SHA-256(amount || nonce || bidder || auction)`, kept for existing auctions.
+    Legacy,
+    /// Domain-separated `SHA-256(domain || version || program_id || auction || bidder || amount || nonce)`.
+    V1,
+    /// V1 with the auction's `creation_slot` after the auction key, so a reveal cannot be
+    /// replayed against an auction re-created at the same address.
+    V2,
+}
+
+impl CommitmentScheme {
+    /// Version byte mixed into the commitment preimage.
+    pub fn version(&self) -> u8 {
+        match self {
+            CommitmentScheme::Legacy => 0,
+            CommitmentScheme::V1 => 1,
+            CommitmentScheme::V2 => 2,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum CommitmentHash {
+    /// SHA-256 over the scheme's byte preimage.
+    Sha256,
+    /// Keccak-256 over the same byte preimage, for EVM-compatible tooling.
+    Keccak256,
+    /// Circom-compatible Poseidon over BN254 field elements, for ZK bidder proofs.
+    Poseidon,
+    /// Not a hash: the Ristretto Pedersen commitment `amount·G + nonce·H`, where the
+    /// nonce is a canonical scalar. Ignores the scheme; the `Bid` PDA already binds
+    /// bidder and auction. Commitments are summed into `Auction.demand_commitment`.
+    Pedersen,
+}
+
+/// Groth16 verifying key in the alt_bn128 syscall encoding (big-endian, EIP-197 G2 order).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct RangeProofVerifyingKey {
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    pub ic: [[u8; 64]; RANGE_PROOF_PUBLIC_INPUTS + 1],
+}
+
+/// Groth16 proof that the committed amount lies in `[reserve_price, deposit_amount]`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RangeProof {
+    pub a: [u8; 64],
+    pub b: [u8; 128],
+    pub c: [u8; 64],
+}
+
+/// Proof sent with a sealed bid when the auction requires one.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum BidProof {
+    /// For auctions created with `range_proof`.
+    Range(RangeProof),
+    /// For `CommitmentHash::Pedersen` auctions, whose commitments are summed.
+    Opening(OpeningProof),
+}
+
+/// Schnorr proof of knowledge of an opening `(amount, blinding)` of a Pedersen
+/// commitment `C`: `amount_response·G + blinding_response·H == nonce_commitment + e·C`,
+/// with `e` from `helpers::opening_challenge`. It keeps bidders from adding points
+/// they cannot open, such as a copy or the negation of another bid, to the demand sum.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct OpeningProof {
+    pub nonce_commitment: [u8; 32],
+    pub amount_response: [u8; 32],
+    pub blinding_response: [u8; 32],
+}
+
+/// A bid amount encrypted to the auction key: `masked_amount` is the little-endian
+/// amount XOR a keystream derived from the shared point `r·PK` (see `helpers`).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct BidCiphertext {
+    /// The bidder's ephemeral Ristretto point `r·G`.
+    pub ephemeral_key: [u8; 32],
+    pub masked_amount: [u8; 8],
+}
+
+/// One `(amount, nonce)` preimage of a `reveal_bids_batch` call.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct BidReveal {
+    pub amount: u64,
+    pub nonce: [u8; 32],
+}
+
+/// Return data of `get_auction_state`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AuctionState {
+    /// Status at `now`, including clock-driven transitions not yet written to the account.
+    pub status: AuctionStatus,
+    /// `finalize_auction` would succeed now.
+    pub finalizable: bool,
+    /// Cluster time the status was computed at.
+    pub now: i64,
+    /// Seconds until the clock ends the current window, or `None` once only an
+    /// instruction can move the auction on.
+    pub seconds_remaining: Option<i64>,
+    pub total_bids: u32,
+    pub total_revealed: u32,
+}
+
+/// Return data of `get_bid_status`. Bids from an earlier instance of the auction count
+/// as neither committed nor revealed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BidStatus {
+    pub status: AuctionStatus,
+    pub committed: bool,
+    pub revealed: bool,
 }
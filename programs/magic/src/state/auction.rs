@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 
-use crate::AuctionStatus;
+use crate::{AuctionStatus, BidMode, CommitmentHash, CommitmentScheme};
 
+/// Fields are only ever appended, after `version`, so existing accounts can be grown in
+/// place by `migrate_auction`.
 #[account]
 #[derive(InitSpace)]
 pub struct Auction {
@@ -17,4 +19,24 @@ pub struct Auction {
     pub total_revealed: u32,
     pub status: AuctionStatus,
     pub bump: u8,
+    /// Layout the account was written with, `Auction::VERSION` once migrated. Auctions
+    /// from before the field existed end at `bump`.
+    pub version: u8,
+    pub bid_mode: BidMode,
+    pub commitment_scheme: CommitmentScheme,
+    pub commitment_hash: CommitmentHash,
+    pub range_proof: bool,
+    pub encryption_key: Option<[u8; 32]>,
+    pub derived_nonce: bool,
+    /// Slot the auction was created in. Mixed into V2 commitments and stamped on every
+    /// bid, so a closed auction re-created at the same address rejects stale reveals.
+    pub creation_slot: u64,
+    /// Sum of all Pedersen bid commitments (Ristretto identity when empty).
+    pub demand_commitment: [u8; 32],
+    pub aggregate_demand: Option<u64>,
+    pub auto_finalize_task: Option<u64>,
+}
+
+impl Auction {
+    pub const VERSION: u8 = 1;
 }
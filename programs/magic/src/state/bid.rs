@@ -1,5 +1,9 @@
 use anchor_lang::prelude::*;
 
+use crate::BidCiphertext;
+
+/// Fields are only ever appended, after `version`, so existing accounts can be grown in
+/// place by `migrate_bid`.
 #[account]
 #[derive(InitSpace)]
 pub struct Bid {
@@ -11,4 +15,18 @@ pub struct Bid {
     pub amount: u64,
     pub nonce: [u8; 32],
     pub bump: u8,
+    /// Layout the account was written with, `Bid::VERSION` once migrated. Bids from
+    /// before the field existed end at `bump`.
+    pub version: u8,
+    /// The revealed amount exceeds the bidder's deposit, so the bid cannot win.
+    pub uncovered: bool,
+    pub ciphertext: Option<BidCiphertext>,
+    /// Optional hot key allowed to reveal on the bidder's behalf via `reveal_bid_for`.
+    pub reveal_agent: Option<Pubkey>,
+    /// `Auction::creation_slot` of the auction instance this bid was placed in.
+    pub auction_slot: u64,
+}
+
+impl Bid {
+    pub const VERSION: u8 = 1;
 }
@@ -0,0 +1,12 @@
+use anchor_lang::prelude::*;
+
+/// Lamport escrow a bidder locks on L1. It is never delegated, so settlement can
+/// pay the winner's bid out of it right after the auction is undelegated.
+#[account]
+#[derive(InitSpace)]
+pub struct Deposit {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
@@ -3,3 +3,9 @@ pub use auction::*;
 
 pub mod bid;
 pub use bid::*;
+
+pub mod deposit;
+pub use deposit::*;
+
+pub mod range_proof_key;
+pub use range_proof_key::*;
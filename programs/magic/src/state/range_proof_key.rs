@@ -0,0 +1,12 @@
+use anchor_lang::prelude::*;
+
+use crate::RangeProofVerifyingKey;
+
+/// Groth16 verifying key the auction authority registers for range-proof auctions.
+#[account]
+#[derive(InitSpace)]
+pub struct RangeProofKey {
+    pub auction: Pubkey,
+    pub vk: RangeProofVerifyingKey,
+    pub bump: u8,
+}
@@ -4,6 +4,7 @@ import { createHash, randomBytes } from "crypto";
 import { expect } from "chai";
 import {
     Connection,
+    Ed25519Program,
     Keypair,
     PublicKey,
     Transaction,
@@ -57,16 +58,17 @@ async function waitUntilOnchainUnix(
     }
 }
 
-async function waitForAuctionFinalizedOnL1(
+async function waitForAuctionStatusOnL1(
     program: Program<Magic>,
     auctionPda: PublicKey,
+    status: string,
     timeoutMs = 20_000
 ) {
     const startedAt = Date.now();
     while (Date.now() - startedAt < timeoutMs) {
         try {
             const auction = await program.account.auction.fetch(auctionPda);
-            if ("finalized" in auction.status) {
+            if (status in auction.status) {
                 return auction;
             }
         } catch {
@@ -74,7 +76,7 @@ async function waitForAuctionFinalizedOnL1(
         }
         await sleep(500);
     }
-    throw new Error("Timed out waiting for finalized auction state on L1");
+    throw new Error(`Timed out waiting for ${status} auction state on L1`);
 }
 
 async function sendViaErWithSigners(params: {
@@ -112,6 +114,76 @@ const computeBidHash = (
     return Array.from(digest);
 };
 
+const BID_COMMITMENT_DOMAIN = Buffer.from("magic-auctions/bid-commitment");
+const SIGNED_BID_DOMAIN = Buffer.from("magic-auctions/signed-bid");
+const NONCE_DERIVATION_DOMAIN = Buffer.from("magic-auctions/bid-nonce");
+
+/**
+ * Ed25519 instruction carrying the bidder's signature over the nonce derivation message,
+ * and the nonce derived from it. Signing is deterministic, so this regenerates the same
+ * nonce at reveal time.
+ */
+const deriveBidNonce = (bidder: Keypair, auction: PublicKey) => {
+    const ix = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: bidder.secretKey,
+        message: Buffer.concat([NONCE_DERIVATION_DOMAIN, auction.toBuffer()]),
+    });
+    // Layout: 16-byte header, 32-byte public key, then the 64-byte signature.
+    const signature = ix.data.subarray(48, 112);
+    const nonce = createHash("sha256").update(NONCE_DERIVATION_DOMAIN).update(signature).digest();
+    return { ix, nonce };
+};
+
+const computeBidHashV1 = (
+    programId: PublicKey,
+    amount: anchor.BN,
+    nonce: Buffer,
+    bidder: PublicKey,
+    auction: PublicKey
+): number[] => {
+    const digest = createHash("sha256")
+        .update(BID_COMMITMENT_DOMAIN)
+        .update(Buffer.from([1]))
+        .update(programId.toBuffer())
+        .update(auction.toBuffer())
+        .update(bidder.toBuffer())
+        .update(u64Le(amount))
+        .update(nonce)
+        .digest();
+    return Array.from(digest);
+};
+
+/** V1 layout with the auction's creation slot (u64 LE) right after the auction key. */
+const computeBidHashV2 = (
+    programId: PublicKey,
+    amount: anchor.BN,
+    nonce: Buffer,
+    bidder: PublicKey,
+    auction: PublicKey,
+    creationSlot: anchor.BN
+): number[] => {
+    const digest = createHash("sha256")
+        .update(BID_COMMITMENT_DOMAIN)
+        .update(Buffer.from([2]))
+        .update(programId.toBuffer())
+        .update(auction.toBuffer())
+        .update(u64Le(creationSlot))
+        .update(bidder.toBuffer())
+        .update(u64Le(amount))
+        .update(nonce)
+        .digest();
+    return Array.from(digest);
+};
+
+const sealedLegacyOptions = {
+    bidMode: { sealed: {} },
+    commitmentScheme: { legacy: {} },
+    commitmentHash: { sha256: {} },
+    rangeProof: false,
+    encryptionKey: null,
+    derivedNonce: false,
+};
+
 /**
  * Fund a keypair by transferring SOL from the provider wallet.
  * Uses transfer instead of airdrop to avoid devnet rate limits.
@@ -131,6 +203,46 @@ async function fundWallet(
     await provider.sendAndConfirm(tx);
 }
 
+/**
+ * Bid PDA of `bidder`. The seeds include the auction's `creation_slot`, so an auction
+ * re-created at the same address gets fresh bid accounts.
+ */
+const findBidPda = async (program: Program<Magic>, auction: PublicKey, bidder: PublicKey) => {
+    const { creationSlot } = await program.account.auction.fetch(auction);
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("bid"), auction.toBuffer(), u64Le(creationSlot), bidder.toBuffer()],
+        program.programId
+    );
+};
+
+const depositPda = (programId: PublicKey, auction: PublicKey, bidder: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+        [Buffer.from("deposit"), auction.toBuffer(), bidder.toBuffer()],
+        programId
+    )[0];
+
+/**
+ * Escrow `amount` lamports for the bidder. Reveals above a bidder's deposit are
+ * counted but cannot win, so every bidder expected to win escrows first.
+ */
+async function depositEscrow(
+    program: Program<Magic>,
+    auction: PublicKey,
+    bidder: Keypair,
+    amount: anchor.BN
+): Promise<void> {
+    await program.methods
+        .depositEscrow(amount)
+        .accounts({
+            auction,
+            deposit: depositPda(program.programId, auction, bidder.publicKey),
+            bidder: bidder.publicKey,
+            systemProgram: SystemProgram.programId,
+        } as any)
+        .signers([bidder])
+        .rpc();
+}
+
 // ===========================================================================
 // Suite 1: Core Auction Lifecycle on Devnet
 // ===========================================================================
@@ -161,7 +273,14 @@ describe("magic — core auction lifecycle (devnet)", function () {
 
         // --- Create Auction ---
         await program.methods
-            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice)
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, {
+                bidMode: { sealed: {} },
+                commitmentScheme: { v1: {} },
+                commitmentHash: { sha256: {} },
+                rangeProof: false,
+                encryptionKey: null,
+                derivedNonce: false,
+            })
             .accounts({
                 auction: auctionPda,
                 authority,
@@ -171,24 +290,27 @@ describe("magic — core auction lifecycle (devnet)", function () {
 
         let auction = await program.account.auction.fetch(auctionPda);
         expect(auction.auctionId.toNumber()).to.equal(auctionId.toNumber());
-        expect(auction.status).to.deep.equal({ bidding: {} });
+        expect(auction.status).to.deep.equal({ scheduled: {} });
         console.log("     Auction created:", auctionPda.toBase58());
 
         // Wait for bidding to start
         await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
 
         // --- Submit Sealed Bid ---
-        const [bidPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("bid"), auctionPda.toBuffer(), bidder.publicKey.toBuffer()],
-            program.programId
-        );
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
 
         const bidAmount = new anchor.BN(100);
         const nonce = randomBytes(32);
-        const bidHash = computeBidHash(bidAmount, nonce, bidder.publicKey, auctionPda);
+        const bidHash = computeBidHashV1(
+            program.programId,
+            bidAmount,
+            nonce,
+            bidder.publicKey,
+            auctionPda
+        );
 
         await program.methods
-            .submitSealedBid(bidHash)
+            .submitSealedBid(bidHash, null)
             .accounts({
                 auction: auctionPda,
                 bid: bidPda,
@@ -203,6 +325,8 @@ describe("magic — core auction lifecycle (devnet)", function () {
         expect(bid.amount.toNumber()).to.equal(0);
         console.log("     Sealed bid submitted");
 
+        await depositEscrow(program, auctionPda, bidder, bidAmount);
+
         // Wait for reveal phase
         await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
 
@@ -260,23 +384,20 @@ describe("magic — core auction lifecycle (devnet)", function () {
         );
 
         await program.methods
-            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice)
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, sealedLegacyOptions)
             .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
             .rpc();
 
         await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
 
-        const [bidPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("bid"), auctionPda.toBuffer(), bidder.publicKey.toBuffer()],
-            program.programId
-        );
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
 
         const bidAmount = new anchor.BN(100);
         const nonce = randomBytes(32);
         const bidHash = computeBidHash(bidAmount, nonce, bidder.publicKey, auctionPda);
 
         await program.methods
-            .submitSealedBid(bidHash)
+            .submitSealedBid(bidHash, null)
             .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
             .signers([bidder])
             .rpc();
@@ -316,41 +437,37 @@ describe("magic — core auction lifecycle (devnet)", function () {
         );
 
         await program.methods
-            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice)
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, sealedLegacyOptions)
             .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
             .rpc();
 
         await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
 
         // Bidder 1: bids 80
-        const [bid1Pda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("bid"), auctionPda.toBuffer(), bidder1.publicKey.toBuffer()],
-            program.programId
-        );
+        const [bid1Pda] = await findBidPda(program, auctionPda, bidder1.publicKey);
         const amount1 = new anchor.BN(80);
         const nonce1 = randomBytes(32);
         const hash1 = computeBidHash(amount1, nonce1, bidder1.publicKey, auctionPda);
 
         await program.methods
-            .submitSealedBid(hash1)
+            .submitSealedBid(hash1, null)
             .accounts({ auction: auctionPda, bid: bid1Pda, bidder: bidder1.publicKey, systemProgram: SystemProgram.programId } as any)
             .signers([bidder1])
             .rpc();
 
         // Bidder 2: bids 200
-        const [bid2Pda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("bid"), auctionPda.toBuffer(), bidder2.publicKey.toBuffer()],
-            program.programId
-        );
+        const [bid2Pda] = await findBidPda(program, auctionPda, bidder2.publicKey);
         const amount2 = new anchor.BN(200);
         const nonce2 = randomBytes(32);
         const hash2 = computeBidHash(amount2, nonce2, bidder2.publicKey, auctionPda);
 
         await program.methods
-            .submitSealedBid(hash2)
+            .submitSealedBid(hash2, null)
             .accounts({ auction: auctionPda, bid: bid2Pda, bidder: bidder2.publicKey, systemProgram: SystemProgram.programId } as any)
             .signers([bidder2])
             .rpc();
+        await depositEscrow(program, auctionPda, bidder1, amount1);
+        await depositEscrow(program, auctionPda, bidder2, amount2);
 
         await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
 
@@ -398,23 +515,20 @@ describe("magic — core auction lifecycle (devnet)", function () {
         );
 
         await program.methods
-            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice)
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, sealedLegacyOptions)
             .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
             .rpc();
 
         await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
 
-        const [bidPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("bid"), auctionPda.toBuffer(), bidder.publicKey.toBuffer()],
-            program.programId
-        );
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
 
         const amount = new anchor.BN(100); // below reserve
         const nonce = randomBytes(32);
         const hash = computeBidHash(amount, nonce, bidder.publicKey, auctionPda);
 
         await program.methods
-            .submitSealedBid(hash)
+            .submitSealedBid(hash, null)
             .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
             .signers([bidder])
             .rpc();
@@ -439,6 +553,647 @@ describe("magic — core auction lifecycle (devnet)", function () {
         expect(auction.status).to.deep.equal({ finalized: {} });
         console.log("     Reserve enforced — no winner (bid 100 < reserve 500)");
     });
+
+    it("rejects L1 sealed bids for private-mode auctions", async () => {
+        const bidder = Keypair.generate();
+        await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 4);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, { ...sealedLegacyOptions, bidMode: { private: {} } })
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+        const hash = computeBidHash(new anchor.BN(100), randomBytes(32), bidder.publicKey, auctionPda);
+
+        try {
+            await program.methods
+                .submitSealedBid(hash, null)
+                .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+                .signers([bidder])
+                .rpc();
+            expect.fail("Should have thrown BidModeMismatch error");
+        } catch (err: any) {
+            expect(err.error.errorCode.code).to.equal("BidModeMismatch");
+            console.log("     Plaintext-mode bid correctly kept off L1");
+        }
+    });
+
+    it("rejects sealed bids without a range proof when the auction requires one", async () => {
+        const bidder = Keypair.generate();
+        await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 5);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, {
+                ...sealedLegacyOptions,
+                commitmentHash: { poseidon: {} },
+                rangeProof: true,
+            })
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+
+        try {
+            await program.methods
+                .submitSealedBid(Array.from(randomBytes(32)), null)
+                .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+                .signers([bidder])
+                .rpc();
+            expect.fail("Should have thrown RangeProofRequired error");
+        } catch (err: any) {
+            expect(err.error.errorCode.code).to.equal("RangeProofRequired");
+            console.log("     Unproven bid correctly rejected");
+        }
+    });
+
+    it("rejects encrypted auctions without an encryption key", async () => {
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 6);
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        try {
+            await program.methods
+                .createAuction(
+                    auctionId,
+                    new anchor.BN(now + 2),
+                    new anchor.BN(now + 8),
+                    new anchor.BN(now + 14),
+                    new anchor.BN(50),
+                    { ...sealedLegacyOptions, bidMode: { encrypted: {} } }
+                )
+                .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+                .rpc();
+            expect.fail("Should have thrown InvalidEncryptionKey error");
+        } catch (err: any) {
+            expect(err.error.errorCode.code).to.equal("InvalidEncryptionKey");
+            console.log("     Keyless encrypted auction correctly rejected");
+        }
+    });
+
+    it("lets only the pinned reveal agent reveal on the bidder's behalf", async () => {
+        const bidder = Keypair.generate();
+        const agent = Keypair.generate();
+        const stranger = Keypair.generate();
+        await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+        await fundWallet(provider, agent.publicKey, 0.1 * LAMPORTS_PER_SOL);
+        await fundWallet(provider, stranger.publicKey, 0.1 * LAMPORTS_PER_SOL);
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 7);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, sealedLegacyOptions)
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+        const bidAmount = new anchor.BN(100);
+        const nonce = randomBytes(32);
+        const bidHash = computeBidHash(bidAmount, nonce, bidder.publicKey, auctionPda);
+
+        await program.methods
+            .submitSealedBid(bidHash, null)
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+            .signers([bidder])
+            .rpc();
+
+        await program.methods
+            .setRevealAgent(agent.publicKey)
+            .accounts({ bid: bidPda, bidder: bidder.publicKey } as any)
+            .signers([bidder])
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
+
+        try {
+            await program.methods
+                .revealBidFor(bidAmount, Array.from(nonce))
+                .accounts({ auction: auctionPda, bid: bidPda, revealer: stranger.publicKey } as any)
+                .signers([stranger])
+                .rpc();
+            expect.fail("Should have thrown UnauthorizedRevealer error");
+        } catch (err: any) {
+            expect(err.error.errorCode.code).to.equal("UnauthorizedRevealer");
+        }
+
+        await program.methods
+            .revealBidFor(bidAmount, Array.from(nonce))
+            .accounts({ auction: auctionPda, bid: bidPda, revealer: agent.publicKey } as any)
+            .signers([agent])
+            .rpc();
+
+        const bidAccount = await program.account.bid.fetch(bidPda);
+        expect(bidAccount.revealed).to.equal(true);
+        expect(bidAccount.amount.toNumber()).to.equal(100);
+        console.log("     Reveal agent revealed the bid");
+    });
+
+    it("reveals a batch of bids and reports rejected preimages", async () => {
+        const bidder1 = Keypair.generate();
+        const bidder2 = Keypair.generate();
+        await fundWallet(provider, bidder1.publicKey, 0.5 * LAMPORTS_PER_SOL);
+        await fundWallet(provider, bidder2.publicKey, 0.5 * LAMPORTS_PER_SOL);
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 8);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, sealedLegacyOptions)
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const bids = [
+            { bidder: bidder1, amount: new anchor.BN(120), nonce: randomBytes(32) },
+            { bidder: bidder2, amount: new anchor.BN(300), nonce: randomBytes(32) },
+        ];
+        const bidPdas = await Promise.all(
+            bids.map(async ({ bidder }) => (await findBidPda(program, auctionPda, bidder.publicKey))[0])
+        );
+        for (const [i, { bidder, amount, nonce }] of bids.entries()) {
+            await program.methods
+                .submitSealedBid(computeBidHash(amount, nonce, bidder.publicKey, auctionPda), null)
+                .accounts({ auction: auctionPda, bid: bidPdas[i], bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+                .signers([bidder])
+                .rpc();
+            await depositEscrow(program, auctionPda, bidder, amount);
+        }
+
+        await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
+
+        // The second preimage is wrong and must be reported, not fail the batch.
+        await program.methods
+            .revealBidsBatch([
+                { amount: bids[0].amount, nonce: Array.from(bids[0].nonce) },
+                { amount: new anchor.BN(999), nonce: Array.from(bids[1].nonce) },
+            ])
+            .accounts({ auction: auctionPda, revealer: authority } as any)
+            // One [bid, deposit] pair per reveal.
+            .remainingAccounts(
+                bids.flatMap(({ bidder }, i) => [
+                    { pubkey: bidPdas[i], isSigner: false, isWritable: true },
+                    {
+                        pubkey: depositPda(program.programId, auctionPda, bidder.publicKey),
+                        isSigner: false,
+                        isWritable: false,
+                    },
+                ])
+            )
+            .rpc();
+
+        const auction = await program.account.auction.fetch(auctionPda);
+        expect(auction.totalRevealed).to.equal(1);
+        expect(auction.highestBid.toNumber()).to.equal(120);
+        expect((await program.account.bid.fetch(bidPdas[1])).revealed).to.equal(false);
+        console.log("     Batch revealed one bid and rejected the other");
+    });
+
+    it("accepts a relayed bid signed off-chain by the bidder", async () => {
+        // The bidder never pays: no airdrop, the provider wallet relays.
+        const bidder = Keypair.generate();
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 9);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, sealedLegacyOptions)
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+        const bidHash = computeBidHash(new anchor.BN(100), randomBytes(32), bidder.publicKey, auctionPda);
+        const { creationSlot } = await program.account.auction.fetch(auctionPda);
+        const message = Buffer.concat([
+            SIGNED_BID_DOMAIN,
+            program.programId.toBuffer(),
+            auctionPda.toBuffer(),
+            u64Le(creationSlot),
+            Buffer.from(bidHash),
+        ]);
+
+        await program.methods
+            .submitSealedBidSigned(bidHash, null)
+            .accounts({
+                auction: auctionPda,
+                bid: bidPda,
+                bidder: bidder.publicKey,
+                relayer: authority,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+                systemProgram: SystemProgram.programId,
+            } as any)
+            .preInstructions([
+                Ed25519Program.createInstructionWithPrivateKey({ privateKey: bidder.secretKey, message }),
+            ])
+            .rpc();
+
+        const bid = await program.account.bid.fetch(bidPda);
+        expect(bid.bidder.toBase58()).to.equal(bidder.publicKey.toBase58());
+        expect(bid.committed).to.equal(true);
+        console.log("     Relayed bid committed without the bidder paying");
+    });
+
+    it("reveals with a nonce re-derived from the bidder's signature", async () => {
+        const bidder = Keypair.generate();
+        await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 10);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, { ...sealedLegacyOptions, derivedNonce: true })
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+        const bidAmount = new anchor.BN(100);
+        const { nonce } = deriveBidNonce(bidder, auctionPda);
+
+        await program.methods
+            .submitSealedBid(computeBidHash(bidAmount, nonce, bidder.publicKey, auctionPda), null)
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+            .signers([bidder])
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
+
+        // Nothing was stored: the wallet signs again and gets the same nonce.
+        const { ix, nonce: rederived } = deriveBidNonce(bidder, auctionPda);
+        await program.methods
+            .revealBid(bidAmount, Array.from(rederived))
+            .accounts({
+                auction: auctionPda,
+                bid: bidPda,
+                bidder: bidder.publicKey,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            } as any)
+            .preInstructions([ix])
+            .signers([bidder])
+            .rpc();
+
+        expect((await program.account.bid.fetch(bidPda)).revealed).to.equal(true);
+        console.log("     Derived-nonce reveal accepted");
+    });
+
+    it("reveals a batch of derived-nonce bids with one signature per bid", async () => {
+        const bidders = [Keypair.generate(), Keypair.generate()];
+        for (const bidder of bidders) {
+            await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+        }
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 15);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, { ...sealedLegacyOptions, derivedNonce: true })
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const amounts = [new anchor.BN(120), new anchor.BN(300)];
+        const bidPdas = await Promise.all(
+            bidders.map(async (bidder) => (await findBidPda(program, auctionPda, bidder.publicKey))[0])
+        );
+        for (const [i, bidder] of bidders.entries()) {
+            const { nonce } = deriveBidNonce(bidder, auctionPda);
+            await program.methods
+                .submitSealedBid(computeBidHash(amounts[i], nonce, bidder.publicKey, auctionPda), null)
+                .accounts({ auction: auctionPda, bid: bidPdas[i], bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+                .signers([bidder])
+                .rpc();
+            await depositEscrow(program, auctionPda, bidder, amounts[i]);
+        }
+
+        await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
+
+        // The Ed25519 instructions precede the batch in the same order as the reveals.
+        const derived = bidders.map((bidder) => deriveBidNonce(bidder, auctionPda));
+        await program.methods
+            .revealBidsBatch(
+                derived.map(({ nonce }, i) => ({ amount: amounts[i], nonce: Array.from(nonce) }))
+            )
+            .accounts({
+                auction: auctionPda,
+                revealer: authority,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            } as any)
+            .remainingAccounts(
+                bidders.flatMap((bidder, i) => [
+                    { pubkey: bidPdas[i], isSigner: false, isWritable: true },
+                    {
+                        pubkey: depositPda(program.programId, auctionPda, bidder.publicKey),
+                        isSigner: false,
+                        isWritable: false,
+                    },
+                ])
+            )
+            .preInstructions(derived.map(({ ix }) => ix))
+            .rpc();
+
+        const auction = await program.account.auction.fetch(auctionPda);
+        expect(auction.totalRevealed).to.equal(2);
+        expect(auction.highestBid.toNumber()).to.equal(300);
+        console.log("     Derived-nonce batch revealed both bids");
+    });
+
+    it("binds V2 commitments to the auction's creation slot", async () => {
+        const bidder = Keypair.generate();
+        await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 11);
+        const startTs = new anchor.BN(now + 2);
+        const endTs = new anchor.BN(now + 8);
+        const revealEndTs = new anchor.BN(now + 14);
+        const reservePrice = new anchor.BN(50);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, { ...sealedLegacyOptions, commitmentScheme: { v2: {} } })
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+        const { creationSlot } = await program.account.auction.fetch(auctionPda);
+        expect(creationSlot.toNumber()).to.be.greaterThan(0);
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+        const bidAmount = new anchor.BN(100);
+        const nonce = randomBytes(32);
+
+        await program.methods
+            .submitSealedBid(computeBidHashV2(program.programId, bidAmount, nonce, bidder.publicKey, auctionPda, creationSlot), null)
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+            .signers([bidder])
+            .rpc();
+        expect((await program.account.bid.fetch(bidPda)).auctionSlot.toString()).to.equal(creationSlot.toString());
+
+        await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
+
+        await program.methods
+            .revealBid(bidAmount, Array.from(nonce))
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey } as any)
+            .signers([bidder])
+            .rpc();
+
+        expect((await program.account.bid.fetch(bidPda)).revealed).to.equal(true);
+        console.log("     V2 reveal accepted at creation slot", creationSlot.toString());
+    });
+
+    it("reports the status and bid status through view return data", async () => {
+        const bidder = Keypair.generate();
+        await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionId = new anchor.BN(Date.now() + 12);
+        const startTs = new anchor.BN(now + 4);
+        const endTs = new anchor.BN(now + 10);
+        const revealEndTs = new anchor.BN(now + 16);
+
+        const [auctionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+            program.programId
+        );
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+
+        // Views only set return data, so simulating them is enough.
+        const view = async (tx: Transaction) => {
+            tx.feePayer = authority;
+            tx.recentBlockhash = (await provider.connection.getLatestBlockhash()).blockhash;
+            const { value } = await provider.connection.simulateTransaction(tx);
+            expect(value.err).to.equal(null);
+            return Buffer.from(value.returnData!.data[0], "base64");
+        };
+        // AuctionStatus: Scheduled, Bidding, Revealing, Finalized, Settled, Cancelled.
+        const auctionState = async () => {
+            const data = await view(
+                await program.methods.getAuctionState().accounts({ auction: auctionPda } as any).transaction()
+            );
+            return {
+                status: data[0],
+                finalizable: data[1] === 1,
+                secondsRemaining: data[10] === 1 ? Number(data.readBigInt64LE(11)) : null,
+                totalBids: data.readUInt32LE(data[10] === 1 ? 19 : 11),
+            };
+        };
+        const bidStatus = async () => {
+            const data = await view(
+                await program.methods
+                    .getBidStatus(bidder.publicKey)
+                    .accounts({ auction: auctionPda, bid: bidPda } as any)
+                    .transaction()
+            );
+            return { committed: data[1] === 1, revealed: data[2] === 1 };
+        };
+
+        await program.methods
+            .createAuction(auctionId, startTs, endTs, revealEndTs, new anchor.BN(0), sealedLegacyOptions)
+            .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+            .rpc();
+
+        const scheduled = await auctionState();
+        expect(scheduled.status).to.equal(0);
+        expect(scheduled.secondsRemaining).to.be.greaterThan(0);
+        expect(await bidStatus()).to.deep.equal({ committed: false, revealed: false });
+
+        await waitUntilOnchainUnix(provider.connection, startTs.toNumber());
+        const amount = new anchor.BN(75);
+        const nonce = randomBytes(32);
+        await program.methods
+            .submitSealedBid(computeBidHash(amount, nonce, bidder.publicKey, auctionPda), null)
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+            .signers([bidder])
+            .rpc();
+        const bidding = await auctionState();
+        expect(bidding.status).to.equal(1);
+        expect(bidding.totalBids).to.equal(1);
+        expect(await bidStatus()).to.deep.equal({ committed: true, revealed: false });
+
+        await waitUntilOnchainUnix(provider.connection, endTs.toNumber());
+        const revealing = await auctionState();
+        expect(revealing.status).to.equal(2);
+        expect(revealing.finalizable).to.equal(false);
+        await program.methods
+            .revealBid(amount, Array.from(nonce))
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey } as any)
+            .signers([bidder])
+            .rpc();
+        expect(await bidStatus()).to.deep.equal({ committed: true, revealed: true });
+
+        await waitUntilOnchainUnix(provider.connection, revealEndTs.toNumber());
+        const finalizable = await auctionState();
+        expect(finalizable.status).to.equal(2);
+        expect(finalizable.finalizable).to.equal(true);
+        expect(finalizable.secondsRemaining).to.equal(null);
+        console.log("     Views tracked the auction from scheduled to finalizable");
+    });
+
+    it("emits AuctionStatusChanged on every transition and cancels unbid auctions", async () => {
+        const bidder = Keypair.generate();
+        await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
+
+        const parser = new anchor.EventParser(program.programId, program.coder);
+        const statusChanges = async (signature: string) => {
+            const tx = await provider.connection.getTransaction(signature, {
+                commitment: "confirmed",
+                maxSupportedTransactionVersion: 0,
+            });
+            return [...parser.parseLogs(tx!.meta!.logMessages!)]
+                .filter((event) => event.name === "auctionStatusChanged")
+                .map((event) => [Object.keys(event.data.from)[0], Object.keys(event.data.to)[0]]);
+        };
+        const create = async (auctionId: anchor.BN, startTs: number) => {
+            const [auctionPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from("auction"), authority.toBuffer(), u64Le(auctionId)],
+                program.programId
+            );
+            await program.methods
+                .createAuction(
+                    auctionId,
+                    new anchor.BN(startTs),
+                    new anchor.BN(startTs + 6),
+                    new anchor.BN(startTs + 12),
+                    new anchor.BN(0),
+                    sealedLegacyOptions
+                )
+                .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
+                .rpc({ commitment: "confirmed" });
+            return auctionPda;
+        };
+
+        const now = Math.floor(Date.now() / 1000);
+        const auctionPda = await create(new anchor.BN(Date.now() + 13), now + 4);
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
+
+        await waitUntilOnchainUnix(provider.connection, now + 4);
+        const amount = new anchor.BN(40);
+        const nonce = randomBytes(32);
+        const commit = await program.methods
+            .submitSealedBid(computeBidHash(amount, nonce, bidder.publicKey, auctionPda), null)
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey, systemProgram: SystemProgram.programId } as any)
+            .signers([bidder])
+            .rpc({ commitment: "confirmed" });
+        expect(await statusChanges(commit)).to.deep.equal([["scheduled", "bidding"]]);
+
+        try {
+            await program.methods.cancelAuction().accounts({ auction: auctionPda, authority } as any).rpc();
+            expect.fail("Should have thrown AuctionHasBids error");
+        } catch (err: any) {
+            expect(err.error.errorCode.code).to.equal("AuctionHasBids");
+        }
+
+        await waitUntilOnchainUnix(provider.connection, now + 10);
+        const reveal = await program.methods
+            .revealBid(amount, Array.from(nonce))
+            .accounts({ auction: auctionPda, bid: bidPda, bidder: bidder.publicKey } as any)
+            .signers([bidder])
+            .rpc({ commitment: "confirmed" });
+        expect(await statusChanges(reveal)).to.deep.equal([["bidding", "revealing"]]);
+
+        await waitUntilOnchainUnix(provider.connection, now + 16);
+        const finalize = await program.methods
+            .finalizeAuction()
+            .accounts({ auction: auctionPda, authority } as any)
+            .rpc({ commitment: "confirmed" });
+        expect(await statusChanges(finalize)).to.deep.equal([["revealing", "finalized"]]);
+
+        const unbidPda = await create(new anchor.BN(Date.now() + 14), Math.floor(Date.now() / 1000) + 30);
+        const cancel = await program.methods
+            .cancelAuction()
+            .accounts({ auction: unbidPda, authority } as any)
+            .rpc({ commitment: "confirmed" });
+        expect(await statusChanges(cancel)).to.deep.equal([["scheduled", "cancelled"]]);
+        expect((await program.account.auction.fetch(unbidPda)).status).to.deep.equal({ cancelled: {} });
+        console.log("     Status changes were emitted from scheduled to finalized, and on cancel");
+    });
 });
 
 // ===========================================================================
@@ -477,7 +1232,7 @@ describe("magic — private bidding with MagicBlock PER", function () {
         // Step 1: Create auction
         console.log("\n    Step 1: Creating auction on localnet...");
         await program.methods
-            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice)
+            .createAuction(auctionId, startTs, endTs, revealEndTs, reservePrice, sealedLegacyOptions)
             .accounts({ auction: auctionPda, authority, systemProgram: SystemProgram.programId } as any)
             .rpc();
         console.log("     Auction created");
@@ -501,10 +1256,7 @@ describe("magic — private bidding with MagicBlock PER", function () {
         // Step 3: Prepare bidder + bid account on L1 (required before delegated ER writes)
         const bidder = Keypair.generate();
         await fundWallet(provider, bidder.publicKey, 0.5 * LAMPORTS_PER_SOL);
-        const [bidPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("bid"), auctionPda.toBuffer(), bidder.publicKey.toBuffer()],
-            program.programId
-        );
+        const [bidPda] = await findBidPda(program, auctionPda, bidder.publicKey);
         const bidPermissionPda = permissionPdaFromAccount(bidPda);
 
         console.log("\n     Step 3: Initializing bid account on L1...");
@@ -520,6 +1272,8 @@ describe("magic — private bidding with MagicBlock PER", function () {
             .signers([bidder])
             .rpc();
         console.log("     Bid account initialized:", bidPda.toBase58());
+        // The deposit stays on L1; the ER reads it when the bid is revealed.
+        await depositEscrow(program, auctionPda, bidder, new anchor.BN(100));
 
         // Step 4: Create bid permission via on-chain CPI (program must sign for bid PDA)
         console.log("\n     Step 4: Creating bid permission...");
@@ -577,8 +1331,9 @@ describe("magic — private bidding with MagicBlock PER", function () {
 
         // Step 8: Delegate bid PDA to ER
         console.log("\n     Step 8: Delegating bid PDA to ER...");
+        const { creationSlot } = await program.account.auction.fetch(auctionPda);
         await program.methods
-            .delegateBid(auctionPda, bidder.publicKey)
+            .delegateBid(auctionPda, creationSlot, bidder.publicKey)
             .accounts({
                 payer: bidder.publicKey,
                 bid: bidPda,
@@ -601,7 +1356,7 @@ describe("magic — private bidding with MagicBlock PER", function () {
         const bidHash = computeBidHash(bidAmount, nonce, bidder.publicKey, auctionPda);
 
         const delegatedBidIx = await program.methods
-            .submitSealedBidDelegated(bidHash)
+            .submitSealedBidDelegated(bidHash, null, null)
             .accounts({
                 auction: auctionPda,
                 bid: bidPda,
@@ -637,6 +1392,9 @@ describe("magic — private bidding with MagicBlock PER", function () {
                 auction: auctionPda,
                 bid: bidPda,
                 bidder: bidder.publicKey,
+                deposit: depositPda(program.programId, auctionPda, bidder.publicKey),
+                permission: bidPermissionPda,
+                permissionProgram: PERMISSION_PROGRAM_ID,
             } as any)
             .instruction();
         await sendViaErWithSigners({
@@ -683,8 +1441,9 @@ describe("magic — private bidding with MagicBlock PER", function () {
 
         // Step 14: Verify final state visible on base layer
         console.log("\n     Step 14: Verifying settled state on L1...");
-        const l1Auction = await waitForAuctionFinalizedOnL1(program, auctionPda);
-        expect(l1Auction.status).to.deep.equal({ finalized: {} });
+        // The L1 settlement scheduled by finalize_and_settle takes the auction to settled.
+        const l1Auction = await waitForAuctionStatusOnL1(program, auctionPda, "settled");
+        expect(l1Auction.status).to.deep.equal({ settled: {} });
         expect(l1Auction.totalBids).to.equal(1);
         expect(l1Auction.totalRevealed).to.equal(1);
         expect(l1Auction.highestBid.toNumber()).to.equal(100);